@@ -27,6 +27,12 @@ pub async fn initialize_global_config() -> Result<()> {
     Ok(())
 }
 
+// Function to set the config without reading the file, as tests do
+pub async fn set_global_config(config: SettingsConfig) {
+    let mut global_config = GLOBAL_CONFIG.write().await;
+    global_config.config = Some(config);
+}
+
 // Function to get a reference to the config
 pub async fn get_config() -> Result<SettingsConfig> {
     let global_config = GLOBAL_CONFIG.read().await;
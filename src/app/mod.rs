@@ -17,12 +17,14 @@ use crate::copytrade::copytrade;
 use crate::daos_fun::menu::daosfun_menu;
 use crate::env::load_config;
 use crate::env::utils::read_keys;
+use crate::journal::{menu::journal_menu, read_entries};
+use crate::latency::report::print_report;
 use crate::pumpfun::sub_menu::pump_main;
 use crate::raydium_amm::swap::instructions::burn_and_close_tokens;
 use crate::raydium_amm::swap::swap_in::{swap_in, swap_out, PriorityTip};
 use crate::raydium_amm::swap::trades::track_trades;
 use crate::rpc::rpc_key;
-use crate::user_inputs::mode::{automatic_snipe, unwrap_sol_call, wrap_sol_call};
+use crate::user_inputs::mode::{automatic_snipe, multi_route_sniper, unwrap_sol_call, wrap_sol_call};
 use crate::utils::terminal::clear_screen;
 
 use self::wallets::wallet_logger;
@@ -105,12 +107,15 @@ pub async fn main_menu(mainmenu: bool) -> Result<(), Box<dyn std::error::Error +
         // .option(DemandOption::new("RaydiumCPMM").label("▪ Raydium CPMM Mode"))
         .option(DemandOption::new("PumpFun").label("▪ PumpFun Mode"))
         .option(DemandOption::new("DaosFun").label("▪ DaosFun Mode"))
+        .option(DemandOption::new("MultiRoute").label("▪ Multi-Route Sniper"))
         .option(DemandOption::new("CopyTrade").label("▪ CopyTrade Mode"))
         // .option(DemandOption::new("MoonShot").label("▪ MoonShot Mode"))
         .option(DemandOption::new("Burnandclose").label("🔥 Burn and Close Accounts"))
         .option(DemandOption::new("Wrap Sol Mode").label("📦 Wrap SOL"))
         .option(DemandOption::new("Unwrap Sol Mode").label("🪤  Unwrap SOL"))
-        .option(DemandOption::new("Wallet Details").label("🍄 Wallet Details"));
+        .option(DemandOption::new("Wallet Details").label("🍄 Wallet Details"))
+        .option(DemandOption::new("Trade Journal").label("📒 Trade Journal"))
+        .option(DemandOption::new("Latency Report").label("⏱  Latency Report"));
 
     let selected_option = ms.run().expect("error running select");
 
@@ -128,6 +133,12 @@ pub async fn main_menu(mainmenu: bool) -> Result<(), Box<dyn std::error::Error +
             let _ = daosfun_menu().await;
             let _ = read_keys().await;
         }
+        "MultiRoute" => {
+            if let Err(e) = multi_route_sniper().await {
+                error!("{}", e);
+            }
+            let _ = read_keys().await;
+        }
         "CopyTrade" => {
             let _ = copytrade().await;
             let _ = read_keys().await;
@@ -148,6 +159,19 @@ pub async fn main_menu(mainmenu: bool) -> Result<(), Box<dyn std::error::Error +
             let _ = wallet_logger().await;
             let _ = read_keys().await;
         }
+        "Trade Journal" => {
+            if let Err(e) = journal_menu().await {
+                error!("{}", e);
+            }
+            let _ = read_keys().await;
+        }
+        "Latency Report" => {
+            match read_entries() {
+                Ok(entries) => print_report(&entries),
+                Err(e) => error!("{}", e),
+            }
+            let _ = read_keys().await;
+        }
         _ => {
             // Handle unexpected option here
         }
@@ -10,7 +10,12 @@ pub fn private_keys() -> io::Result<Vec<(String, Keypair)>> {
     Ok(private_keys
         .iter()
         .map(|(name, value)| {
-            let key = value.as_str();
+            let key = match resolve_secret(value) {
+                Ok(key) => key,
+                Err(e) => {
+                    panic!("Error: {}", e);
+                }
+            };
 
             let secret_key = match bs58::decode(key).into_vec() {
                 Ok(v) => v,
@@ -34,7 +39,7 @@ pub fn private_keys() -> io::Result<Vec<(String, Keypair)>> {
 use serde_json::Value;
 use std::io;
 
-use crate::env::load_config;
+use crate::{env::load_config, keystore::resolve_secret};
 
 fn private_keys_loader() -> io::Result<Vec<(String, String)>> {
     let mut keys = Vec::new();
@@ -125,7 +130,7 @@ pub fn private_key(wallet: String) -> Keypair {
     parsed
 }
 
-//read the private key from the json file
+//read the private key from the json file, following keystore references
 fn custom_private_key(wallet: String) -> Option<String> {
     let path = format!("./wallets/{}.json", wallet);
 
@@ -140,7 +145,10 @@ fn custom_private_key(wallet: String) -> Option<String> {
     let json: Result<Value, _> = serde_json::from_str(&data);
     if let Ok(json) = json {
         if let Some(key) = json["wallet"].as_str() {
-            return Some(key.to_owned());
+            match resolve_secret(key) {
+                Ok(key) => return Some(key),
+                Err(e) => warn!("Unable to resolve the key in {}: {}", path, e),
+            }
         } else {
             warn!("Invalid wallet key in {}", path);
         }
@@ -0,0 +1,481 @@
+use std::{path::PathBuf, str::FromStr};
+
+use clap::{App, Arg, ArgMatches, Command};
+use log::{error, info};
+use solana_sdk::{native_token::sol_to_lamports, pubkey::Pubkey};
+
+use crate::{
+    app::{config_init::initialize_global_config, wallets::wallet_logger},
+    copytrade::subscription::copytrading_grpc,
+    daos_fun::{
+        daos_transaction::{daos_trade, DAOSTrade},
+        daosfun_snipe::daosfun_sniper_run,
+    },
+    env::{load_config, set_headless, set_profile},
+    journal::{print_entries, JournalQuery},
+    latency::report::{print_report, write_csv},
+    keystore::{migrate::migrate_plaintext_keys, with_keystore, KEYSTORE_PATH},
+    pumpfun::{
+        pump_interface::builder::{PumpAmount, PUMP_TOKEN_DECIMALS},
+        sniper::pumpfun_sniper_run,
+        swap::{pump_buy, pump_sell},
+    },
+    raydium_amm::swap::{
+        instructions::unwrap_sol,
+        swap_in::{raydium_buy, raydium_sell},
+    },
+    router::{multi_sniper_run, replay::replay, SniperRoute},
+    rpc::rpc_key,
+    user_inputs::mode::{raydium_sniper_run, wrap_sol_run},
+};
+
+const VENUES: [&str; 3] = ["raydium", "pump", "daos"];
+
+pub fn build_cli() -> App<'static> {
+    App::new("Mevarik")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Solana sniper. Runs the interactive menu when no subcommand is given.")
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .takes_value(true)
+                .global(true)
+                .help("Overlay config.<PROFILE>.toml on top of config.toml"),
+        )
+        .subcommand(
+            Command::new("snipe")
+                .about("Snipe a launch or migration")
+                .arg(
+                    Arg::new("route")
+                        .required(true)
+                        .possible_values(["pumpfun", "migration", "raydium", "daosfun", "all"])
+                        .help("Route to snipe on, all runs every route enabled under [sniper]"),
+                )
+                .arg(
+                    Arg::new("auto")
+                        .long("auto")
+                        .conflicts_with("mint")
+                        .help("Snipe the next launch seen on the route"),
+                )
+                .arg(
+                    Arg::new("mint")
+                        .long("mint")
+                        .takes_value(true)
+                        .required_unless_present("auto")
+                        .help("Token mint to snipe"),
+                )
+                .arg(sol_arg(false)),
+        )
+        .subcommand(
+            Command::new("buy")
+                .about("Buy a token")
+                .arg(venue_arg())
+                .arg(pool_arg())
+                .arg(mint_arg())
+                .arg(sol_arg(true).required(false).required_unless_present("tokens"))
+                .arg(tokens_arg("sol", "Exact number of tokens to buy, pump only")),
+        )
+        .subcommand(
+            Command::new("sell")
+                .about("Sell a percentage of a token balance, or an exact amount on pump")
+                .arg(venue_arg())
+                .arg(pool_arg())
+                .arg(mint_arg())
+                .arg(
+                    Arg::new("percent")
+                        .long("percent")
+                        .takes_value(true)
+                        .required_unless_present("tokens")
+                        .help("Percentage of the balance to sell (1-100)"),
+                )
+                .arg(tokens_arg("percent", "Exact number of tokens to sell, pump only")),
+        )
+        .subcommand(
+            Command::new("copytrade").about("Copy trades of wallets").arg(
+                Arg::new("account")
+                    .long("account")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .help("Wallet to follow, defaults to trading.copytrade_accounts"),
+            ),
+        )
+        .subcommand(
+            Command::new("wrap")
+                .about("Wrap SOL into WSOL")
+                .arg(sol_arg(true)),
+        )
+        .subcommand(
+            Command::new("unwrap").about("Unwrap all WSOL").arg(
+                Arg::new("deployer")
+                    .long("deployer")
+                    .help("Unwrap the bundler deployer and buyer wallets instead of the folder wallets"),
+            ),
+        )
+        .subcommand(Command::new("wallets").about("Print wallet balances"))
+        .subcommand(
+            Command::new("replay")
+                .about("Run a recorded Geyser stream through the parsers without sending")
+                .arg(
+                    Arg::new("file")
+                        .required(true)
+                        .help("Recording written to network.record_path"),
+                )
+                .arg(
+                    Arg::new("speed")
+                        .long("speed")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Times the recorded pace, 0 replays as fast as possible"),
+                ),
+        )
+        .subcommand(
+            Command::new("journal")
+                .about("Show recorded trades, oldest first")
+                .arg(
+                    Arg::new("mint")
+                        .long("mint")
+                        .takes_value(true)
+                        .help("Only trades of this mint"),
+                )
+                .arg(
+                    Arg::new("wallet")
+                        .long("wallet")
+                        .takes_value(true)
+                        .help("Only trades sent from this wallet"),
+                )
+                .arg(
+                    Arg::new("route")
+                        .long("route")
+                        .takes_value(true)
+                        .help("Only trades on this route, e.g. pumpfun or raydiumamm"),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .takes_value(true)
+                        .help("Only the newest N matching trades"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print matching entries as JSON lines"),
+                ),
+        )
+        .subcommand(
+            Command::new("latency")
+                .about("Show how long each stage of timed trades took, per route")
+                .arg(
+                    Arg::new("route")
+                        .long("route")
+                        .takes_value(true)
+                        .help("Only trades on this route, e.g. pumpfun or raydiumamm"),
+                )
+                .arg(
+                    Arg::new("csv")
+                        .long("csv")
+                        .takes_value(true)
+                        .help("Also write every timed trade to this CSV file"),
+                ),
+        )
+        .subcommand(
+            Command::new("keystore")
+                .about("Manage the encrypted keystore")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("migrate")
+                        .about("Import plaintext keys from config.toml, bundler_settings.json and wallet folders")
+                        .arg(
+                            Arg::new("wallets")
+                                .long("wallets")
+                                .takes_value(true)
+                                .multiple_occurrences(true)
+                                .help("Wallet folder to import, defaults to every folder in the working directory"),
+                        ),
+                )
+                .subcommand(Command::new("list").about("List stored labels and public keys")),
+        )
+}
+
+fn venue_arg() -> Arg<'static> {
+    Arg::new("venue")
+        .required(true)
+        .possible_values(VENUES)
+        .help("Venue to trade on")
+}
+
+fn pool_arg() -> Arg<'static> {
+    Arg::new("pool")
+        .long("pool")
+        .takes_value(true)
+        .required_if_eq("venue", "raydium")
+        .help("Raydium AMM pool id")
+}
+
+fn mint_arg() -> Arg<'static> {
+    Arg::new("mint")
+        .long("mint")
+        .takes_value(true)
+        .required_if_eq_any(&[("venue", "pump"), ("venue", "daos")])
+        .help("Token mint")
+}
+
+fn sol_arg(required: bool) -> Arg<'static> {
+    Arg::new("sol")
+        .long("sol")
+        .takes_value(true)
+        .required(required)
+        .help(if required {
+            "Amount in SOL"
+        } else {
+            "Amount in SOL, defaults to trading.buy_amount"
+        })
+}
+
+fn tokens_arg(instead_of: &'static str, help: &'static str) -> Arg<'static> {
+    Arg::new("tokens")
+        .long("tokens")
+        .takes_value(true)
+        .conflicts_with(instead_of)
+        .help(help)
+}
+
+fn pubkey_of(matches: &ArgMatches, name: &str) -> eyre::Result<Pubkey> {
+    let value = matches
+        .value_of(name)
+        .ok_or_else(|| eyre::eyre!("--{} is required", name))?;
+    Pubkey::from_str(value).map_err(|e| eyre::eyre!("Invalid --{} {}: {}", name, value, e))
+}
+
+fn lamports_of(matches: &ArgMatches, name: &str, default: f64) -> eyre::Result<u64> {
+    let sol = match matches.value_of(name) {
+        Some(value) => value
+            .parse::<f64>()
+            .map_err(|e| eyre::eyre!("Invalid --{} {}: {}", name, value, e))?,
+        None => default,
+    };
+    if sol <= 0.0 {
+        return Err(eyre::eyre!("--{} must be greater than 0", name));
+    }
+    Ok(sol_to_lamports(sol))
+}
+
+fn percent_of(matches: &ArgMatches) -> eyre::Result<u64> {
+    let value = matches.value_of("percent").unwrap_or_default();
+    let percent = value
+        .parse::<u64>()
+        .map_err(|e| eyre::eyre!("Invalid --percent {}: {}", value, e))?;
+    if percent == 0 || percent > 100 {
+        return Err(eyre::eyre!("--percent must be between 1 and 100"));
+    }
+    Ok(percent)
+}
+
+/// `--tokens` in whole tokens, as a raw Pump.fun amount.
+fn tokens_of(matches: &ArgMatches) -> eyre::Result<Option<PumpAmount>> {
+    let Some(value) = matches.value_of("tokens") else {
+        return Ok(None);
+    };
+    let tokens = value
+        .parse::<f64>()
+        .map_err(|e| eyre::eyre!("Invalid --tokens {}: {}", value, e))?;
+    if !tokens.is_finite() || tokens <= 0.0 {
+        return Err(eyre::eyre!("--tokens must be greater than 0"));
+    }
+    if matches.value_of("venue") != Some("pump") {
+        return Err(eyre::eyre!("--tokens is only supported on the pump venue"));
+    }
+    Ok(Some(PumpAmount::Tokens(spl_token::ui_amount_to_amount(
+        tokens,
+        PUMP_TOKEN_DECIMALS,
+    ))))
+}
+
+/// Runs a single subcommand without any interactive prompt. Returns `Ok(false)`
+/// when no subcommand was given so the caller can fall back to the menu.
+pub async fn run_cli(matches: &ArgMatches) -> eyre::Result<bool> {
+    set_profile(matches.value_of("profile").map(|p| p.to_string()));
+
+    let (name, sub) = match matches.subcommand() {
+        Some(subcommand) => subcommand,
+        None => return Ok(false),
+    };
+
+    // The keystore has to work before the config can be loaded, since the
+    // config may reference keys that are not migrated yet. It is also the one
+    // command allowed to prompt, for the passphrase.
+    if name == "keystore" {
+        run_keystore(sub)?;
+        return Ok(true);
+    }
+    // Reading the journal needs neither config nor keys.
+    if name == "journal" {
+        run_journal(sub)?;
+        return Ok(true);
+    }
+    if name == "latency" {
+        run_latency(sub)?;
+        return Ok(true);
+    }
+
+    set_headless(true);
+
+    initialize_global_config().await?;
+    let args = load_config().await?;
+    let _http_loader = rpc_key(args.network.rpc_url.clone()).await;
+
+    match name {
+        "snipe" => {
+            let token = if sub.is_present("auto") {
+                None
+            } else {
+                Some(pubkey_of(sub, "mint")?)
+            };
+            let sol_amount = lamports_of(sub, "sol", args.trading.buy_amount)?;
+
+            match sub.value_of("route").unwrap_or_default() {
+                "pumpfun" => {
+                    pumpfun_sniper_run(args, token, sol_amount, SniperRoute::PumpFun).await?
+                }
+                "migration" => {
+                    pumpfun_sniper_run(args, token, sol_amount, SniperRoute::PumpFunMigration)
+                        .await?
+                }
+                "daosfun" => {
+                    daosfun_sniper_run(args, token, sol_amount, SniperRoute::DaosFun).await?
+                }
+                "all" => multi_sniper_run(args, token).await?,
+                _ => raydium_sniper_run(args, token, sol_amount).await?,
+            }
+        }
+        "buy" => {
+            if let Some(tokens) = tokens_of(sub)? {
+                pump_buy(pubkey_of(sub, "mint")?, tokens).await?;
+                return Ok(true);
+            }
+            let sol_amount = lamports_of(sub, "sol", args.trading.buy_amount)?;
+            match sub.value_of("venue").unwrap_or_default() {
+                "raydium" => raydium_buy(pubkey_of(sub, "pool")?, sol_amount)
+                    .await
+                    .map_err(|e| eyre::eyre!("{}", e))?,
+                "pump" => pump_buy(pubkey_of(sub, "mint")?, PumpAmount::Sol(sol_amount)).await?,
+                _ => daos_trade(pubkey_of(sub, "mint")?, sol_amount, DAOSTrade::Buy).await?,
+            }
+        }
+        "sell" => {
+            if let Some(tokens) = tokens_of(sub)? {
+                pump_sell(pubkey_of(sub, "mint")?, tokens).await?;
+                return Ok(true);
+            }
+            let percent = percent_of(sub)?;
+            match sub.value_of("venue").unwrap_or_default() {
+                "raydium" => raydium_sell(pubkey_of(sub, "pool")?, percent)
+                    .await
+                    .map_err(|e| eyre::eyre!("{}", e))?,
+                "pump" => {
+                    pump_sell(pubkey_of(sub, "mint")?, PumpAmount::Percentage(percent as f64))
+                        .await?
+                }
+                _ => daos_trade(pubkey_of(sub, "mint")?, percent, DAOSTrade::Sell).await?,
+            }
+        }
+        "copytrade" => {
+            let accounts: Vec<String> = match sub.values_of("account") {
+                Some(values) => values.map(|v| v.to_string()).collect(),
+                None => args.trading.copytrade_accounts.clone(),
+            };
+            if accounts.is_empty() {
+                return Err(eyre::eyre!(
+                    "No accounts to copy, pass --account or set trading.copytrade_accounts"
+                ));
+            }
+            info!("Listening for {} account(s)...", accounts.len());
+            copytrading_grpc(args, accounts)
+                .await
+                .map_err(|e| eyre::eyre!("{}", e))?;
+        }
+        "wrap" => {
+            let sol_amount = lamports_of(sub, "sol", 0.0)?;
+            wrap_sol_run(sol_amount).await?;
+        }
+        "unwrap" => unwrap_sol(sub.is_present("deployer"))
+            .await
+            .map_err(|e| eyre::eyre!("{}", e))?,
+        "wallets" => wallet_logger().await?,
+        "replay" => {
+            let value = sub.value_of("speed").unwrap_or_default();
+            let speed = value
+                .parse::<f64>()
+                .ok()
+                .filter(|speed| *speed >= 0.0)
+                .ok_or_else(|| eyre::eyre!("Invalid --speed {}", value))?;
+            let file = PathBuf::from(sub.value_of("file").unwrap_or_default());
+            replay(args, &file, speed).await?;
+        }
+        other => error!("Unknown command: {}", other),
+    }
+
+    Ok(true)
+}
+
+fn run_journal(matches: &ArgMatches) -> eyre::Result<()> {
+    let limit = match matches.value_of("limit") {
+        Some(value) => Some(
+            value
+                .parse::<usize>()
+                .map_err(|e| eyre::eyre!("Invalid --limit {}: {}", value, e))?,
+        ),
+        None => None,
+    };
+    let query = JournalQuery {
+        mint: matches.value_of("mint").map(|m| m.to_string()),
+        wallet: matches.value_of("wallet").map(|w| w.to_string()),
+        route: matches.value_of("route").map(|r| r.to_string()),
+        limit,
+    };
+
+    let entries = query.run()?;
+    if matches.is_present("json") {
+        for entry in &entries {
+            println!("{}", serde_json::to_string(entry)?);
+        }
+    } else {
+        print_entries(&entries);
+    }
+    Ok(())
+}
+
+fn run_latency(matches: &ArgMatches) -> eyre::Result<()> {
+    let query = JournalQuery {
+        route: matches.value_of("route").map(|r| r.to_string()),
+        ..Default::default()
+    };
+    let entries = query.run()?;
+    print_report(&entries);
+
+    if let Some(path) = matches.value_of("csv") {
+        let rows = write_csv(&entries, &PathBuf::from(path))?;
+        info!("Wrote {} timed trade(s) to {}", rows, path);
+    }
+    Ok(())
+}
+
+fn run_keystore(matches: &ArgMatches) -> eyre::Result<()> {
+    match matches.subcommand() {
+        Some(("migrate", sub)) => {
+            let dirs: Vec<PathBuf> = sub
+                .values_of("wallets")
+                .map(|values| values.map(PathBuf::from).collect())
+                .unwrap_or_default();
+            let imported = migrate_plaintext_keys(&dirs)?;
+            info!("Imported {} key(s) into {}", imported, KEYSTORE_PATH);
+        }
+        Some(("list", _)) => with_keystore(|keystore| {
+            for (label, pubkey) in keystore.labels() {
+                info!("{:<24} {}", label, pubkey);
+            }
+            Ok(())
+        })?,
+        _ => {}
+    }
+    Ok(())
+}
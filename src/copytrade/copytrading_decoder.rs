@@ -4,41 +4,35 @@ use crate::{
 use {
     crate::{
         app::config_init::get_config,
+        geyser::{ParsedInstruction, ParsedTransaction},
         instruction::instruction::{AmmInstruction, RaydiumAmmAccounts, RAYDIUM_AMM_ACCOUNTS_LEN},
+        journal::{Order, TradeSide},
         jupiter::interface::{RouteIxData, RouteKeys, ROUTE_IX_ACCOUNTS_LEN},
+        positions::{self, venue::Venue},
         pumpfun::{
-            executor::pump_tracker,
             pump_interface::{
-                accounts::BondingCurve,
-                builder::{calculate_buy_price, get_bonding_curve},
-                instructions::{
-                    buy_ix_with_program_id, BuyIxArgs, BuyIxData, BuyKeys, BUY_IX_ACCOUNTS_LEN,
-                },
+                builder::{fetch_curve_and_global, get_bonding_curve, pump_buy_args},
+                instructions::{buy_ix_with_program_id, BuyIxData, BuyKeys, BUY_IX_ACCOUNTS_LEN},
                 PUMPFUN_PROGRAM,
             },
         },
         raydium_amm::pool_searcher::amm_keys::pool_keys_fetcher,
         router::SniperRoute,
-        utils::transaction::send_transaction,
+        sender::TransactionSender,
     },
-    borsh::BorshDeserialize,
     chrono::{offset::LocalResult, TimeZone, Utc},
     log::{info, warn},
     solana_client::nonblocking::rpc_client::RpcClient,
     solana_program::pubkey::Pubkey,
     solana_sdk::{
         native_token::sol_to_lamports, program_pack::Pack, signature::Keypair, signer::Signer,
-        transaction::VersionedTransaction,
     },
     spl_associated_token_account::{
         get_associated_token_address, instruction::create_associated_token_account_idempotent,
     },
     spl_token::state::Mint,
     std::{str::FromStr, sync::Arc},
-    yellowstone_grpc_proto::{
-        geyser::{CommitmentLevel, SubscribeUpdateTransaction},
-        solana::storage::confirmed_block::CompiledInstruction,
-    },
+    yellowstone_grpc_proto::geyser::CommitmentLevel,
 };
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct Args {
@@ -93,49 +87,18 @@ pub const RAYDIUM_AMM_V4_PROGRAM_ID: Pubkey =
 pub const RAYDIUM_AMM_FEE_COLLECTOR: &str = "7YttLkHDoNj9wyDur5pM1ejNaAvT9X4eqaYcHQqtj2G5";
 
 pub async fn copy_trade_sub(
-    tx: SubscribeUpdateTransaction,
+    tx: &ParsedTransaction,
     rpc_client: Arc<RpcClient>,
 ) -> eyre::Result<()> {
-    // println!("tx: {:?}", tx);
-    let info = tx.clone().transaction.unwrap_or_default();
-    let accounts = info
-        .transaction
-        .clone()
-        .unwrap_or_default()
-        .message
-        .unwrap_or_default()
-        .account_keys
-        .iter()
-        .map(|i| {
-            let mut array = [0; 32];
-            let bytes = &i[..array.len()];
-            array.copy_from_slice(bytes);
-            Pubkey::new_from_array(array)
-        })
-        .collect::<Vec<Pubkey>>();
-    let outer_instructions = {
-        let transaction = info.transaction.unwrap_or_default();
-        let message = transaction.message.unwrap_or_default();
-        let instructions = message.instructions.iter();
-        instructions.cloned().collect::<Vec<_>>()
-    };
-
-    let meta = info.meta.unwrap_or_default();
-
-    let inner_instructions: Vec<CompiledInstruction> = meta
-        .clone()
-        .inner_instructions
+    let accounts = tx.account_keys();
+    let outer_instructions = &tx.instructions;
+
+    let inner_instructions: Vec<&ParsedInstruction> = outer_instructions
         .iter()
-        .flat_map(|inner| {
-            inner.instructions.iter().map(|instr| CompiledInstruction {
-                program_id_index: instr.program_id_index,
-                accounts: instr.accounts.clone(),
-                data: instr.data.clone(),
-            })
-        })
+        .flat_map(|instruction| &instruction.inner)
         .collect();
 
-    let signature_base58 = bs58::encode(&info.signature).into_string();
+    let signature_base58 = tx.signature.to_string();
 
     let mut coin_args: Option<BuyIxData> = None;
     let mut coin_args_amm: Option<AmmInstruction> = None;
@@ -154,18 +117,18 @@ pub async fn copy_trade_sub(
 
                 if instructions.accounts.len() >= BUY_IX_ACCOUNTS_LEN {
                     buy_keys = Some(BuyKeys {
-                        global: accounts[instructions.accounts[0] as usize],
-                        fee_recipient: accounts[instructions.accounts[1] as usize],
-                        mint: accounts[instructions.accounts[2] as usize],
-                        bonding_curve: accounts[instructions.accounts[3] as usize],
-                        associated_bonding_curve: accounts[instructions.accounts[4] as usize],
-                        associated_user: accounts[instructions.accounts[5] as usize],
-                        user: accounts[instructions.accounts[6] as usize],
-                        system_program: accounts[instructions.accounts[7] as usize],
-                        token_program: accounts[instructions.accounts[8] as usize],
-                        rent: accounts[instructions.accounts[9] as usize],
-                        event_authority: accounts[instructions.accounts[10] as usize],
-                        program: accounts[instructions.accounts[11] as usize],
+                        global: instructions.accounts[0],
+                        fee_recipient: instructions.accounts[1],
+                        mint: instructions.accounts[2],
+                        bonding_curve: instructions.accounts[3],
+                        associated_bonding_curve: instructions.accounts[4],
+                        associated_user: instructions.accounts[5],
+                        user: instructions.accounts[6],
+                        system_program: instructions.accounts[7],
+                        token_program: instructions.accounts[8],
+                        rent: instructions.accounts[9],
+                        event_authority: instructions.accounts[10],
+                        program: instructions.accounts[11],
                     });
                 }
                 break;
@@ -177,26 +140,26 @@ pub async fn copy_trade_sub(
 
                     if instructions.accounts.len() >= RAYDIUM_AMM_ACCOUNTS_LEN {
                         raydium_accounts = Some(RaydiumAmmAccounts {
-                            spl_token: accounts[instructions.accounts[0] as usize],
-                            amm_pool: accounts[instructions.accounts[1] as usize],
-                            amm_authority: accounts[instructions.accounts[2] as usize],
-                            amm_open_orders: accounts[instructions.accounts[3] as usize],
-                            amm_target_orders: accounts[instructions.accounts[4] as usize],
-                            amm_coin_vault: accounts[instructions.accounts[5] as usize],
-                            amm_pc_vault: accounts[instructions.accounts[6] as usize],
+                            spl_token: instructions.accounts[0],
+                            amm_pool: instructions.accounts[1],
+                            amm_authority: instructions.accounts[2],
+                            amm_open_orders: instructions.accounts[3],
+                            amm_target_orders: instructions.accounts[4],
+                            amm_coin_vault: instructions.accounts[5],
+                            amm_pc_vault: instructions.accounts[6],
                             market_program: Pubkey::from_str(
                                 "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX",
                             )?,
-                            market: accounts[instructions.accounts[8] as usize],
-                            market_bids: accounts[instructions.accounts[9] as usize],
-                            market_asks: accounts[instructions.accounts[10] as usize],
-                            market_event_queue: accounts[instructions.accounts[11] as usize],
-                            market_coin_vault: accounts[instructions.accounts[12] as usize],
-                            market_pc_vault: accounts[instructions.accounts[13] as usize],
-                            market_vault_signer: accounts[instructions.accounts[14] as usize],
-                            source_token_account: accounts[instructions.accounts[15] as usize],
-                            destination_token_account: accounts[instructions.accounts[16] as usize],
-                            user_source_owner: accounts[instructions.accounts[17] as usize],
+                            market: instructions.accounts[8],
+                            market_bids: instructions.accounts[9],
+                            market_asks: instructions.accounts[10],
+                            market_event_queue: instructions.accounts[11],
+                            market_coin_vault: instructions.accounts[12],
+                            market_pc_vault: instructions.accounts[13],
+                            market_vault_signer: instructions.accounts[14],
+                            source_token_account: instructions.accounts[15],
+                            destination_token_account: instructions.accounts[16],
+                            user_source_owner: instructions.accounts[17],
                         });
                     }
                     break;
@@ -210,20 +173,15 @@ pub async fn copy_trade_sub(
 
                             if instructions.accounts.len() >= ROUTE_IX_ACCOUNTS_LEN {
                                 jup_route_keys = Some(RouteKeys {
-                                    token_program: accounts[instructions.accounts[0] as usize],
-                                    user_transfer_authority: accounts
-                                        [instructions.accounts[1] as usize],
-                                    user_source_token_account: accounts
-                                        [instructions.accounts[2] as usize],
-                                    user_destination_token_account: accounts
-                                        [instructions.accounts[3] as usize],
-                                    destination_token_account: accounts
-                                        [instructions.accounts[4] as usize],
-                                    destination_mint: accounts[instructions.accounts[5] as usize],
-                                    platform_fee_account: accounts
-                                        [instructions.accounts[6] as usize],
-                                    event_authority: accounts[instructions.accounts[7] as usize],
-                                    program: accounts[instructions.accounts[8] as usize],
+                                    token_program: instructions.accounts[0],
+                                    user_transfer_authority: instructions.accounts[1],
+                                    user_source_token_account: instructions.accounts[2],
+                                    user_destination_token_account: instructions.accounts[3],
+                                    destination_token_account: instructions.accounts[4],
+                                    destination_mint: instructions.accounts[5],
+                                    platform_fee_account: instructions.accounts[6],
+                                    event_authority: instructions.accounts[7],
+                                    program: instructions.accounts[8],
                                 })
                             }
                             break;
@@ -252,7 +210,7 @@ pub async fn copy_trade_sub(
 
         let decoded_transfers: Vec<Option<(Pubkey, Pubkey, u64)>> = inner_instructions
             .iter()
-            .map(|ix| decode_transfer(ix, &accounts))
+            .map(|ix| decode_transfer(ix))
             .collect();
 
         let user_spl_mint = get_associated_token_address(&accounts[0], &pool_keys.base_mint);
@@ -316,30 +274,16 @@ pub async fn copy_trade_sub(
                 keys.associated_bonding_curve = bonding_curve_ata;
             }
 
-            let account_data = rpc_client.get_account_data(&bonding_curve_pda).await?;
-
-            let sliced_data: &mut &[u8] = &mut account_data.as_slice();
-
-            let reserves = BondingCurve::deserialize_reader(sliced_data)?;
-
-            let reserves = (
-                reserves.real_token_reserves as u128,
-                reserves.virtual_sol_reserves as u128,
-                reserves.real_sol_reserves as u128,
-            );
-
             println!("keys: {buy_keys:#?}");
 
-            let price: (u128, (u128, u128, u128)) = calculate_buy_price(
-                sol_to_lamports(settings_config.trading.buy_amount) as u128,
-                reserves,
-            );
-            let fee_bps = sol_to_lamports(settings_config.trading.buy_amount) * 1 / 100;
-
-            let args = BuyIxArgs {
-                amount: price.0 as u64,
-                max_sol_cost: sol_to_lamports(settings_config.trading.buy_amount) + fee_bps,
-            };
+            let (bonding_curve, global) =
+                fetch_curve_and_global(&rpc_client, &buy_keys.unwrap().mint).await?;
+            let args = pump_buy_args(
+                &bonding_curve,
+                &global,
+                sol_to_lamports(settings_config.trading.buy_amount),
+                settings_config.slippage_bps(&SniperRoute::PumpFun),
+            )?;
 
             let create_account = create_associated_token_account_idempotent(
                 &wallet.pubkey(),
@@ -350,44 +294,26 @@ pub async fn copy_trade_sub(
 
             let buy_ix = buy_ix_with_program_id(PUMPFUN_PROGRAM, buy_keys.unwrap(), args)?;
 
-            let config = solana_sdk::commitment_config::CommitmentLevel::Finalized;
-            let (latest_blockhash, _) = rpc_client
-                .get_latest_blockhash_with_commitment(
-                    solana_sdk::commitment_config::CommitmentConfig { commitment: config },
-                )
+            let report = TransactionSender::from_config(&settings_config, rpc_client.clone())
+                .with_order(Order::new(
+                    SniperRoute::PumpFun,
+                    TradeSide::Buy,
+                    buy_keys.unwrap().mint,
+                    sol_to_lamports(settings_config.trading.buy_amount),
+                ))
+                .send(&wallet, &[create_account, buy_ix], &[])
                 .await?;
+            report.log();
+            if !report.landed() {
+                return Ok(());
+            }
 
-            let message = match solana_program::message::v0::Message::try_compile(
-                &wallet.pubkey(),
-                &[create_account, buy_ix],
-                &[],
-                latest_blockhash,
-            ) {
-                Ok(x) => x,
-                Err(e) => {
-                    println!("Error: {:?}", e);
-                    return Ok(());
-                }
-            };
-
-            let transaction = match VersionedTransaction::try_new(
-                solana_program::message::VersionedMessage::V0(message),
-                &[&wallet],
-            ) {
-                Ok(x) => x,
-                Err(e) => {
-                    println!("Error: {:?}", e);
-                    return Ok(());
-                }
-            };
-
-            send_transaction(settings_config.clone(), transaction).await?;
-
-            let _ = pump_tracker(
-                sol_to_lamports(settings_config.trading.buy_amount),
+            positions::open(
+                Venue::PumpFun,
                 buy_keys.unwrap().mint,
-            )
-            .await?;
+                sol_to_lamports(settings_config.trading.buy_amount),
+                &report,
+            );
         }
     } else if trade_route
         .as_ref()
@@ -406,14 +332,11 @@ pub async fn copy_trade_sub(
     Ok(())
 }
 
-fn decode_transfer(
-    instruction: &CompiledInstruction,
-    accounts: &[Pubkey],
-) -> Option<(Pubkey, Pubkey, u64)> {
+fn decode_transfer(instruction: &ParsedInstruction) -> Option<(Pubkey, Pubkey, u64)> {
     if let Ok(lamports) = bincode::deserialize::<u64>(&instruction.data) {
         if instruction.accounts.len() >= 2 {
-            let from = accounts[instruction.accounts[0] as usize];
-            let to = accounts[instruction.accounts[1] as usize];
+            let from = instruction.accounts[0];
+            let to = instruction.accounts[1];
             return Some((from, to, lamports));
         }
     }
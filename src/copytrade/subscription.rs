@@ -1,111 +1,76 @@
-use std::{
-    collections::HashMap,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
-    thread,
-    time::Duration,
-};
+use std::{collections::HashMap, sync::Arc, time::Instant};
 
-use colorize::AnsiColor;
-use futures::{SinkExt, StreamExt};
-use log::{error, info};
+use log::{error, warn};
 use maplit::hashmap;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use yellowstone_grpc_proto::geyser::{
-    subscribe_update::UpdateOneof, SubscribeRequest, SubscribeRequestFilterBlocksMeta,
-    SubscribeRequestFilterTransactions,
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterTransactions,
 };
 
 use crate::{
-    copytrade::copytrading_decoder::copy_trade_sub, env::SettingsConfig,
-    plugins::yellowstone_plugin::lib::GeyserGrpcClient,
-    raydium_amm::swap::raydium_amm_sniper::clear_previous_line,
+    copytrade::copytrading_decoder::copy_trade_sub,
+    env::SettingsConfig,
+    geyser::{GeyserSubscription, ParsedTransaction, Recorder},
+    latency::{self, Stage, Timeline},
 };
 
-pub async fn copytrading_grpc(args: SettingsConfig, address: Vec<String>) -> anyhow::Result<()> {
-    // Shared atomic boolean flag to stop the animation
-    let stop_animation = Arc::new(AtomicBool::new(false));
-    let stop_animation_clone = Arc::clone(&stop_animation);
-
-    // Start the dot animation in a separate thread
-    let handle = thread::spawn(move || {
-        let mut dots = String::new();
-        let mut count = 0;
-        while !stop_animation_clone.load(Ordering::Relaxed) {
-            if count == 3 {
-                dots.clear();
-                count = 0;
-            }
-            dots.push('.');
-            count += 1;
-            clear_previous_line();
-            info!("Connecting to the Port{}", dots.clone().red());
-            thread::sleep(Duration::from_millis(500));
-        }
-    });
-
-    let endpoint = args.network.grpc_url.clone();
-
-    let x_token = Some("00000000-0000-0000-0000-000000000000");
+/// Name of the copied wallets' transaction filter, which recordings keep.
+pub const COPYTRADE_FILTER: &str = "copytrade";
 
-    let mut client = GeyserGrpcClient::connect(endpoint, x_token, None)?;
-    let (mut subscribe_tx, mut stream) = client.subscribe().await?;
-
-    let rpc_client = Arc::new(RpcClient::new(args.network.rpc_url));
-    clear_previous_line();
-    info!("Successfully connected to Geyser");
-
-    // Stop the animation
-    stop_animation.store(true, Ordering::Relaxed);
+pub async fn copytrading_grpc(args: SettingsConfig, address: Vec<String>) -> anyhow::Result<()> {
+    let rpc_client = Arc::new(RpcClient::new(args.network.rpc_url.clone()));
 
-    // Wait for the animation thread to finish
-    handle.join().unwrap();
+    let request = SubscribeRequest {
+        slots: HashMap::new(),
+        accounts: HashMap::new(),
+        transactions: hashmap! { COPYTRADE_FILTER.to_owned() => SubscribeRequestFilterTransactions {
+            vote: Default::default(),
+            failed: Some(false),
+            signature: Default::default(),
+            account_include: address,
+            account_exclude: Default::default(),
+            account_required: Default::default(),
+        } },
+        entry: HashMap::new(),
+        blocks: HashMap::new(),
+        blocks_meta: hashmap! { "".to_owned() => SubscribeRequestFilterBlocksMeta {} },
+        commitment: Some(CommitmentLevel::Processed as i32),
+        accounts_data_slice: vec![],
+        ping: None,
+    };
 
-    let commitment = 0;
-    subscribe_tx
-        .send(SubscribeRequest {
-            slots: HashMap::new(),
-            accounts: HashMap::new(),
-            transactions: hashmap! { "".to_owned() => SubscribeRequestFilterTransactions {
-                vote: Default::default(),
-                failed: Some(false),
-                signature: Default::default(),
-                account_include: address.into(),
-                account_exclude: Default::default(),
-                account_required: Default::default(),
-            } },
-            entry: HashMap::new(),
-            blocks: HashMap::new(),
-            blocks_meta: hashmap! { "".to_owned() => SubscribeRequestFilterBlocksMeta {} },
-            commitment: Some(commitment as i32),
-            accounts_data_slice: vec![],
-            ping: None,
-        })
-        .await?;
+    let mut stream = GeyserSubscription::from_config(&args, request).start()?;
+    let mut recorder = Recorder::from_config(&args)
+        .map_err(|e| anyhow::anyhow!("{}", e))?
+        .map(Recorder::spawn);
 
     while let Some(message) = stream.next().await {
+        if let Some(sink) = &recorder {
+            if !sink.record(Instant::now(), message.clone()) {
+                recorder = None;
+            }
+        }
+        latency::observe(&message);
+        let Some(UpdateOneof::Transaction(tx)) = message.update_oneof else {
+            continue;
+        };
+        let mut timeline = Timeline::start(tx.slot);
+        let tx = match ParsedTransaction::try_from(tx) {
+            Ok(tx) => tx,
+            Err(e) => {
+                warn!("Skipping a transaction that does not decode: {}", e);
+                continue;
+            }
+        };
+        timeline.mark(Stage::Parsed);
+
         let rpc_client = rpc_client.clone();
-        tokio::spawn(async move {
-            match message {
-                Ok(msg) => match msg.update_oneof {
-                    Some(UpdateOneof::Transaction(tx)) => {
-                        let _ = match copy_trade_sub(tx, rpc_client).await {
-                            Ok(_) => {}
-                            Err(e) => {
-                                error!("Error: {:?}", e);
-                            }
-                        };
-                    }
-                    _ => {}
-                },
-                Err(error) => {
-                    error!("stream error: {error:?}");
-                }
+        tokio::spawn(timeline.scope(async move {
+            if let Err(e) = copy_trade_sub(&tx, rpc_client).await {
+                error!("Error: {:?}", e);
             }
-            Ok::<(), ()>(())
-        });
+        }));
     }
 
     Ok(())
@@ -1,38 +1,18 @@
 use std::{str::FromStr, sync::Arc};
 
-use jito_protos::searcher::SubscribeBundleResultsRequest;
-use jito_searcher_client::{get_searcher_client, send_bundle_with_confirmation};
-use log::{error, info};
-use solana_client::{
-    nonblocking::rpc_client::{self, RpcClient},
-    rpc_config::RpcSendTransactionConfig,
-};
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
-    commitment_config::{CommitmentConfig, CommitmentLevel},
-    native_token::sol_to_lamports,
-    pubkey::{self, Pubkey},
-    signature::Keypair,
-    signer::Signer,
-    system_instruction::transfer,
-    system_program,
-    transaction::VersionedTransaction,
-};
-use spl_associated_token_account::{
-    get_associated_token_address, get_associated_token_address_with_program_id,
+    pubkey::Pubkey, signature::Keypair, signer::Signer, system_program,
 };
+use spl_associated_token_account::get_associated_token_address_with_program_id;
 
 use crate::{
-    env::load_config,
-    instruction::instruction::compute_ixs,
-    liquidity::utils::tip_account,
-    raydium_amm::swap::{instructions::SOLC_MINT, swapper::auth_keypair},
+    env::load_config, raydium_amm::swap::instructions::SOLC_MINT, sender::TransactionSender,
 };
 
 use super::{
-    dao_burned_interface::{
-        initialize_ix_with_program_id, InitializeIxArgs, InitializeIxData, InitializeKeys,
-    },
-    daos_transaction::{DAOSTrade, DAOS_BURNED_PROGRAM, DAOS_PROGRAM},
+    dao_burned_interface::{initialize_ix_with_program_id, InitializeIxArgs, InitializeKeys},
+    daos_transaction::{DAOS_BURNED_PROGRAM, DAOS_PROGRAM},
     fee_share_interface::FEE_SHARED,
     inx_builder::FUND_RAISE_PROGRAM,
 };
@@ -135,149 +115,14 @@ pub async fn create_daos_fund() -> eyre::Result<()> {
     };
 
     let initialize = initialize_ix_with_program_id(DAOS_BURNED_PROGRAM, keys, token_args)?;
-    let compute = compute_ixs(sol_to_lamports(args.trading.priority_fee), 200000)?;
-
-    let mut instructions = vec![];
-    instructions.extend(compute);
-    instructions.push(initialize_initi_wallet);
-    instructions.push(initialize);
+    let instructions = vec![initialize_initi_wallet, initialize];
 
-    let rpc_client = RpcClient::new(args.network.rpc_url);
+    let rpc_client = Arc::new(RpcClient::new(args.network.rpc_url.clone()));
 
-    let config = CommitmentLevel::Processed;
-    let (latest_blockhash, _) = rpc_client
-        .get_latest_blockhash_with_commitment(solana_sdk::commitment_config::CommitmentConfig {
-            commitment: config,
-        })
+    let report = TransactionSender::from_config(&args, rpc_client)
+        .send(&wallet, &instructions, &[&token_mint])
         .await?;
+    report.log();
 
-    let message = match solana_program::message::v0::Message::try_compile(
-        &wallet.pubkey(),
-        &instructions,
-        &[],
-        latest_blockhash,
-    ) {
-        Ok(x) => x,
-        Err(e) => {
-            println!("Error: {:?}", e);
-            return Ok(());
-        }
-    };
-
-    let transaction = match VersionedTransaction::try_new(
-        solana_program::message::VersionedMessage::V0(message),
-        &[&wallet, &token_mint],
-    ) {
-        Ok(x) => x,
-        Err(e) => {
-            println!("Error: {:?}", e);
-            return Ok(());
-        }
-    };
-
-    if args.engine.use_bundles {
-        let mut searcher_client =
-            get_searcher_client(&args.network.block_engine_url, &Arc::new(auth_keypair())).await?;
-
-        let tip_ix = transfer(
-            &wallet.pubkey(),
-            &tip_account(),
-            sol_to_lamports(args.trading.bundle_tip),
-        );
-        instructions.push(tip_ix);
-
-        let message = match solana_program::message::v0::Message::try_compile(
-            &wallet.pubkey(),
-            &instructions,
-            &[],
-            latest_blockhash,
-        ) {
-            Ok(x) => x,
-            Err(e) => {
-                println!("Error: {:?}", e);
-                return Ok(());
-            }
-        };
-        let transaction = match VersionedTransaction::try_new(
-            solana_program::message::VersionedMessage::V0(message),
-            &[&wallet, &token_mint],
-        ) {
-            Ok(x) => x,
-            Err(e) => {
-                println!("Error: {:?}", e);
-                return Ok(());
-            }
-        };
-
-        let bundle_txn = vec![transaction];
-
-        let mut bundle_results_subscription = searcher_client
-            .subscribe_bundle_results(SubscribeBundleResultsRequest {})
-            .await
-            .expect("subscribe to bundle results")
-            .into_inner();
-
-        match send_bundle_with_confirmation(
-            &bundle_txn,
-            &Arc::new(rpc_client),
-            &mut searcher_client,
-            &mut bundle_results_subscription,
-        )
-        .await
-        {
-            Ok(_) => {}
-            Err(e) => {
-                error!("Error: {}", e);
-            }
-        };
-
-        std::mem::drop(bundle_results_subscription);
-    } else {
-        info!("Sending Transaction");
-        let config = RpcSendTransactionConfig {
-            skip_preflight: true,
-            ..Default::default()
-        };
-
-        if args.trading.spam {
-            let mut counter = 0;
-            while counter < args.trading.spam_count {
-                let result = match rpc_client
-                    .send_transaction_with_config(&transaction, config)
-                    .await
-                {
-                    Ok(x) => x,
-                    Err(e) => {
-                        error!("Error: {:?}", e);
-                        return Ok(());
-                    }
-                };
-
-                info!("Transaction Sent {:?}", result);
-                counter += 1;
-            }
-        } else {
-            let result = match rpc_client
-                .send_transaction_with_config(&transaction, config)
-                .await
-            {
-                Ok(x) => x,
-                Err(e) => {
-                    error!("Error: {:?}", e);
-                    return Ok(());
-                }
-            };
-
-            rpc_client
-                .confirm_transaction_with_spinner(
-                    &result,
-                    &latest_blockhash,
-                    CommitmentConfig::confirmed(),
-                )
-                .await?;
-
-            info!("Transaction Sent {:?}", result);
-        }
-    }
     Ok(())
 }
@@ -5,12 +5,11 @@ use crossterm::style::Stylize;
 use log::{error, info};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
-    native_token::{lamports_to_sol, sol_to_lamports},
+    native_token::lamports_to_sol,
     pubkey::Pubkey,
     signature::Keypair,
     system_program,
 };
-use yellowstone_grpc_proto::geyser::SubscribeUpdateTransaction;
 
 use crate::{
     app::config_init::{get_config, update_config_field},
@@ -22,15 +21,16 @@ use crate::{
         daos_transaction::daosfun_sender,
     },
     env::{load_config, SettingsConfig},
+    geyser::ParsedTransaction,
+    positions::{self, venue::Venue},
     pumpfun::{
         executor::pump_swap,
         pump_interface::{builder::PumpFunDirection, instructions::CreateIxData},
     },
     raydium_amm::{
-        subscribe::auto_sniper_stream,
         swap::{metadata::decode_metadata, raydium_amm_sniper::RAYDIUM_AMM_FEE_COLLECTOR},
     },
-    router::{grpc_pair_sub, SniperRoute},
+    router::{pair_sub, SniperRoute},
     user_inputs::{amounts::sol_amount, tokens::token_env},
 };
 
@@ -45,12 +45,8 @@ pub async fn daosfun_sniper(manual_snipe: bool, route: SniperRoute) -> eyre::Res
         }
     };
 
-    if args.network.grpc_url.is_empty() {
-        let _ = auto_sniper_stream(manual_snipe).await?;
-        return Ok(());
-    }
     let sol_amount = sol_amount("Snipe Amount:").await;
-
+    // Only an amount typed at the prompt is kept for next time.
     update_config_field(|c| &mut c.trading.buy_amount, lamports_to_sol(sol_amount)).await?;
 
     let token;
@@ -83,13 +79,25 @@ pub async fn daosfun_sniper(manual_snipe: bool, route: SniperRoute) -> eyre::Res
         token = None;
     }
 
+    daosfun_sniper_run(args, token, sol_amount, route).await
+}
+
+pub async fn daosfun_sniper_run(
+    mut args: SettingsConfig,
+    token: Option<Pubkey>,
+    sol_amount: u64,
+    route: SniperRoute,
+) -> eyre::Result<()> {
+    let manual_snipe = token.is_some();
+    args.trading.buy_amount = lamports_to_sol(sol_amount);
+
     let contract = if route == SniperRoute::DaosFun {
         DAOS_BURNED_PROGRAM
     } else {
         DAOS_BURNED_PROGRAM
     };
 
-    let _ = match grpc_pair_sub(args, manual_snipe, token, contract.to_string(), route).await {
+    let _ = match pair_sub(args, manual_snipe, token, contract.to_string(), route).await {
         Ok(_) => info!("Transaction Sent"),
         Err(e) => error!("{}", e),
     };
@@ -99,34 +107,15 @@ pub async fn daosfun_sniper(manual_snipe: bool, route: SniperRoute) -> eyre::Res
 
 pub async fn daosfun_parser(
     args: SettingsConfig,
-    tx: SubscribeUpdateTransaction,
+    tx: &ParsedTransaction,
     base_mint: Option<Pubkey>,
+    amount: u64,
 ) -> eyre::Result<()> {
     let config = get_config().await?;
     let wallet = Keypair::from_base58_string(&config.engine.payer_keypair);
 
-    let info = tx.clone().transaction.unwrap_or_default();
-    let accounts = info
-        .transaction
-        .clone()
-        .unwrap_or_default()
-        .message
-        .unwrap_or_default()
-        .account_keys
-        .iter()
-        .map(|i| {
-            let mut array = [0; 32];
-            let bytes = &i[..array.len()]; // panics if not enough data
-            array.copy_from_slice(bytes);
-            Pubkey::new_from_array(array)
-        })
-        .collect::<Vec<Pubkey>>();
-    let outer_instructions = {
-        let transaction = info.transaction.clone().unwrap_or_default();
-        let message = transaction.message.unwrap_or_default();
-        let instructions = message.instructions.iter();
-        instructions.cloned().collect::<Vec<_>>()
-    };
+    let accounts = tx.account_keys();
+    let outer_instructions = &tx.instructions;
 
     let mut coin_found = false;
 
@@ -150,19 +139,19 @@ pub async fn daosfun_parser(
         for (index, instructions) in outer_instructions.iter().enumerate() {
             if instructions.accounts.len() >= INIT_CURVE_IX_ACCOUNTS_LEN {
                 daosfun_accounts = Some(InitCurveKeys {
-                    payer: accounts[instructions.accounts[0] as usize],
-                    dao_mint: accounts[instructions.accounts[1] as usize],
-                    funding_mint: accounts[instructions.accounts[2] as usize],
-                    state: accounts[instructions.accounts[3] as usize],
-                    fundraise_state: accounts[instructions.accounts[4] as usize],
-                    dao_mint_vault: accounts[instructions.accounts[5] as usize],
-                    curve: accounts[instructions.accounts[6] as usize],
-                    curve_dao_mint_ata: accounts[instructions.accounts[7] as usize],
-                    fee_authority: accounts[instructions.accounts[8] as usize],
+                    payer: instructions.accounts[0],
+                    dao_mint: instructions.accounts[1],
+                    funding_mint: instructions.accounts[2],
+                    state: instructions.accounts[3],
+                    fundraise_state: instructions.accounts[4],
+                    dao_mint_vault: instructions.accounts[5],
+                    curve: instructions.accounts[6],
+                    curve_dao_mint_ata: instructions.accounts[7],
+                    fee_authority: instructions.accounts[8],
                     system_program: system_program::id(),
                     token_program: spl_token::id(),
                     associated_token_program: spl_associated_token_account::id(),
-                    virtual_xyk_program: accounts[instructions.accounts[9] as usize],
+                    virtual_xyk_program: instructions.accounts[9],
                 });
             }
             break;
@@ -173,7 +162,7 @@ pub async fn daosfun_parser(
         return Ok(());
     }
 
-    let signature = bs58::encode(&info.signature).into_string();
+    let signature = tx.signature;
 
     if base_mint.is_some() {
         if accounts[1] != base_mint.unwrap() {
@@ -195,16 +184,13 @@ pub async fn daosfun_parser(
         daosfun_accounts.unwrap().dao_mint
     );
 
-    let config = get_config().await?;
-
-    let amount = sol_to_lamports(config.trading.buy_amount);
     let rpc_client = RpcClient::new(args.clone().network.rpc_url);
 
     let token = rpc_client.get_account(&dao_mint).await?;
     // let mint = Mint::unpack(&token).unwrap();
     let dao_mint_program = token.owner;
 
-    match daosfun_sender(
+    let report = match daosfun_sender(
         rpc_client,
         args,
         wallet,
@@ -222,5 +208,12 @@ pub async fn daosfun_parser(
         }
     };
 
+    if report.landed() {
+        let venue = Venue::DaosFun {
+            mint_program: dao_mint_program,
+        };
+        positions::open(venue, dao_mint, amount, &report);
+    }
+
     Ok(())
 }
@@ -8,8 +8,10 @@ use spl_associated_token_account::{
 };
 use spl_token::instruction::sync_native;
 
-use super::virtual_xyk_interface::{buy_token_ix_with_program_id, BuyTokenIxArgs, BuyTokenKeys};
-use crate::app::config_init::get_config;
+use super::virtual_xyk_interface::{
+    accounts::{Curve, CurveAccount},
+    buy_token_ix_with_program_id, BuyTokenIxArgs, BuyTokenKeys,
+};
 use crate::daos_fun::virtual_xyk_interface::{
     sell_token_ix_with_program_id, SellTokenIxArgs, SellTokenKeys,
 };
@@ -20,6 +22,36 @@ pub const DAOS_PROGRAM: Pubkey = pubkey!("5jnapfrAN47UYkLkEf7HnprPPBCQLvkYWGZDeK
 pub const DAOS_BURNED_PROGRAM: Pubkey = pubkey!("4FqThZWv3QKWkSyXCDmATpWkpEiCHq5yhkdGWpSEDAZM");
 pub const FUND_RAISE_PROGRAM: Pubkey = pubkey!("ETK5PUmiqVDRsd1TPFqCu84bsrLNG4YySZND96PEjW97");
 
+/// The depositor and curve accounts of `token_mint`.
+pub fn curve_pda(program_id: &Pubkey, token_mint: &Pubkey) -> (Pubkey, Pubkey) {
+    let depositor = Pubkey::find_program_address(
+        &[b"state".as_ref(), token_mint.as_ref()],
+        &DAOS_BURNED_PROGRAM,
+    )
+    .0;
+    let (curve_pda, _) = Pubkey::find_program_address(&[b"curve", depositor.as_ref()], program_id);
+    (depositor, curve_pda)
+}
+
+/// Reads the curve of `token_mint` as it is now.
+pub async fn fetch_curve(rpc_client: &RpcClient, token_mint: &Pubkey) -> eyre::Result<Curve> {
+    let (_, curve_pda) = curve_pda(&DAOS_PROGRAM, token_mint);
+    let data = rpc_client.get_account_data(&curve_pda).await?;
+    Ok(CurveAccount::deserialize(&data)?.0)
+}
+
+/// Raw tokens `funding_amount` lamports buy on `curve`.
+pub fn buy_quote(curve: &Curve, funding_amount: u64) -> u64 {
+    let funding = curve.funding_amount as u128 + curve.virtual_funding_amount as u128;
+    (curve.token_amount as u128 * funding_amount as u128 / (funding + funding_amount as u128)) as u64
+}
+
+/// Lamports `tokens` sell for on `curve`.
+pub fn sell_quote(curve: &Curve, tokens: u64) -> u64 {
+    let funding = curve.funding_amount as u128 + curve.virtual_funding_amount as u128;
+    (funding * tokens as u128 / (curve.token_amount as u128 + tokens as u128)) as u64
+}
+
 pub fn create_buy_instruction(
     program_id: &Pubkey,
     signer: &Pubkey,
@@ -28,12 +60,7 @@ pub fn create_buy_instruction(
     amount: u64,
     min_token_amount: u64,
 ) -> Result<Vec<Instruction>, ProgramError> {
-    let depositor = Pubkey::find_program_address(
-        &[b"state".as_ref(), token_mint.as_ref()],
-        &DAOS_BURNED_PROGRAM,
-    )
-    .0;
-    let (curve_pda, _) = Pubkey::find_program_address(&[b"curve", depositor.as_ref()], program_id);
+    let (depositor, curve_pda) = curve_pda(program_id, token_mint);
     // These match the JS y() function which creates ATAs
     let signer_token_ata =
         get_associated_token_address_with_program_id(signer, token_mint, token_mint_program);
@@ -81,22 +108,16 @@ pub fn create_buy_instruction(
     Ok(inxs)
 }
 
-pub async fn create_sell_instruction(
+/// Sells exactly `amount` raw tokens.
+pub fn create_sell_instruction(
     program_id: &Pubkey,
     signer: &Pubkey,
     token_mint: &Pubkey,
     token_mint_program: &Pubkey,
     amount: u64,
-    min_token_amount: u64,
+    min_funding_amount: u64,
 ) -> Result<Instruction, ProgramError> {
-    let config = get_config().await.unwrap();
-    let rpc_client = RpcClient::new(config.network.rpc_url);
-    let depositor = Pubkey::find_program_address(
-        &[b"state".as_ref(), token_mint.as_ref()],
-        &DAOS_BURNED_PROGRAM,
-    )
-    .0;
-    let (curve_pda, _) = Pubkey::find_program_address(&[b"curve", depositor.as_ref()], program_id);
+    let (depositor, curve_pda) = curve_pda(program_id, token_mint);
     // These match the JS y() function which creates ATAs
     let signer_token_ata =
         get_associated_token_address_with_program_id(signer, token_mint, token_mint_program);
@@ -105,13 +126,6 @@ pub async fn create_sell_instruction(
         get_associated_token_address_with_program_id(&curve_pda, token_mint, token_mint_program);
     let funding_vault = get_associated_token_address(&curve_pda, &SOLC_MINT);
 
-    let tokens_amount = rpc_client
-        .get_token_account_balance(&signer_token_ata)
-        .await
-        .unwrap();
-
-    let amount = tokens_amount.amount.parse::<u64>().unwrap() * amount / 100;
-
     let accounts = SellTokenKeys {
         signer: *signer,
         depositor: depositor,
@@ -131,7 +145,7 @@ pub async fn create_sell_instruction(
 
     let args = SellTokenIxArgs {
         amount,
-        min_funding_amount: min_token_amount,
+        min_funding_amount,
     };
 
     let inx = sell_token_ix_with_program_id(DAOS_PROGRAM, accounts, args)?;
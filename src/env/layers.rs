@@ -0,0 +1,220 @@
+use std::{env, fs, path::PathBuf};
+
+use toml::Value;
+
+pub const ENV_PREFIX: &str = "MEVARIK";
+
+/// Path of the overlay file for a named profile, e.g. `config.fast-ny.toml`.
+pub fn profile_path(profile: &str) -> PathBuf {
+    PathBuf::from(format!("config.{}.toml", profile))
+}
+
+/// Reads and parses a profile overlay. Profiles only need the keys they change.
+pub fn read_profile(profile: &str) -> eyre::Result<Value> {
+    let path = profile_path(profile);
+    let content = fs::read_to_string(&path)
+        .map_err(|e| eyre::eyre!("Profile `{}` not readable at {}: {}", profile, path.display(), e))?;
+    toml::from_str(&content)
+        .map_err(|e| eyre::eyre!("Profile `{}` ({}) is not valid TOML: {}", profile, path.display(), e))
+}
+
+/// Recursively merges `overlay` into `base`. Tables are merged key by key, any
+/// other value in the overlay replaces the one in the base.
+pub fn merge_values(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Table(base), Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Name of the variable overriding the key at `path`, e.g.
+/// `MEVARIK_TRADING_BUY_AMOUNT` for `trading.buy_amount`.
+pub fn env_var_name<S: AsRef<str>>(path: &[S]) -> String {
+    let path: Vec<&str> = path.iter().map(|key| key.as_ref()).collect();
+    format!("{}_{}", ENV_PREFIX, path.join("_").to_uppercase())
+}
+
+/// Applies `MEVARIK_<PATH>` overrides for every key present in `schema`,
+/// nested tables included, e.g. `MEVARIK_SNIPER_PUMP_FUN_ENABLED`. The
+/// schema's value types decide how each variable is parsed. Arrays are given
+/// as comma separated lists, or as a TOML array such as
+/// `[{ gain_percentage = 100.0, sell_percentage = 50.0 }]`, which arrays of
+/// tables require. Tables themselves cannot be set, only their keys.
+pub fn apply_env_overrides(config: &mut Value, schema: &Value) -> eyre::Result<()> {
+    apply_overrides_with(config, schema, |name| env::var(name).ok())
+}
+
+pub fn apply_overrides_with(
+    config: &mut Value,
+    schema: &Value,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> eyre::Result<()> {
+    apply_table(config, schema, &mut vec![], &lookup)
+}
+
+fn apply_table(
+    config: &mut Value,
+    schema: &Value,
+    path: &mut Vec<String>,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> eyre::Result<()> {
+    let fields = match schema.as_table() {
+        Some(fields) => fields,
+        None => return Ok(()),
+    };
+
+    for (key, kind) in fields {
+        path.push(key.clone());
+        let name = env_var_name(path);
+        let table = config
+            .as_table_mut()
+            .ok_or_else(|| eyre::eyre!("`{}` is not a table", path[..path.len() - 1].join(".")))?;
+
+        if kind.is_table() {
+            // Sections are never values, only the tables inside them are.
+            if path.len() > 1 && lookup(&name).is_some() {
+                return Err(eyre::eyre!(
+                    "{} cannot be set, `{}` is a table: set its keys one by one or use a profile",
+                    name,
+                    path.join(".")
+                ));
+            }
+            let nested = table
+                .entry(key.clone())
+                .or_insert_with(|| Value::Table(Default::default()));
+            apply_table(nested, kind, path, lookup)?;
+        } else if let Some(raw) = lookup(&name) {
+            let value =
+                parse_as(kind, &raw).map_err(|e| eyre::eyre!("{}={:?} is invalid: {}", name, raw, e))?;
+            table.insert(key.clone(), value);
+        }
+        path.pop();
+    }
+
+    Ok(())
+}
+
+fn parse_as(kind: &Value, raw: &str) -> eyre::Result<Value> {
+    let raw = raw.trim();
+    Ok(match kind {
+        Value::Boolean(_) => Value::Boolean(raw.parse()?),
+        Value::Integer(_) => Value::Integer(raw.parse()?),
+        Value::Float(_) => Value::Float(raw.parse()?),
+        Value::Array(_) if raw.starts_with('[') => {
+            let mut parsed: toml::Table = toml::from_str(&format!("value = {}", raw))?;
+            parsed.remove("value").unwrap_or(Value::Array(vec![]))
+        }
+        Value::Array(items) => {
+            let item_kind = match items.first() {
+                Some(Value::Table(_)) => {
+                    return Err(eyre::eyre!(
+                        "expected a TOML array of tables, e.g. [{{ key = 1 }}]"
+                    ))
+                }
+                Some(item) => item.clone(),
+                None => Value::String(String::new()),
+            };
+            Value::Array(
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| parse_as(&item_kind, item))
+                    .collect::<eyre::Result<_>>()?,
+            )
+        }
+        Value::Table(_) => return Err(eyre::eyre!("tables cannot be set from a variable")),
+        _ => Value::String(raw.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_overlay_keeps_untouched_keys() {
+        let mut base: Value =
+            toml::from_str("[network]\nrpc_url = \"https://a\"\ngrpc_url = \"\"\n").unwrap();
+        let overlay: Value = toml::from_str("[network]\ngrpc_url = \"http://b\"\n").unwrap();
+        merge_values(&mut base, overlay);
+
+        assert_eq!(base["network"]["rpc_url"].as_str(), Some("https://a"));
+        assert_eq!(base["network"]["grpc_url"].as_str(), Some("http://b"));
+    }
+
+    #[test]
+    fn env_overrides_follow_schema_types() {
+        let schema: Value = toml::from_str(
+            "[trading]\nbuy_amount = 0.0\nspam = false\nspam_count = 0\ncopytrade_accounts = []\n",
+        )
+        .unwrap();
+        let mut config = schema.clone();
+        apply_overrides_with(&mut config, &schema, |name| match name {
+            "MEVARIK_TRADING_BUY_AMOUNT" => Some("0.5".to_string()),
+            "MEVARIK_TRADING_SPAM" => Some("true".to_string()),
+            "MEVARIK_TRADING_COPYTRADE_ACCOUNTS" => Some("a, b".to_string()),
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!(config["trading"]["buy_amount"].as_float(), Some(0.5));
+        assert_eq!(config["trading"]["spam"].as_bool(), Some(true));
+        assert_eq!(config["trading"]["spam_count"].as_integer(), Some(0));
+        assert_eq!(config["trading"]["copytrade_accounts"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn bad_env_override_names_the_variable() {
+        let schema: Value = toml::from_str("[trading]\nspam_count = 0\n").unwrap();
+        let mut config = schema.clone();
+        let err = apply_overrides_with(&mut config, &schema, |_| Some("lots".to_string()))
+            .unwrap_err();
+        assert!(err.to_string().contains("MEVARIK_TRADING_SPAM_COUNT"));
+    }
+
+    #[test]
+    fn env_overrides_reach_nested_keys() {
+        let schema: Value = toml::from_str(
+            "[trading]\nroute_slippage_bps = {}\ntake_profit = [{ gain_percentage = 0.0, sell_percentage = 0.0 }]\n\
+             [trading.curve_exit]\nfollow_migration = false\n[sniper.pump_fun]\nenabled = false\n",
+        )
+        .unwrap();
+        let mut config: Value = toml::from_str("[trading]\ntake_profit = []\n").unwrap();
+        apply_overrides_with(&mut config, &schema, |name| match name {
+            "MEVARIK_TRADING_CURVE_EXIT_FOLLOW_MIGRATION" => Some("true".to_string()),
+            "MEVARIK_SNIPER_PUMP_FUN_ENABLED" => Some("true".to_string()),
+            "MEVARIK_TRADING_TAKE_PROFIT" => {
+                Some("[{ gain_percentage = 100.0, sell_percentage = 50.0 }]".to_string())
+            }
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!(config["trading"]["curve_exit"]["follow_migration"].as_bool(), Some(true));
+        assert_eq!(config["sniper"]["pump_fun"]["enabled"].as_bool(), Some(true));
+        assert_eq!(
+            config["trading"]["take_profit"][0]["sell_percentage"].as_float(),
+            Some(50.0)
+        );
+
+        for (name, raw) in [
+            ("MEVARIK_TRADING_ROUTE_SLIPPAGE_BPS", "PumpFun=100"),
+            ("MEVARIK_TRADING_TAKE_PROFIT", "100,50"),
+        ] {
+            let err = apply_overrides_with(&mut config, &schema, |var| {
+                (var == name).then(|| raw.to_string())
+            })
+            .unwrap_err();
+            assert!(err.to_string().contains(name));
+        }
+    }
+}
@@ -10,7 +10,10 @@ use serde::{Deserialize, Serialize};
 use solana_program::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
 
-use crate::app::private_key_env;
+use crate::{
+    app::private_key_env,
+    keystore::{resolve_secret, store_key},
+};
 
 #[derive(Debug, Clone)]
 pub struct BackrunAccount {
@@ -66,8 +69,10 @@ struct HelperSettings {
     volume_lut_key: String,
 }
 
+pub const BUNDLER_SETTINGS_PATH: &str = "bundler_settings.json";
+
 pub async fn load_minter_settings() -> eyre::Result<PoolDataSettings> {
-    let args = match fs::read_to_string("bundler_settings.json") {
+    let args = match fs::read_to_string(BUNDLER_SETTINGS_PATH) {
         Ok(args) => args,
         Err(_) => {
             info!("Settings file not found, creating a new one");
@@ -82,7 +87,7 @@ pub async fn load_minter_settings() -> eyre::Result<PoolDataSettings> {
                 volume_lut_key: "".to_string(),
             };
             let default_settings_json = serde_json::to_string(&default_settings).unwrap();
-            let mut file = File::create("bundler_settings.json").unwrap();
+            let mut file = File::create(BUNDLER_SETTINGS_PATH).unwrap();
             file.write_all(default_settings_json.as_bytes()).unwrap();
 
             "".to_string() // Return an empty string if the file does not exist
@@ -97,24 +102,27 @@ pub async fn load_minter_settings() -> eyre::Result<PoolDataSettings> {
         }
     };
 
-    // If any field is empty, ask the user to fill it
+    // If any field is empty, ask the user to fill it and keep it in the keystore
     if helper_settings.deployer_key.is_empty() {
-        helper_settings.deployer_key = private_key_env("Deployer Private Key").await.unwrap();
+        let deployer_key = private_key_env("Deployer Private Key").await.unwrap();
+        helper_settings.deployer_key =
+            store_key("deployer", &Keypair::from_base58_string(&deployer_key))?;
     }
     if helper_settings.buyer_key.is_empty() {
-        helper_settings.buyer_key = private_key_env("Buyer Private Key").await.unwrap();
+        let buyer_key = private_key_env("Buyer Private Key").await.unwrap();
+        helper_settings.buyer_key = store_key("buyer", &Keypair::from_base58_string(&buyer_key))?;
     }
 
     // Save the updated settings to the file
     let default_settings_json = serde_json::to_string_pretty(&helper_settings).unwrap();
-    let mut file = File::create("bundler_settings.json").unwrap();
+    let mut file = File::create(BUNDLER_SETTINGS_PATH).unwrap();
     file.write_all(default_settings_json.as_bytes()).unwrap();
 
     Ok(PoolDataSettings {
         market_id: helper_settings.market_id,
         token_mint: helper_settings.token_mint,
-        deployer_key: helper_settings.deployer_key,
-        buyer_key: helper_settings.buyer_key,
+        deployer_key: resolve_secret(&helper_settings.deployer_key)?,
+        buyer_key: resolve_secret(&helper_settings.buyer_key)?,
         pool_id: helper_settings.pool_id,
         lut_key: helper_settings.lut_key,
         volume_lut_key: helper_settings.volume_lut_key,
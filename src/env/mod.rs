@@ -1,19 +1,40 @@
 use std::{
+    collections::BTreeMap,
     error::Error,
+    fmt,
     fs::{self, File},
-    io::Write,
+    io::{ErrorKind, Write},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
 };
 
 use demand::Input;
 use log::info;
-use serde::{Deserialize, Serialize};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use solana_sdk::signature::Keypair;
 use toml;
 
-use crate::app::private_key_env;
+use crate::{
+    app::private_key_env,
+    keystore::{resolve_secret, store_key},
+    router::SniperRoute,
+};
+
+use self::{
+    layers::{apply_env_overrides, merge_values, read_profile},
+    validate::validate_config,
+};
 
 pub mod input;
+pub mod layers;
 pub mod minter;
 pub mod utils;
+pub mod validate;
 pub mod vanity;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -22,6 +43,8 @@ pub struct SettingsConfig {
     pub network: NetworkSettings,
     pub engine: EngineSettings,
     pub trading: TradingSettings,
+    #[serde(default)]
+    pub sniper: SniperSettings,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -36,7 +59,17 @@ pub struct NetworkSettings {
     pub pubsub_url: String,
     pub rpc_url: String,
     pub grpc_url: String,
+    /// Sent as `x-token` with every Geyser request, empty sends none.
+    #[serde(default)]
+    pub grpc_x_token: String,
     pub regions: Vec<String>,
+    /// Extra RPC endpoints every transaction is fanned out to.
+    #[serde(default)]
+    pub send_rpc_urls: Vec<String>,
+    /// File every Geyser update of the sniper and copytrade streams is written
+    /// to for `replay`, empty records nothing.
+    #[serde(default)]
+    pub record_path: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -49,75 +82,307 @@ pub struct EngineSettings {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TradingSettings {
     pub buy_amount: f64,
+    /// Minimum priority fee in SOL per transaction, spread over its compute units.
     pub priority_fee: f64,
+    /// Percentile of recent fees on the touched accounts to pay, 0 pays
+    /// `priority_fee` only.
+    #[serde(default = "default_priority_fee_percentile")]
+    pub priority_fee_percentile: u8,
+    /// Upper bound on the priority fee in SOL per transaction. A higher
+    /// `priority_fee` raises it.
+    #[serde(default = "default_max_priority_fee")]
+    pub max_priority_fee: f64,
+    /// Compute-unit limit set on every transaction. 0 simulates each one for
+    /// its limit, which costs a round trip before sending.
+    #[serde(default)]
+    pub compute_unit_limit: u32,
     pub bundle_tip: f64,
-    pub slippage: bool,
+    /// Price move tolerated between the quote and the fill, in basis points.
+    /// Past it the swap reverts on-chain.
+    #[serde(default = "default_slippage_bps")]
+    pub slippage_bps: u16,
     pub spam: bool,
     pub spam_count: i32,
+    /// Sells the whole position once its gain falls to this percentage, a
+    /// negative value, e.g. -50.0 for half the cost lost. 0 disables.
     pub loss_threshold_percentage: f64,
+    /// Sells the whole position once it is up this many percent, unless a
+    /// `take_profit` ladder is set. 0 disables.
     pub profit_threshold_percentage: f64,
     pub copytrade_accounts: Vec<String>,
+    /// How many times a transaction that expired unconfirmed is re-signed with
+    /// a fresh blockhash and sent again.
+    #[serde(default = "default_send_retries")]
+    pub send_retries: u32,
+    /// Lets the position manager sell on its own. When off, positions are only
+    /// watched.
+    #[serde(default)]
+    pub auto_exit: bool,
+    /// Sells everything once the value falls this many percent below its high
+    /// since the buy, 0 disables.
+    #[serde(default)]
+    pub trailing_stop_percentage: f64,
+    /// Sells everything this many seconds after the buy, 0 disables.
+    #[serde(default)]
+    pub max_hold_secs: u64,
+    /// Per-route overrides of `slippage_bps`, keyed by route, e.g.
+    /// `PumpFun = 1500`.
+    #[serde(default)]
+    pub route_slippage_bps: BTreeMap<String, u16>,
+    /// Take-profit steps in rising order, e.g. sell 50% at +100% then the rest
+    /// at +300%.
+    #[serde(default)]
+    pub take_profit: Vec<TakeProfitStep>,
+    /// Exits of Pump.fun positions driven by how far the bonding curve is
+    /// from migrating.
+    #[serde(default)]
+    pub curve_exit: CurveExitSettings,
 }
 
-pub async fn load_config() -> eyre::Result<SettingsConfig> {
-    let config_content = fs::read_to_string("config.toml").unwrap_or_else(|_| {
-        info!("Config file not found, creating a new one");
-        let default_config = SettingsConfig {
-            user: UserSettings {
-                username: String::new(),
-                license_key: String::new(),
-            },
-            network: NetworkSettings {
-                block_engine_url: "https://ny.mainnet.block-engine.jito.wtf".to_string(),
-                pubsub_url:
-                    "wss://mainnet.helius-rpc.com/?api-key=0b99078c-7247-47ad-8cf8-35cbfc021667"
-                        .to_string(),
-                rpc_url:
-                    "https://mainnet.helius-rpc.com/?api-key=0b99078c-7247-47ad-8cf8-35cbfc021667"
-                        .to_string(),
-                grpc_url: String::new(),
-                regions: vec!["ny".to_string()],
-            },
-            engine: EngineSettings {
-                payer_keypair: String::new(),
-                subscribe_bundle_results: false,
-                use_bundles: true,
-            },
-            trading: TradingSettings {
-                buy_amount: 0.00001,
-                spam: false,
-                spam_count: 15,
-                slippage: false,
-                priority_fee: 0.0,
-                bundle_tip: 0.0,
-                copytrade_accounts: vec![],
-                loss_threshold_percentage: 50.0,
-                profit_threshold_percentage: 100.0,
-            },
-        };
-        let default_toml = toml::to_string(&default_config).unwrap();
-        let mut file = File::create("config.toml").unwrap();
-        file.write_all(default_toml.as_bytes()).unwrap();
-        default_toml
-    });
-
-    let mut config: SettingsConfig = toml::from_str(&config_content).unwrap_or_else(|_| {
-        info!("Invalid config file, creating a new one");
-        let default_config = SettingsConfig {
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TakeProfitStep {
+    /// Gain over the buy price, in percent.
+    pub gain_percentage: f64,
+    /// Share of the tokens still held to sell, in percent.
+    pub sell_percentage: f64,
+}
+
+/// Bonding-curve exits of Pump.fun positions. Empty steps and zero limits are
+/// off, and apply whether or not `auto_exit` is on.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct CurveExitSettings {
+    /// Sells in rising order of curve completion, e.g. 50% of the tokens at
+    /// 60% complete.
+    #[serde(default)]
+    pub steps: Vec<CurveExitStep>,
+    /// Sells everything once the curve is this many percent complete, before
+    /// it migrates.
+    #[serde(default)]
+    pub exit_before_migration_percentage: f64,
+    /// Keeps the position through the migration and sells it on the Raydium
+    /// pool it moves to. When off, the watcher stops once the curve completes,
+    /// so exits need `exit_before_migration_percentage` instead.
+    #[serde(default)]
+    pub follow_migration: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CurveExitStep {
+    /// Share of the curve's tokens sold, in percent.
+    pub completion_percentage: f64,
+    /// Share of the tokens still held to sell, in percent.
+    pub sell_percentage: f64,
+}
+
+/// Routes the multi-route sniper watches together over one Geyser stream.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SniperSettings {
+    #[serde(default)]
+    pub raydium_amm: RouteSettings,
+    #[serde(default)]
+    pub pump_fun: RouteSettings,
+    #[serde(default)]
+    pub pump_fun_migration: RouteSettings,
+    #[serde(default)]
+    pub daos_fun: RouteSettings,
+    /// Screening of launches the Pump.fun sniper finds on its own.
+    #[serde(default)]
+    pub pump_fun_filters: LaunchFilters,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct RouteSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// SOL spent per snipe, 0 uses `trading.buy_amount`.
+    #[serde(default)]
+    pub buy_amount: f64,
+}
+
+/// Rules a Pump.fun launch must pass before an auto snipe buys it. Empty lists,
+/// empty patterns and zero limits are off.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct LaunchFilters {
+    /// Only launches by these creators, when not empty.
+    #[serde(default)]
+    pub creator_allow: Vec<String>,
+    #[serde(default)]
+    pub creator_deny: Vec<String>,
+    /// Regex the coin name must match.
+    #[serde(default)]
+    pub name_regex: Pattern,
+    /// Regex the coin symbol must match.
+    #[serde(default)]
+    pub symbol_regex: Pattern,
+    /// Words that reject a coin when its name or symbol contains them, in any
+    /// case.
+    #[serde(default)]
+    pub blocked_keywords: Vec<String>,
+    /// Only metadata hosted on these domains or their subdomains, when not
+    /// empty.
+    #[serde(default)]
+    pub uri_allowed_domains: Vec<String>,
+    #[serde(default)]
+    pub uri_blocked_domains: Vec<String>,
+    /// SOL the creator holds after launching.
+    #[serde(default)]
+    pub min_creator_sol: f64,
+    #[serde(default)]
+    pub max_creator_sol: f64,
+    /// Most earlier Pump.fun launches by the creator, counted on chain from
+    /// their recent transactions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_prior_launches: Option<u32>,
+    /// SOL the creator buys in the launch transaction. A minimum rejects
+    /// launches without a dev buy.
+    #[serde(default)]
+    pub min_dev_buy: f64,
+    #[serde(default)]
+    pub max_dev_buy: f64,
+    #[serde(default)]
+    pub max_launches_per_hour: u32,
+}
+
+/// A regex compiled as the config is read, so launches are never matched
+/// against an invalid one. Empty matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct Pattern(Option<Regex>);
+
+impl Pattern {
+    pub fn as_str(&self) -> &str {
+        self.0.as_ref().map_or("", Regex::as_str)
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.as_ref().is_none_or(|regex| regex.is_match(text))
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = regex::Error;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        if pattern.is_empty() {
+            return Ok(Pattern(None));
+        }
+        Regex::new(pattern).map(|regex| Pattern(Some(regex)))
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        pattern
+            .parse()
+            .map_err(|e| de::Error::custom(format!("`{}` is not a valid regex: {}", pattern, e)))
+    }
+}
+
+impl SniperSettings {
+    /// Every route with its key under `[sniper]`.
+    pub fn routes(&self) -> [(&'static str, SniperRoute, &RouteSettings); 4] {
+        [
+            ("raydium_amm", SniperRoute::RaydiumAMM, &self.raydium_amm),
+            ("pump_fun", SniperRoute::PumpFun, &self.pump_fun),
+            ("pump_fun_migration", SniperRoute::PumpFunMigration, &self.pump_fun_migration),
+            ("daos_fun", SniperRoute::DaosFun, &self.daos_fun),
+        ]
+    }
+}
+
+fn default_slippage_bps() -> u16 {
+    500
+}
+
+fn default_send_retries() -> u32 {
+    2
+}
+
+fn default_priority_fee_percentile() -> u8 {
+    75
+}
+
+fn default_max_priority_fee() -> f64 {
+    0.005
+}
+
+static HEADLESS: AtomicBool = AtomicBool::new(false);
+
+/// Marks the session as non-interactive: config loading and the swap paths
+/// must never prompt, missing values become errors instead.
+pub fn set_headless(headless: bool) {
+    HEADLESS.store(headless, Ordering::Relaxed);
+}
+
+pub fn is_headless() -> bool {
+    HEADLESS.load(Ordering::Relaxed)
+}
+
+pub const CONFIG_PATH: &str = "config.toml";
+
+static PROFILE: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+
+/// Selects the profile overlay (`config.<name>.toml`) used by `load_config`.
+/// Without one, `MEVARIK_PROFILE` is consulted.
+pub fn set_profile(profile: Option<String>) {
+    *PROFILE.write().unwrap() = profile;
+}
+
+pub fn active_profile() -> Option<String> {
+    PROFILE
+        .read()
+        .unwrap()
+        .clone()
+        .or_else(|| std::env::var(format!("{}_PROFILE", layers::ENV_PREFIX)).ok())
+        .filter(|profile| !profile.is_empty())
+}
+
+impl SettingsConfig {
+    /// Slippage for `route`, its override in `route_slippage_bps` first.
+    pub fn slippage_bps(&self, route: &SniperRoute) -> u16 {
+        let name = format!("{:?}", route);
+        self.trading
+            .route_slippage_bps
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(&name))
+            .map_or(self.trading.slippage_bps, |(_, bps)| *bps)
+    }
+}
+
+impl Default for SettingsConfig {
+    fn default() -> Self {
+        SettingsConfig {
             user: UserSettings {
                 username: String::new(),
                 license_key: String::new(),
             },
             network: NetworkSettings {
                 block_engine_url: "https://ny.mainnet.block-engine.jito.wtf".to_string(),
-                pubsub_url:
-                    "wss://mainnet.helius-rpc.com/?api-key=0b99078c-7247-47ad-8cf8-35cbfc021667"
-                        .to_string(),
-                rpc_url:
-                    "https://mainnet.helius-rpc.com/?api-key=0b99078c-7247-47ad-8cf8-35cbfc021667"
-                        .to_string(),
+                pubsub_url: "wss://api.mainnet-beta.solana.com".to_string(),
+                rpc_url: "https://api.mainnet-beta.solana.com".to_string(),
                 grpc_url: String::new(),
+                grpc_x_token: String::new(),
                 regions: vec!["ny".to_string()],
+                send_rpc_urls: vec![],
+                record_path: String::new(),
             },
             engine: EngineSettings {
                 payer_keypair: String::new(),
@@ -127,45 +392,183 @@ pub async fn load_config() -> eyre::Result<SettingsConfig> {
             trading: TradingSettings {
                 buy_amount: 0.00001,
                 priority_fee: 0.0,
+                priority_fee_percentile: default_priority_fee_percentile(),
+                max_priority_fee: default_max_priority_fee(),
+                compute_unit_limit: 0,
                 bundle_tip: 0.0,
                 spam: false,
                 spam_count: 15,
-                slippage: false,
+                slippage_bps: default_slippage_bps(),
                 loss_threshold_percentage: -50.0,
                 profit_threshold_percentage: 100.0,
                 copytrade_accounts: vec![],
+                send_retries: default_send_retries(),
+                auto_exit: false,
+                trailing_stop_percentage: 0.0,
+                max_hold_secs: 0,
+                route_slippage_bps: BTreeMap::new(),
+                take_profit: vec![],
+                curve_exit: CurveExitSettings::default(),
             },
-        };
-        let default_toml = toml::to_string(&default_config).unwrap();
-        let mut file = File::create("config.toml").unwrap();
-        file.write_all(default_toml.as_bytes()).unwrap();
-        default_config
-    });
+            sniper: SniperSettings::default(),
+        }
+    }
+}
+
+/// Every key an environment variable can set, with a value of its type. The
+/// defaults leave out unset options and the shape of empty arrays of tables,
+/// so those are added.
+fn env_schema(defaults: &toml::Value) -> eyre::Result<toml::Value> {
+    let mut schema = defaults.clone();
+    merge_values(
+        &mut schema,
+        toml::from_str(
+            r#"
+            [trading]
+            take_profit = [{ gain_percentage = 0.0, sell_percentage = 0.0 }]
+
+            [trading.curve_exit]
+            steps = [{ completion_percentage = 0.0, sell_percentage = 0.0 }]
+
+            [sniper.pump_fun_filters]
+            max_prior_launches = 0
+            "#,
+        )?,
+    );
+    Ok(schema)
+}
+
+/// Loads the settings in layers: built-in defaults, `config.toml`, the active
+/// profile overlay, then `MEVARIK_<PATH>` environment variables, e.g.
+/// `MEVARIK_SNIPER_PUMP_FUN_ENABLED`.
+/// The result is validated before it is returned. `config.toml` is only ever
+/// written to create it or to store values the user was prompted for, never
+/// to replace a file that fails to parse.
+pub async fn load_config() -> eyre::Result<SettingsConfig> {
+    let defaults = toml::Value::try_from(SettingsConfig::default())?;
+
+    let mut file_config = match fs::read_to_string(CONFIG_PATH) {
+        Ok(content) => toml::from_str::<toml::Value>(&content).map_err(|e| {
+            eyre::eyre!(
+                "{} is not valid TOML, fix or move it away and restart: {}",
+                CONFIG_PATH,
+                e
+            )
+        })?,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            info!("Config file not found, creating a new one");
+            let mut file = File::create(CONFIG_PATH)?;
+            file.write_all(toml::to_string_pretty(&SettingsConfig::default())?.as_bytes())?;
+            defaults.clone()
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut layered = defaults.clone();
+    merge_values(&mut layered, file_config.clone());
+    if let Some(profile) = active_profile() {
+        merge_values(&mut layered, read_profile(&profile)?);
+    }
+    apply_env_overrides(&mut layered, &env_schema(&defaults)?)?;
+
+    let mut config: SettingsConfig = layered
+        .try_into()
+        .map_err(|e| eyre::eyre!("Invalid config: {}", e))?;
+    config.engine.payer_keypair = resolve_secret(&config.engine.payer_keypair)?;
+    validate_config(&config)?;
+
+    if is_headless() {
+        if config.engine.payer_keypair.is_empty() {
+            return Err(eyre::eyre!(
+                "engine.payer_keypair is empty, set it in {} or {}",
+                CONFIG_PATH,
+                layers::env_var_name(&["engine", "payer_keypair"])
+            ));
+        }
+        return Ok(config);
+    }
+
+    let mut prompted = false;
 
     if config.user.username.is_empty() {
         config.user.username = register_sims("Enter Discord Username: ", "popuy...")
             .await
             .unwrap();
+        set_field(&mut file_config, "user", "username", &config.user.username);
+        prompted = true;
     }
 
     if config.user.license_key.is_empty() {
         config.user.license_key = register_sims("Enter License Key: ", "MEVA........ImCh")
             .await
             .unwrap();
+        set_field(&mut file_config, "user", "license_key", &config.user.license_key);
+        prompted = true;
     }
 
     if config.engine.payer_keypair.is_empty() {
         config.engine.payer_keypair = private_key_env("Enter Wallet Private-Key: ").await.unwrap();
+        let reference = store_key(
+            "payer",
+            &Keypair::from_base58_string(&config.engine.payer_keypair),
+        )?;
+        set_field(&mut file_config, "engine", "payer_keypair", &reference);
+        prompted = true;
     }
 
-    let updated_config = toml::to_string_pretty(&config).unwrap();
-    fs::write("config.toml", updated_config)?;
+    if prompted {
+        fs::write(CONFIG_PATH, toml::to_string_pretty(&file_config)?)?;
+    }
 
     Ok(config)
 }
 
+fn set_field(config: &mut toml::Value, section: &str, field: &str, value: &str) {
+    if let Some(root) = config.as_table_mut() {
+        let table = root
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(Default::default()));
+        if let Some(table) = table.as_table_mut() {
+            table.insert(field.to_string(), toml::Value::String(value.to_string()));
+        }
+    }
+}
+
 pub async fn register_sims(key: &str, place_holder: &str) -> Result<String, Box<dyn Error>> {
     let t = Input::new(key).placeholder(place_holder).prompt("Input: ");
     let string = t.run().expect("error running input");
     Ok(string)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::layers::apply_overrides_with;
+
+    #[test]
+    fn env_schema_covers_options_and_arrays_of_tables() {
+        let defaults = toml::Value::try_from(SettingsConfig::default()).unwrap();
+        let mut layered = defaults.clone();
+        apply_overrides_with(&mut layered, &env_schema(&defaults).unwrap(), |name| match name {
+            "MEVARIK_SNIPER_PUMP_FUN_FILTERS_MAX_PRIOR_LAUNCHES" => Some("3".to_string()),
+            "MEVARIK_TRADING_CURVE_EXIT_STEPS" => Some(
+                "[{ completion_percentage = 60.0, sell_percentage = 50.0 }]".to_string(),
+            ),
+            _ => None,
+        })
+        .unwrap();
+
+        let config: SettingsConfig = layered.try_into().unwrap();
+        assert_eq!(config.sniper.pump_fun_filters.max_prior_launches, Some(3));
+        assert_eq!(config.trading.curve_exit.steps.len(), 1);
+        assert!(config.trading.take_profit.is_empty());
+    }
+
+    #[test]
+    fn invalid_patterns_fail_to_load() {
+        let mut layered = toml::Value::try_from(SettingsConfig::default()).unwrap();
+        layered["sniper"]["pump_fun_filters"]["name_regex"] = toml::Value::from("(");
+        let err = layered.try_into::<SettingsConfig>().unwrap_err().to_string();
+        assert!(err.contains("name_regex"), "{}", err);
+    }
+}
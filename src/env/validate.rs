@@ -0,0 +1,297 @@
+use std::str::FromStr;
+
+use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+
+use crate::{sender::fees::MAX_COMPUTE_UNIT_LIMIT, utils::slippage::MAX_BPS};
+
+use super::SettingsConfig;
+
+/// Route names accepted as `route_slippage_bps` keys.
+const ROUTES: [&str; 7] = [
+    "RaydiumAMM",
+    "RaydiumCPMM",
+    "PumpFun",
+    "PumpFunMigration",
+    "MoonShot",
+    "Jupiter",
+    "DaosFun",
+];
+
+/// Checks a fully layered config and reports every problem at once, one per line.
+/// An empty `payer_keypair` is accepted here; the caller decides whether to
+/// prompt for it or fail.
+pub fn validate_config(config: &SettingsConfig) -> eyre::Result<()> {
+    let mut errors = vec![];
+
+    check_url(&mut errors, "network.rpc_url", &config.network.rpc_url, &["http", "https"], false);
+    check_url(&mut errors, "network.pubsub_url", &config.network.pubsub_url, &["ws", "wss"], false);
+    check_url(
+        &mut errors,
+        "network.block_engine_url",
+        &config.network.block_engine_url,
+        &["http", "https"],
+        false,
+    );
+    check_url(&mut errors, "network.grpc_url", &config.network.grpc_url, &["http", "https"], true);
+    for url in &config.network.send_rpc_urls {
+        check_url(&mut errors, "network.send_rpc_urls", url, &["http", "https"], false);
+    }
+
+    if !config.engine.payer_keypair.is_empty() && !is_valid_keypair(&config.engine.payer_keypair) {
+        errors.push("engine.payer_keypair is not a valid base58 encoded keypair".to_string());
+    }
+
+    for (name, value) in [
+        ("trading.buy_amount", config.trading.buy_amount),
+        ("trading.priority_fee", config.trading.priority_fee),
+        ("trading.max_priority_fee", config.trading.max_priority_fee),
+        ("trading.bundle_tip", config.trading.bundle_tip),
+        ("trading.profit_threshold_percentage", config.trading.profit_threshold_percentage),
+        ("trading.trailing_stop_percentage", config.trading.trailing_stop_percentage),
+    ] {
+        if !value.is_finite() || value < 0.0 {
+            errors.push(format!("{} must not be negative, got {}", name, value));
+        }
+    }
+    for (key, _, settings) in config.sniper.routes() {
+        if !settings.buy_amount.is_finite() || settings.buy_amount < 0.0 {
+            errors.push(format!(
+                "sniper.{}.buy_amount must not be negative, got {}",
+                key, settings.buy_amount
+            ));
+        }
+    }
+    if config.trading.priority_fee_percentile > 100 {
+        errors.push(format!(
+            "trading.priority_fee_percentile must be at most 100, got {}",
+            config.trading.priority_fee_percentile
+        ));
+    }
+    if !config.trading.loss_threshold_percentage.is_finite()
+        || config.trading.loss_threshold_percentage > 0.0
+    {
+        errors.push(format!(
+            "trading.loss_threshold_percentage must not be positive, got {}",
+            config.trading.loss_threshold_percentage
+        ));
+    }
+    if config.trading.trailing_stop_percentage >= 100.0 {
+        errors.push(format!(
+            "trading.trailing_stop_percentage must be below 100, got {}",
+            config.trading.trailing_stop_percentage
+        ));
+    }
+    if config.trading.slippage_bps as u64 > MAX_BPS {
+        errors.push(format!(
+            "trading.slippage_bps must be at most {}, got {}",
+            MAX_BPS, config.trading.slippage_bps
+        ));
+    }
+    for (route, bps) in &config.trading.route_slippage_bps {
+        if !ROUTES.iter().any(|name| name.eq_ignore_ascii_case(route)) {
+            errors.push(format!(
+                "trading.route_slippage_bps: unknown route `{}`, expected one of {}",
+                route,
+                ROUTES.join(", ")
+            ));
+        }
+        if *bps as u64 > MAX_BPS {
+            errors.push(format!(
+                "trading.route_slippage_bps.{} must be at most {}, got {}",
+                route, MAX_BPS, bps
+            ));
+        }
+    }
+    let mut last_gain = 0.0;
+    for (i, step) in config.trading.take_profit.iter().enumerate() {
+        if !step.gain_percentage.is_finite() || step.gain_percentage <= last_gain {
+            errors.push(format!(
+                "trading.take_profit[{}]: gain_percentage must be above {}, got {}",
+                i, last_gain, step.gain_percentage
+            ));
+        }
+        if !(step.sell_percentage > 0.0 && step.sell_percentage <= 100.0) {
+            errors.push(format!(
+                "trading.take_profit[{}]: sell_percentage must be in (0, 100], got {}",
+                i, step.sell_percentage
+            ));
+        }
+        last_gain = step.gain_percentage;
+    }
+    let curve_exit = &config.trading.curve_exit;
+    let mut last_completion = 0.0;
+    for (i, step) in curve_exit.steps.iter().enumerate() {
+        if !(step.completion_percentage > last_completion && step.completion_percentage < 100.0) {
+            errors.push(format!(
+                "trading.curve_exit.steps[{}]: completion_percentage must be in ({}, 100), got {}",
+                i, last_completion, step.completion_percentage
+            ));
+        }
+        if !(step.sell_percentage > 0.0 && step.sell_percentage <= 100.0) {
+            errors.push(format!(
+                "trading.curve_exit.steps[{}]: sell_percentage must be in (0, 100], got {}",
+                i, step.sell_percentage
+            ));
+        }
+        last_completion = step.completion_percentage;
+    }
+    let before_migration = curve_exit.exit_before_migration_percentage;
+    if !(0.0..100.0).contains(&before_migration) {
+        errors.push(format!(
+            "trading.curve_exit.exit_before_migration_percentage must be in [0, 100), got {}",
+            before_migration
+        ));
+    }
+    // A completed curve can no longer be sold on, so the exits need either a
+    // sell before it completes or the pool it migrates to.
+    let exits_in_use = config.trading.auto_exit || !curve_exit.steps.is_empty();
+    if exits_in_use && before_migration == 0.0 && !curve_exit.follow_migration {
+        errors.push(
+            "trading.curve_exit: set exit_before_migration_percentage or follow_migration = true, \
+             otherwise Pump.fun positions are dropped unsold once their curve completes"
+                .to_string(),
+        );
+    }
+    if config.trading.compute_unit_limit > MAX_COMPUTE_UNIT_LIMIT {
+        errors.push(format!(
+            "trading.compute_unit_limit must be at most {}, got {}",
+            MAX_COMPUTE_UNIT_LIMIT, config.trading.compute_unit_limit
+        ));
+    }
+    if config.trading.spam_count < 0 {
+        errors.push(format!(
+            "trading.spam_count must not be negative, got {}",
+            config.trading.spam_count
+        ));
+    }
+
+    for account in &config.trading.copytrade_accounts {
+        if Pubkey::from_str(account).is_err() {
+            errors.push(format!("trading.copytrade_accounts: `{}` is not a valid pubkey", account));
+        }
+    }
+
+    let filters = &config.sniper.pump_fun_filters;
+    for (name, accounts) in [
+        ("creator_allow", &filters.creator_allow),
+        ("creator_deny", &filters.creator_deny),
+    ] {
+        for account in accounts {
+            if Pubkey::from_str(account).is_err() {
+                errors.push(format!(
+                    "sniper.pump_fun_filters.{}: `{}` is not a valid pubkey",
+                    name, account
+                ));
+            }
+        }
+    }
+    for (name, min, max) in [
+        ("creator_sol", filters.min_creator_sol, filters.max_creator_sol),
+        ("dev_buy", filters.min_dev_buy, filters.max_dev_buy),
+    ] {
+        if !min.is_finite() || !max.is_finite() || min < 0.0 || max < 0.0 {
+            errors.push(format!(
+                "sniper.pump_fun_filters min_{0} and max_{0} must not be negative",
+                name
+            ));
+        } else if max > 0.0 && min > max {
+            errors.push(format!(
+                "sniper.pump_fun_filters.min_{0} must not exceed max_{0}, got {1} > {2}",
+                name, min, max
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(eyre::eyre!("Invalid config:\n  - {}", errors.join("\n  - ")))
+    }
+}
+
+pub fn is_valid_keypair(private_key: &str) -> bool {
+    bs58::decode(private_key)
+        .into_vec()
+        .map(|bytes| Keypair::from_bytes(&bytes).is_ok())
+        .unwrap_or(false)
+}
+
+fn check_url(errors: &mut Vec<String>, name: &str, url: &str, schemes: &[&str], optional: bool) {
+    if url.is_empty() {
+        if !optional {
+            errors.push(format!("{} is empty", name));
+        }
+        return;
+    }
+    match url.split_once("://") {
+        Some((scheme, rest)) if schemes.contains(&scheme) && !rest.is_empty() => {}
+        _ => errors.push(format!(
+            "{} `{}` must start with {}",
+            name,
+            url,
+            schemes
+                .iter()
+                .map(|s| format!("{}://", s))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::TakeProfitStep;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(validate_config(&SettingsConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let mut config = SettingsConfig::default();
+        config.network.rpc_url = "wss://mainnet".to_string();
+        config.trading.buy_amount = -1.0;
+        config.trading.copytrade_accounts = vec!["not-a-key".to_string()];
+        config.trading.route_slippage_bps.insert("pumpswap".to_string(), 100);
+        config.engine.payer_keypair = "abc".to_string();
+        config.sniper.pump_fun.buy_amount = -0.5;
+        config.trading.curve_exit.exit_before_migration_percentage = 100.0;
+        config.trading.take_profit = vec![
+            TakeProfitStep {
+                gain_percentage: 300.0,
+                sell_percentage: 50.0,
+            },
+            TakeProfitStep {
+                gain_percentage: 100.0,
+                sell_percentage: 100.0,
+            },
+        ];
+
+        let err = validate_config(&config).unwrap_err().to_string();
+        assert!(err.contains("network.rpc_url"));
+        assert!(err.contains("trading.buy_amount"));
+        assert!(err.contains("not-a-key"));
+        assert!(err.contains("engine.payer_keypair"));
+        assert!(err.contains("trading.take_profit[1]"));
+        assert!(err.contains("`pumpswap`"));
+        assert!(err.contains("sniper.pump_fun.buy_amount"));
+        assert!(err.contains("trading.curve_exit.exit_before_migration_percentage"));
+    }
+
+    #[test]
+    fn exits_must_outlive_the_curve() {
+        let mut config = SettingsConfig::default();
+        config.trading.auto_exit = true;
+        let err = validate_config(&config).unwrap_err().to_string();
+        assert!(err.contains("trading.curve_exit: set exit_before_migration_percentage"));
+
+        config.trading.curve_exit.follow_migration = true;
+        assert!(validate_config(&config).is_ok());
+
+        config.trading.curve_exit.follow_migration = false;
+        config.trading.curve_exit.exit_before_migration_percentage = 95.0;
+        assert!(validate_config(&config).is_ok());
+    }
+}
@@ -0,0 +1,74 @@
+use solana_sdk::pubkey::Pubkey;
+use yellowstone_grpc_proto::geyser::SubscribeUpdateTransactionInfo;
+
+/// Every account an instruction can point to, in index order: the static keys
+/// of the message, then the writable and the readonly addresses loaded from
+/// lookup tables.
+pub fn account_keys(info: &SubscribeUpdateTransactionInfo) -> eyre::Result<Vec<Pubkey>> {
+    let static_keys = info
+        .transaction
+        .as_ref()
+        .and_then(|transaction| transaction.message.as_ref())
+        .map(|message| message.account_keys.as_slice())
+        .unwrap_or_default();
+    let (loaded_writable, loaded_readonly) = info
+        .meta
+        .as_ref()
+        .map(|meta| {
+            (
+                meta.loaded_writable_addresses.as_slice(),
+                meta.loaded_readonly_addresses.as_slice(),
+            )
+        })
+        .unwrap_or_default();
+
+    static_keys
+        .iter()
+        .chain(loaded_writable)
+        .chain(loaded_readonly)
+        .map(|key| {
+            Pubkey::try_from(key.as_slice())
+                .map_err(|_| eyre::eyre!("Account key of {} bytes", key.len()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use yellowstone_grpc_proto::prelude::{Message, Transaction, TransactionStatusMeta};
+
+    use super::*;
+
+    fn key(byte: u8) -> Vec<u8> {
+        vec![byte; 32]
+    }
+
+    #[test]
+    fn loaded_addresses_follow_the_static_keys() {
+        let info = SubscribeUpdateTransactionInfo {
+            transaction: Some(Transaction {
+                message: Some(Message {
+                    account_keys: vec![key(1), key(2)],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            meta: Some(TransactionStatusMeta {
+                loaded_writable_addresses: vec![key(3)],
+                loaded_readonly_addresses: vec![key(4), key(5)],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let keys = account_keys(&info).unwrap();
+        let expected: Vec<Pubkey> = (1..=5)
+            .map(|byte| Pubkey::new_from_array([byte; 32]))
+            .collect();
+        assert_eq!(keys, expected);
+
+        let mut truncated = info;
+        truncated.meta.as_mut().unwrap().loaded_readonly_addresses = vec![vec![6; 31]];
+        assert!(account_keys(&truncated).is_err());
+    }
+}
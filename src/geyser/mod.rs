@@ -0,0 +1,9 @@
+pub mod accounts;
+pub mod parsed;
+pub mod record;
+pub mod subscription;
+
+pub use accounts::account_keys;
+pub use parsed::{ParsedInstruction, ParsedTransaction};
+pub use record::{Recorder, RecorderSink, Recording};
+pub use subscription::{GeyserStream, GeyserSubscription, RequestHandle, StreamState};
@@ -0,0 +1,509 @@
+use std::collections::BTreeMap;
+
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiInstruction, UiLoadedAddresses,
+    UiTransactionTokenBalance,
+};
+use yellowstone_grpc_proto::{
+    geyser::{SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo},
+    prelude::{
+        CompiledInstruction, InnerInstruction, InnerInstructions, Message, MessageHeader,
+        TokenBalance as ProtoTokenBalance, Transaction, TransactionError, TransactionStatusMeta,
+        UiTokenAmount,
+    },
+};
+
+use super::accounts::account_keys;
+
+/// A Geyser transaction decoded once, with every account index resolved.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedTransaction {
+    pub signature: Signature,
+    pub slot: u64,
+    /// Static keys first, then the addresses loaded from lookup tables.
+    pub accounts: Vec<TransactionAccount>,
+    pub instructions: Vec<ParsedInstruction>,
+    pub logs: Vec<String>,
+    /// Raw token balances by mint and owner.
+    pub token_balances: BTreeMap<(Pubkey, Pubkey), TokenBalance>,
+    pub fee: u64,
+    pub failed: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    /// Lamports before and after the transaction.
+    pub pre_balance: u64,
+    pub post_balance: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+    /// Instructions invoked by this one, in order. Always empty on inner
+    /// instructions themselves.
+    pub inner: Vec<ParsedInstruction>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub pre: u64,
+    pub post: u64,
+    pub decimals: u32,
+}
+
+impl TokenBalance {
+    pub fn change(&self) -> i128 {
+        self.post as i128 - self.pre as i128
+    }
+}
+
+impl TryFrom<SubscribeUpdateTransaction> for ParsedTransaction {
+    type Error = eyre::Report;
+
+    fn try_from(update: SubscribeUpdateTransaction) -> eyre::Result<Self> {
+        let info = update
+            .transaction
+            .ok_or_else(|| eyre::eyre!("Update without a transaction"))?;
+        let keys = account_keys(&info)?;
+        let message = info
+            .transaction
+            .and_then(|transaction| transaction.message)
+            .unwrap_or_default();
+        let meta = info.meta.unwrap_or_default();
+
+        let static_len = message.account_keys.len();
+        let loaded_writable = meta.loaded_writable_addresses.len();
+        let header = message.header.unwrap_or_default();
+        let accounts = keys
+            .iter()
+            .enumerate()
+            .map(|(index, pubkey)| TransactionAccount {
+                pubkey: *pubkey,
+                is_signer: index < header.num_required_signatures as usize,
+                is_writable: if index < static_len {
+                    is_static_writable(&header, static_len, index)
+                } else {
+                    index < static_len + loaded_writable
+                },
+                pre_balance: meta.pre_balances.get(index).copied().unwrap_or_default(),
+                post_balance: meta.post_balances.get(index).copied().unwrap_or_default(),
+            })
+            .collect();
+
+        let mut instructions = message
+            .instructions
+            .iter()
+            .map(|instruction| {
+                resolve(
+                    &keys,
+                    instruction.program_id_index,
+                    &instruction.accounts,
+                    &instruction.data,
+                )
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        for group in &meta.inner_instructions {
+            let outer = instructions.get_mut(group.index as usize).ok_or_else(|| {
+                eyre::eyre!("Inner instructions of missing instruction {}", group.index)
+            })?;
+            for instruction in &group.instructions {
+                outer.inner.push(resolve(
+                    &keys,
+                    instruction.program_id_index,
+                    &instruction.accounts,
+                    &instruction.data,
+                )?);
+            }
+        }
+
+        let mut token_balances: BTreeMap<(Pubkey, Pubkey), TokenBalance> = BTreeMap::new();
+        for (balance, is_post) in meta
+            .pre_token_balances
+            .iter()
+            .map(|balance| (balance, false))
+            .chain(
+                meta.post_token_balances
+                    .iter()
+                    .map(|balance| (balance, true)),
+            )
+        {
+            let Some((mint, owner, amount, decimals)) = token_amount(balance) else {
+                continue;
+            };
+            let entry = token_balances.entry((mint, owner)).or_default();
+            entry.decimals = decimals;
+            if is_post {
+                entry.post += amount;
+            } else {
+                entry.pre += amount;
+            }
+        }
+
+        Ok(ParsedTransaction {
+            signature: Signature::try_from(info.signature.as_slice())
+                .map_err(|_| eyre::eyre!("Signature of {} bytes", info.signature.len()))?,
+            slot: update.slot,
+            accounts,
+            instructions,
+            logs: meta.log_messages,
+            token_balances,
+            fee: meta.fee,
+            failed: meta.err.is_some(),
+        })
+    }
+}
+
+/// A transaction fetched with `getTransaction` in a binary encoding, for
+/// event sources without Geyser.
+impl TryFrom<EncodedConfirmedTransactionWithStatusMeta> for ParsedTransaction {
+    type Error = eyre::Report;
+
+    fn try_from(confirmed: EncodedConfirmedTransactionWithStatusMeta) -> eyre::Result<Self> {
+        let transaction = confirmed
+            .transaction
+            .transaction
+            .decode()
+            .ok_or_else(|| eyre::eyre!("Transaction is not binary encoded"))?;
+        let meta = confirmed
+            .transaction
+            .meta
+            .ok_or_else(|| eyre::eyre!("Transaction without status meta"))?;
+
+        let header = transaction.message.header();
+        let message = Message {
+            header: Some(MessageHeader {
+                num_required_signatures: header.num_required_signatures as u32,
+                num_readonly_signed_accounts: header.num_readonly_signed_accounts as u32,
+                num_readonly_unsigned_accounts: header.num_readonly_unsigned_accounts as u32,
+            }),
+            account_keys: transaction
+                .message
+                .static_account_keys()
+                .iter()
+                .map(|key| key.to_bytes().to_vec())
+                .collect(),
+            instructions: transaction
+                .message
+                .instructions()
+                .iter()
+                .map(|instruction| CompiledInstruction {
+                    program_id_index: instruction.program_id_index as u32,
+                    accounts: instruction.accounts.clone(),
+                    data: instruction.data.clone(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let loaded = Option::<UiLoadedAddresses>::from(meta.loaded_addresses).unwrap_or_default();
+        let inner_instructions = Option::<Vec<_>>::from(meta.inner_instructions)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|group| {
+                Ok(InnerInstructions {
+                    index: group.index as u32,
+                    instructions: group
+                        .instructions
+                        .into_iter()
+                        .map(compiled_inner_instruction)
+                        .collect::<eyre::Result<_>>()?,
+                })
+            })
+            .collect::<eyre::Result<_>>()?;
+        let status = TransactionStatusMeta {
+            err: meta.err.map(|_| TransactionError::default()),
+            fee: meta.fee,
+            pre_balances: meta.pre_balances,
+            post_balances: meta.post_balances,
+            inner_instructions,
+            log_messages: Option::from(meta.log_messages).unwrap_or_default(),
+            pre_token_balances: proto_token_balances(meta.pre_token_balances.into()),
+            post_token_balances: proto_token_balances(meta.post_token_balances.into()),
+            loaded_writable_addresses: pubkey_bytes(&loaded.writable)?,
+            loaded_readonly_addresses: pubkey_bytes(&loaded.readonly)?,
+            ..Default::default()
+        };
+
+        SubscribeUpdateTransaction {
+            slot: confirmed.slot,
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: transaction
+                    .signatures
+                    .first()
+                    .map(|signature| signature.as_ref().to_vec())
+                    .unwrap_or_default(),
+                transaction: Some(Transaction {
+                    signatures: transaction
+                        .signatures
+                        .iter()
+                        .map(|signature| signature.as_ref().to_vec())
+                        .collect(),
+                    message: Some(message),
+                }),
+                meta: Some(status),
+                ..Default::default()
+            }),
+        }
+        .try_into()
+    }
+}
+
+impl ParsedTransaction {
+    /// The fee payer.
+    pub fn signer(&self) -> Option<Pubkey> {
+        self.accounts.first().map(|account| account.pubkey)
+    }
+
+    pub fn account_keys(&self) -> Vec<Pubkey> {
+        self.accounts.iter().map(|account| account.pubkey).collect()
+    }
+
+    /// Outer instructions calling `program_id`.
+    pub fn find_instructions(
+        &self,
+        program_id: &Pubkey,
+    ) -> impl Iterator<Item = &ParsedInstruction> {
+        let program_id = *program_id;
+        self.instructions
+            .iter()
+            .filter(move |instruction| instruction.program_id == program_id)
+    }
+
+    /// Outer and inner instructions calling `program_id`, in execution order.
+    pub fn find_all_instructions(
+        &self,
+        program_id: &Pubkey,
+    ) -> impl Iterator<Item = &ParsedInstruction> {
+        let program_id = *program_id;
+        self.instructions
+            .iter()
+            .flat_map(|instruction| std::iter::once(instruction).chain(&instruction.inner))
+            .filter(move |instruction| instruction.program_id == program_id)
+    }
+
+    /// Lamports `pubkey` gained, negative when it paid.
+    pub fn sol_change(&self, pubkey: &Pubkey) -> i128 {
+        self.accounts
+            .iter()
+            .filter(|account| account.pubkey == *pubkey)
+            .map(|account| account.post_balance as i128 - account.pre_balance as i128)
+            .sum()
+    }
+
+    /// Raw `mint` tokens `owner` gained, negative when they were sold.
+    pub fn token_change(&self, mint: &Pubkey, owner: &Pubkey) -> i128 {
+        self.token_balances
+            .get(&(*mint, *owner))
+            .map(TokenBalance::change)
+            .unwrap_or_default()
+    }
+}
+
+/// The static keys are ordered writable signers, readonly signers, writable
+/// non-signers and readonly non-signers.
+fn is_static_writable(header: &MessageHeader, static_len: usize, index: usize) -> bool {
+    let signers = header.num_required_signatures as usize;
+    if index < signers {
+        index < signers.saturating_sub(header.num_readonly_signed_accounts as usize)
+    } else {
+        index < static_len.saturating_sub(header.num_readonly_unsigned_accounts as usize)
+    }
+}
+
+fn resolve(
+    keys: &[Pubkey],
+    program_id_index: u32,
+    accounts: &[u8],
+    data: &[u8],
+) -> eyre::Result<ParsedInstruction> {
+    let key = |index: usize| {
+        keys.get(index)
+            .copied()
+            .ok_or_else(|| eyre::eyre!("Account index {} of {} accounts", index, keys.len()))
+    };
+
+    Ok(ParsedInstruction {
+        program_id: key(program_id_index as usize)?,
+        accounts: accounts
+            .iter()
+            .map(|index| key(*index as usize))
+            .collect::<eyre::Result<_>>()?,
+        data: data.to_vec(),
+        inner: Vec::new(),
+    })
+}
+
+/// Balances of accounts without a known owner cannot be keyed and are left
+/// out.
+fn token_amount(balance: &ProtoTokenBalance) -> Option<(Pubkey, Pubkey, u64, u32)> {
+    let amount = balance.ui_token_amount.clone().unwrap_or_default();
+    Some((
+        balance.mint.parse().ok()?,
+        balance.owner.parse().ok()?,
+        amount.amount.parse().unwrap_or_default(),
+        amount.decimals,
+    ))
+}
+
+fn compiled_inner_instruction(instruction: UiInstruction) -> eyre::Result<InnerInstruction> {
+    match instruction {
+        UiInstruction::Compiled(instruction) => Ok(InnerInstruction {
+            program_id_index: instruction.program_id_index as u32,
+            accounts: instruction.accounts,
+            data: bs58::decode(&instruction.data).into_vec()?,
+            stack_height: instruction.stack_height,
+        }),
+        UiInstruction::Parsed(_) => Err(eyre::eyre!("Inner instruction is not compiled")),
+    }
+}
+
+fn proto_token_balances(
+    balances: Option<Vec<UiTransactionTokenBalance>>,
+) -> Vec<ProtoTokenBalance> {
+    balances
+        .unwrap_or_default()
+        .into_iter()
+        .map(|balance| ProtoTokenBalance {
+            account_index: balance.account_index as u32,
+            mint: balance.mint,
+            ui_token_amount: Some(UiTokenAmount {
+                ui_amount: balance.ui_token_amount.ui_amount.unwrap_or_default(),
+                decimals: balance.ui_token_amount.decimals as u32,
+                amount: balance.ui_token_amount.amount,
+                ui_amount_string: balance.ui_token_amount.ui_amount_string,
+            }),
+            owner: Option::from(balance.owner).unwrap_or_default(),
+            program_id: Option::from(balance.program_id).unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn pubkey_bytes(keys: &[String]) -> eyre::Result<Vec<Vec<u8>>> {
+    keys.iter()
+        .map(|key| Ok(key.parse::<Pubkey>()?.to_bytes().to_vec()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn token_balance(mint: Pubkey, owner: Pubkey, amount: u64) -> ProtoTokenBalance {
+        ProtoTokenBalance {
+            mint: mint.to_string(),
+            owner: owner.to_string(),
+            ui_token_amount: Some(UiTokenAmount {
+                amount: amount.to_string(),
+                decimals: 6,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn update() -> SubscribeUpdateTransaction {
+        // Payer, a readonly signer, a writable and a readonly program, then one
+        // writable and one readonly lookup-table address.
+        let keys = (1..=4).map(|byte| key(byte).to_bytes().to_vec()).collect();
+        SubscribeUpdateTransaction {
+            slot: 7,
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: vec![9; 64],
+                transaction: Some(Transaction {
+                    message: Some(Message {
+                        header: Some(MessageHeader {
+                            num_required_signatures: 2,
+                            num_readonly_signed_accounts: 1,
+                            num_readonly_unsigned_accounts: 1,
+                        }),
+                        account_keys: keys,
+                        instructions: vec![CompiledInstruction {
+                            program_id_index: 3,
+                            accounts: vec![0, 4, 5],
+                            data: vec![1],
+                        }],
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                meta: Some(TransactionStatusMeta {
+                    pre_balances: vec![100, 0, 0, 0, 0, 0],
+                    post_balances: vec![40, 0, 60, 0, 0, 0],
+                    loaded_writable_addresses: vec![key(5).to_bytes().to_vec()],
+                    loaded_readonly_addresses: vec![key(6).to_bytes().to_vec()],
+                    inner_instructions: vec![InnerInstructions {
+                        index: 0,
+                        instructions: vec![InnerInstruction {
+                            program_id_index: 5,
+                            accounts: vec![2],
+                            data: vec![2],
+                            stack_height: Some(2),
+                        }],
+                    }],
+                    pre_token_balances: vec![token_balance(key(6), key(1), 10)],
+                    post_token_balances: vec![token_balance(key(6), key(1), 35)],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn resolves_accounts_instructions_and_balances() {
+        let tx = ParsedTransaction::try_from(update()).unwrap();
+
+        assert_eq!(tx.slot, 7);
+        assert_eq!(tx.signer(), Some(key(1)));
+        let flags: Vec<_> = tx
+            .accounts
+            .iter()
+            .map(|account| (account.is_signer, account.is_writable))
+            .collect();
+        assert_eq!(
+            flags,
+            [
+                (true, true),
+                (true, false),
+                (false, true),
+                (false, false),
+                (false, true),
+                (false, false)
+            ]
+        );
+
+        let outer: Vec<_> = tx.find_instructions(&key(4)).collect();
+        assert_eq!(outer.len(), 1);
+        assert_eq!(outer[0].accounts, [key(1), key(5), key(6)]);
+        assert_eq!(outer[0].inner[0].program_id, key(6));
+        assert_eq!(tx.find_all_instructions(&key(6)).count(), 1);
+
+        assert_eq!(tx.sol_change(&key(1)), -60);
+        assert_eq!(tx.token_change(&key(6), &key(1)), 25);
+    }
+
+    #[test]
+    fn out_of_range_index_is_an_error() {
+        let mut update = update();
+        let message = update
+            .transaction
+            .as_mut()
+            .and_then(|info| info.transaction.as_mut())
+            .and_then(|transaction| transaction.message.as_mut())
+            .unwrap();
+        message.instructions[0].accounts.push(6);
+
+        assert!(ParsedTransaction::try_from(update).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+use tokio::sync::mpsc;
+use yellowstone_grpc_proto::{geyser::SubscribeUpdate, prost::Message};
+
+use crate::env::SettingsConfig;
+
+/// Writes every update of a stream to a file, each one as the microseconds
+/// since recording started and the length of the protobuf that follows, both
+/// little endian.
+pub struct Recorder {
+    file: BufWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Recorder {
+            file: BufWriter::new(File::create(path)?),
+            started: Instant::now(),
+        })
+    }
+
+    /// Records to `network.record_path`, `None` when it is empty.
+    pub fn from_config(args: &SettingsConfig) -> eyre::Result<Option<Self>> {
+        let path = &args.network.record_path;
+        if path.is_empty() {
+            return Ok(None);
+        }
+        Recorder::create(path)
+            .map(Some)
+            .map_err(|e| eyre::eyre!("Cannot record to {}: {}", path, e))
+    }
+
+    /// Appends `update`, flushed so a killed run keeps what it saw.
+    pub fn write(&mut self, update: &SubscribeUpdate) -> io::Result<()> {
+        self.write_at(self.started.elapsed(), update)
+    }
+
+    /// Writes from a blocking task from now on, so a slow disk never holds up
+    /// the stream being recorded.
+    pub fn spawn(mut self) -> RecorderSink {
+        let (updates, mut pending) = mpsc::unbounded_channel::<(Duration, SubscribeUpdate)>();
+        let started = self.started;
+        tokio::task::spawn_blocking(move || {
+            while let Some((elapsed, update)) = pending.blocking_recv() {
+                if let Err(e) = self.write_at(elapsed, &update) {
+                    warn!("Stopped recording: {}", e);
+                    return;
+                }
+            }
+        });
+        RecorderSink { updates, started }
+    }
+
+    fn write_at(&mut self, elapsed: Duration, update: &SubscribeUpdate) -> io::Result<()> {
+        let elapsed = elapsed.as_micros() as u64;
+        let data = update.encode_to_vec();
+        self.file.write_all(&elapsed.to_le_bytes())?;
+        self.file.write_all(&(data.len() as u32).to_le_bytes())?;
+        self.file.write_all(&data)?;
+        self.file.flush()
+    }
+}
+
+/// Queues updates for a [`Recorder`] writing in the background.
+pub struct RecorderSink {
+    updates: mpsc::UnboundedSender<(Duration, SubscribeUpdate)>,
+    started: Instant,
+}
+
+impl RecorderSink {
+    /// Queues `update`, received at `received`. `false` once the writer has
+    /// stopped.
+    pub fn record(&self, received: Instant, update: SubscribeUpdate) -> bool {
+        let elapsed = received.saturating_duration_since(self.started);
+        self.updates.send((elapsed, update)).is_ok()
+    }
+}
+
+/// Reads back what a [`Recorder`] wrote.
+pub struct Recording<R> {
+    reader: R,
+}
+
+impl Recording<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Recording::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read> Recording<R> {
+    pub fn new(reader: R) -> Self {
+        Recording { reader }
+    }
+
+    /// The next update with when it was received after recording started.
+    /// `None` at the end of the file, an error for a truncated record.
+    pub fn next_update(&mut self) -> eyre::Result<Option<(Duration, SubscribeUpdate)>> {
+        let mut elapsed = [0; 8];
+        match self.reader.read_exact(&mut elapsed) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let mut len = [0; 4];
+        self.reader.read_exact(&mut len)?;
+        let mut data = vec![0; u32::from_le_bytes(len) as usize];
+        self.reader.read_exact(&mut data)?;
+
+        Ok(Some((
+            Duration::from_micros(u64::from_le_bytes(elapsed)),
+            SubscribeUpdate::decode(data.as_slice())?,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yellowstone_grpc_proto::geyser::{subscribe_update::UpdateOneof, SubscribeUpdateSlot};
+
+    use super::*;
+
+    #[test]
+    fn recorded_updates_read_back_in_order() {
+        let path = std::env::temp_dir().join(format!("mevarik-record-{}.bin", std::process::id()));
+        let updates: Vec<SubscribeUpdate> = (1..=3)
+            .map(|slot| SubscribeUpdate {
+                filters: vec!["PumpFun".to_string()],
+                update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot {
+                    slot,
+                    parent: None,
+                    status: 0,
+                })),
+            })
+            .collect();
+
+        let mut recorder = Recorder::create(&path).unwrap();
+        for update in &updates {
+            recorder.write(update).unwrap();
+        }
+
+        let mut recording = Recording::open(&path).unwrap();
+        let mut read = Vec::new();
+        let mut last = Duration::ZERO;
+        while let Some((elapsed, update)) = recording.next_update().unwrap() {
+            assert!(elapsed >= last);
+            last = elapsed;
+            read.push(update);
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read, updates);
+    }
+
+    #[tokio::test]
+    async fn sink_writes_in_the_background() {
+        let path = std::env::temp_dir().join(format!("mevarik-sink-{}.bin", std::process::id()));
+        let update = SubscribeUpdate {
+            filters: vec!["PumpFun".to_string()],
+            update_oneof: Some(UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot: 7,
+                parent: None,
+                status: 0,
+            })),
+        };
+
+        let sink = Recorder::create(&path).unwrap().spawn();
+        assert!(sink.record(Instant::now(), update.clone()));
+        drop(sink);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let recorded = loop {
+            if let Some((_, recorded)) = Recording::open(&path).unwrap().next_update().unwrap() {
+                break recorded;
+            }
+            assert!(Instant::now() < deadline, "the update was never written");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(recorded, update);
+    }
+}
@@ -0,0 +1,265 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use futures::{SinkExt, StreamExt};
+use log::{info, warn};
+use tokio::{
+    sync::{mpsc, watch},
+    time::{interval, sleep, Instant, MissedTickBehavior},
+};
+use yellowstone_grpc_proto::geyser::{
+    subscribe_update::UpdateOneof, SubscribeRequest, SubscribeRequestPing, SubscribeUpdate,
+};
+
+use crate::{
+    env::SettingsConfig,
+    plugins::yellowstone_plugin::lib::{GeyserGrpcClient, GeyserGrpcClientResult},
+};
+
+/// Keeps load balancers from closing an idle stream.
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A stream silent for this long, pongs included, is reconnected.
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Updates held for a consumer that is still busy with earlier ones.
+const UPDATE_BUFFER: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamState {
+    Connecting,
+    Connected,
+    Reconnecting {
+        attempt: u32,
+        retry_in: Duration,
+        error: String,
+    },
+    /// The consumer went away, nothing is streamed any more.
+    Closed,
+}
+
+impl fmt::Display for StreamState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamState::Connecting => write!(f, "Connecting to Geyser"),
+            StreamState::Connected => write!(f, "Connected to Geyser"),
+            StreamState::Reconnecting {
+                attempt,
+                retry_in,
+                error,
+            } => write!(
+                f,
+                "Geyser stream lost ({}), reconnecting in {:.1}s (attempt {})",
+                error,
+                retry_in.as_secs_f64(),
+                attempt
+            ),
+            StreamState::Closed => write!(f, "Geyser stream closed"),
+        }
+    }
+}
+
+/// A Geyser subscription that survives dropped streams. The latest request is
+/// sent again on every reconnect, and pings are answered here without reaching
+/// the consumer.
+#[derive(Debug, Clone)]
+pub struct GeyserSubscription {
+    endpoint: String,
+    x_token: Option<String>,
+    request: SubscribeRequest,
+}
+
+/// Updates of a started [`GeyserSubscription`], in the order received.
+pub struct GeyserStream {
+    updates: mpsc::Receiver<SubscribeUpdate>,
+    state: watch::Receiver<StreamState>,
+    requests: RequestHandle,
+}
+
+/// Changes what a started [`GeyserSubscription`] follows, on the stream it
+/// already has open.
+#[derive(Clone)]
+pub struct RequestHandle(Arc<watch::Sender<SubscribeRequest>>);
+
+impl GeyserSubscription {
+    pub fn new(endpoint: String, x_token: Option<String>, request: SubscribeRequest) -> Self {
+        GeyserSubscription {
+            endpoint,
+            x_token,
+            request,
+        }
+    }
+
+    /// Subscribes to `network.grpc_url` with `network.grpc_x_token`, if set.
+    pub fn from_config(args: &SettingsConfig, request: SubscribeRequest) -> Self {
+        let x_token = Some(args.network.grpc_x_token.clone()).filter(|token| !token.is_empty());
+        Self::new(args.network.grpc_url.clone(), x_token, request)
+    }
+
+    /// Starts streaming in the background. Only a malformed endpoint or token
+    /// is an error, everything else is retried until the stream is dropped.
+    #[allow(clippy::result_large_err)]
+    pub fn start(self) -> GeyserGrpcClientResult<GeyserStream> {
+        let client = GeyserGrpcClient::connect(self.endpoint.clone(), self.x_token.clone(), None)?;
+
+        let (updates_tx, updates) = mpsc::channel(UPDATE_BUFFER);
+        let (state_tx, state) = watch::channel(StreamState::Connecting);
+        let (requests_tx, mut requests) = watch::channel(self.request.clone());
+        tokio::spawn(async move {
+            let mut client = client;
+            let mut retry = ExponentialBackoff {
+                max_interval: MAX_RETRY_INTERVAL,
+                max_elapsed_time: None,
+                ..Default::default()
+            };
+            let mut attempt = 0;
+
+            loop {
+                set_state(&state_tx, StreamState::Connecting);
+                let error = match self
+                    .stream_once(
+                        &mut client,
+                        &mut requests,
+                        &updates_tx,
+                        &state_tx,
+                        &mut retry,
+                        &mut attempt,
+                    )
+                    .await
+                {
+                    Ok(()) => break,
+                    Err(e) => e,
+                };
+
+                attempt += 1;
+                let retry_in = retry.next_backoff().unwrap_or(MAX_RETRY_INTERVAL);
+                set_state(
+                    &state_tx,
+                    StreamState::Reconnecting {
+                        attempt,
+                        retry_in,
+                        error: error.to_string(),
+                    },
+                );
+                tokio::select! {
+                    _ = sleep(retry_in) => {}
+                    _ = updates_tx.closed() => break,
+                }
+            }
+            set_state(&state_tx, StreamState::Closed);
+        });
+
+        Ok(GeyserStream {
+            updates,
+            state,
+            requests: RequestHandle(Arc::new(requests_tx)),
+        })
+    }
+
+    /// Streams until the connection fails, or returns `Ok` once the consumer
+    /// is gone.
+    async fn stream_once(
+        &self,
+        client: &mut GeyserGrpcClient<impl tonic::service::Interceptor>,
+        requests: &mut watch::Receiver<SubscribeRequest>,
+        updates: &mpsc::Sender<SubscribeUpdate>,
+        state: &watch::Sender<StreamState>,
+        retry: &mut ExponentialBackoff,
+        attempt: &mut u32,
+    ) -> eyre::Result<()> {
+        let request = requests.borrow_and_update().clone();
+        let (mut subscribe_tx, mut stream) = client.subscribe_with_request(Some(request)).await?;
+        set_state(state, StreamState::Connected);
+
+        let mut ping = interval(PING_INTERVAL);
+        ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut ping_id = 0;
+        let mut last_update = Instant::now();
+        let mut healthy = false;
+
+        loop {
+            tokio::select! {
+                _ = ping.tick() => {
+                    if last_update.elapsed() > STALE_AFTER {
+                        return Err(eyre::eyre!("no update for {}s", STALE_AFTER.as_secs()));
+                    }
+                    ping_id += 1;
+                    subscribe_tx
+                        .send(SubscribeRequest {
+                            ping: Some(SubscribeRequestPing { id: ping_id }),
+                            ..Default::default()
+                        })
+                        .await?;
+                }
+                // A new request replaces the filters of the open stream.
+                Ok(()) = requests.changed() => {
+                    let request = requests.borrow_and_update().clone();
+                    subscribe_tx.send(request).await?;
+                }
+                message = stream.next() => {
+                    let update = match message {
+                        Some(update) => update?,
+                        None => return Err(eyre::eyre!("stream ended")),
+                    };
+                    last_update = Instant::now();
+                    // A stream that delivers is worth retrying quickly again.
+                    if !healthy {
+                        healthy = true;
+                        retry.reset();
+                        *attempt = 0;
+                    }
+
+                    if matches!(
+                        update.update_oneof,
+                        Some(UpdateOneof::Ping(_)) | Some(UpdateOneof::Pong(_))
+                    ) {
+                        continue;
+                    }
+                    if updates.send(update).await.is_err() {
+                        return Ok(());
+                    }
+                }
+                _ = updates.closed() => return Ok(()),
+            }
+        }
+    }
+}
+
+impl GeyserStream {
+    /// The next update, reconnecting as needed. `None` once the stream is
+    /// closed.
+    pub async fn next(&mut self) -> Option<SubscribeUpdate> {
+        self.updates.recv().await
+    }
+
+    pub fn state(&self) -> StreamState {
+        self.state.borrow().clone()
+    }
+
+    /// Follows the connection state, e.g. to show it in a status line.
+    pub fn watch_state(&self) -> watch::Receiver<StreamState> {
+        self.state.clone()
+    }
+
+    /// Updates the request after the stream has started.
+    pub fn requests(&self) -> RequestHandle {
+        self.requests.clone()
+    }
+}
+
+impl RequestHandle {
+    /// Sends `request` on the open stream, and on every reconnect after.
+    pub fn update(&self, request: SubscribeRequest) {
+        self.0.send_replace(request);
+    }
+}
+
+fn set_state(state: &watch::Sender<StreamState>, new_state: StreamState) {
+    match &new_state {
+        StreamState::Reconnecting { .. } => warn!("{}", new_state),
+        _ => info!("{}", new_state),
+    }
+    state.send_replace(new_state);
+}
@@ -2,13 +2,25 @@
 
 use solana_program::{
     decode_error::DecodeError,
+    instruction::InstructionError,
     msg,
     program_error::{PrintProgramError, ProgramError},
+    pubkey::Pubkey,
+    system_instruction::SystemError,
+    system_program,
 };
+use solana_sdk::transaction::TransactionError;
+use spl_token::error::TokenError;
 use thiserror::Error;
 
+use crate::{
+    liquidity::pool_ixs::AMM_PROGRAM,
+    moonshot::instructions::{errors::TokenLaunchpadError, instructions::MOONSHOT_TOKEN_LAUNCHPAD},
+    pumpfun::pump_interface::{errors::PumpError, PUMPFUN_PROGRAM},
+};
+
 /// Errors that may be returned by the TokenAmm program.
-#[derive(Clone, Debug, Error)]
+#[derive(Clone, Debug, Error, num_derive::FromPrimitive)]
 pub enum AmmError {
     // 0
     /// The account cannot be initialized because it is already being used.
@@ -302,3 +314,63 @@ impl PrintProgramError for AmmError {
         }
     }
 }
+
+fn decode<E: num_traits::FromPrimitive + ToString>(code: u32) -> Option<String> {
+    E::from_u32(code).map(|e| e.to_string())
+}
+
+/// Describes why a transaction failed. Custom error codes are looked up in the
+/// error enum of the program that owns the failing instruction; `program_ids`
+/// holds the program of each top-level instruction, in order.
+pub fn describe_transaction_error(error: &TransactionError, program_ids: &[Pubkey]) -> String {
+    let (index, code) = match error {
+        TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+            (*index, *code)
+        }
+        TransactionError::InstructionError(index, e) => {
+            return format!("instruction {}: {}", index, e)
+        }
+        e => return e.to_string(),
+    };
+
+    let decoded = program_ids
+        .get(index as usize)
+        .and_then(|program| match *program {
+            p if p == AMM_PROGRAM => decode::<AmmError>(code),
+            p if p == PUMPFUN_PROGRAM => decode::<PumpError>(code),
+            p if p == MOONSHOT_TOKEN_LAUNCHPAD => decode::<TokenLaunchpadError>(code),
+            p if p == spl_token::id() => decode::<TokenError>(code),
+            p if p == system_program::id() => decode::<SystemError>(code),
+            _ => None,
+        });
+
+    match decoded {
+        Some(message) => format!("instruction {}: {}", index, message),
+        None => format!("instruction {}: custom program error {:#x}", index, code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_by_failing_program() {
+        let programs = [solana_sdk::compute_budget::id(), AMM_PROGRAM, PUMPFUN_PROGRAM];
+
+        let slippage = TransactionError::InstructionError(1, InstructionError::Custom(30));
+        assert_eq!(
+            describe_transaction_error(&slippage, &programs),
+            "instruction 1: instruction exceeds desired slippage limit"
+        );
+
+        let pump = TransactionError::InstructionError(2, InstructionError::Custom(6002));
+        assert!(describe_transaction_error(&pump, &programs).contains("Too much SOL required"));
+
+        let unknown = TransactionError::InstructionError(0, InstructionError::Custom(7));
+        assert_eq!(
+            describe_transaction_error(&unknown, &programs),
+            "instruction 0: custom program error 0x7"
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use demand::Input;
+
+use super::{print_entries, JournalQuery};
+
+/// Newest entries shown by the menu.
+const MENU_LIMIT: usize = 25;
+
+pub async fn journal_menu() -> eyre::Result<()> {
+    let mint = Input::new("Filter by mint")
+        .placeholder("Leave empty for every trade")
+        .prompt("Mint: ")
+        .run()?;
+
+    let query = JournalQuery {
+        mint: Some(mint.trim().to_string()).filter(|mint| !mint.is_empty()),
+        limit: Some(MENU_LIMIT),
+        ..Default::default()
+    };
+    print_entries(&query.run()?);
+
+    Ok(())
+}
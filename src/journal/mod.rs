@@ -0,0 +1,428 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    native_token::lamports_to_sol, pubkey::Pubkey, system_instruction::SystemInstruction,
+    system_program, transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+
+use crate::{
+    latency::{report::describe, Latency},
+    liquidity::utils::tip_program_id,
+    plugins::jito_plugin::lib::generate_tip_accounts,
+    raydium_amm::swap::instructions::TAX_ACCOUNT,
+    router::SniperRoute,
+    sender::{fetch_confirmed, SendOutcome, SendReport},
+};
+
+pub mod menu;
+
+pub const JOURNAL_PATH: &str = "journal.jsonl";
+
+/// Serialises appends from concurrent sends so lines never interleave.
+static JOURNAL_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// What the caller meant to do, attached to a send so it can be journaled.
+#[derive(Debug, Clone)]
+pub struct Order {
+    pub route: SniperRoute,
+    pub side: TradeSide,
+    pub mint: Pubkey,
+    /// Lamports spent for a buy, raw token amount for a sell.
+    pub amount: u64,
+}
+
+impl Order {
+    pub fn new(route: SniperRoute, side: TradeSide, mint: Pubkey, amount: u64) -> Self {
+        Order {
+            route,
+            side,
+            mint,
+            amount,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TradeStatus {
+    Landed,
+    Failed { error: String },
+    Expired,
+}
+
+/// The wallet's actual balance changes, reconciled from the transaction meta.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fill {
+    /// Lamports in (+) or out (-) of the wallet, fees, tip, tax and rent included.
+    pub sol_change: i64,
+    /// Raw token amount in (+) or out (-) of the wallet.
+    pub token_change: i128,
+    pub fee: u64,
+    pub tip: u64,
+    pub tax: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeEntry {
+    /// Unix seconds.
+    pub timestamp: i64,
+    pub route: SniperRoute,
+    pub side: TradeSide,
+    pub wallet: String,
+    pub mint: String,
+    pub amount: u64,
+    pub signature: Option<String>,
+    pub slot: Option<u64>,
+    pub status: TradeStatus,
+    pub fill: Option<Fill>,
+    /// Stage timings of a sniped or copied trade, absent for manual trades.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<Latency>,
+}
+
+pub fn append(entry: &TradeEntry) -> eyre::Result<()> {
+    let line = serde_json::to_string(entry)?;
+    let _guard = JOURNAL_LOCK.lock().unwrap();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(JOURNAL_PATH)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// Reads every entry, oldest first. Lines that do not parse are skipped so a
+/// torn write never hides the rest of the journal.
+pub fn read_entries() -> eyre::Result<Vec<TradeEntry>> {
+    let content = match fs::read_to_string(JOURNAL_PATH) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(index, line)| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                warn!("Skipping {} line {}: {}", JOURNAL_PATH, index + 1, e);
+                None
+            }
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct JournalQuery {
+    pub mint: Option<String>,
+    pub wallet: Option<String>,
+    /// Route name, matched case-insensitively, e.g. `pumpfun`.
+    pub route: Option<String>,
+    /// Keep only the newest `limit` matches.
+    pub limit: Option<usize>,
+}
+
+impl JournalQuery {
+    pub fn matches(&self, entry: &TradeEntry) -> bool {
+        self.mint.as_ref().is_none_or(|mint| &entry.mint == mint)
+            && self.wallet.as_ref().is_none_or(|wallet| &entry.wallet == wallet)
+            && self.route.as_ref().is_none_or(|route| {
+                format!("{:?}", entry.route).eq_ignore_ascii_case(route)
+            })
+    }
+
+    pub fn run(&self) -> eyre::Result<Vec<TradeEntry>> {
+        let mut entries: Vec<TradeEntry> = read_entries()?
+            .into_iter()
+            .filter(|entry| self.matches(entry))
+            .collect();
+        if let Some(limit) = self.limit {
+            let skip = entries.len().saturating_sub(limit);
+            entries.drain(..skip);
+        }
+        Ok(entries)
+    }
+}
+
+pub fn print_entries(entries: &[TradeEntry]) {
+    if entries.is_empty() {
+        println!("No trades recorded yet.");
+        return;
+    }
+
+    println!(
+        "{:<19} {:<16} {:<4} {:<44} {:>16} {:<8} {:>14} {:>20}  Signature",
+        "Time", "Route", "Side", "Mint", "Amount", "Status", "SOL", "Tokens"
+    );
+    for entry in entries {
+        let time = chrono::DateTime::from_timestamp(entry.timestamp, 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let status = match &entry.status {
+            TradeStatus::Landed => "landed",
+            TradeStatus::Failed { .. } => "failed",
+            TradeStatus::Expired => "expired",
+        };
+        let (sol, tokens) = match &entry.fill {
+            Some(fill) => (
+                format!("{:+.6}", fill.sol_change as f64 / 1e9),
+                format!("{:+}", fill.token_change),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        println!(
+            "{:<19} {:<16} {:<4} {:<44} {:>16} {:<8} {:>14} {:>20}  {}",
+            time,
+            format!("{:?}", entry.route),
+            format!("{:?}", entry.side),
+            entry.mint,
+            entry.amount,
+            status,
+            sol,
+            tokens,
+            entry.signature.as_deref().unwrap_or("-")
+        );
+        if let TradeStatus::Failed { error } = &entry.status {
+            println!("    {}", error);
+        }
+        if let Some(latency) = &entry.latency {
+            println!("    {}", describe(latency));
+        }
+    }
+
+    let landed: Vec<&Fill> = entries
+        .iter()
+        .filter(|e| e.status == TradeStatus::Landed)
+        .filter_map(|e| e.fill.as_ref())
+        .collect();
+    let net: i64 = landed.iter().map(|f| f.sol_change).sum();
+    let costs: u64 = landed.iter().map(|f| f.fee + f.tip + f.tax).sum();
+    println!(
+        "{} trade(s), {} landed, net {:+.6} SOL, fees/tips/tax {:.6} SOL",
+        entries.len(),
+        landed.len(),
+        net as f64 / 1e9,
+        lamports_to_sol(costs)
+    );
+}
+
+/// Builds and appends the journal entry for a finished send. `transactions` are
+/// every transaction of the send, the first one being the trade itself, and
+/// `latency` how long it took from its trigger. Returns the reconciled fill of
+/// a landed send.
+pub async fn record(
+    rpc_client: Arc<RpcClient>,
+    order: Order,
+    report: SendReport,
+    transactions: Vec<VersionedTransaction>,
+    latency: Option<Latency>,
+) -> Option<Fill> {
+    let wallet = transactions
+        .first()
+        .and_then(|tx| tx.message.static_account_keys().first().copied())
+        .unwrap_or_default();
+
+    let (status, slot) = match &report.outcome {
+        SendOutcome::Landed { slot, .. } => (TradeStatus::Landed, Some(*slot)),
+        SendOutcome::Failed { error } => (
+            TradeStatus::Failed {
+                error: error.clone(),
+            },
+            None,
+        ),
+        SendOutcome::Expired => (TradeStatus::Expired, None),
+    };
+
+    let mut fill = None;
+    if let (TradeStatus::Landed, Some(signature)) = (&status, report.signature()) {
+        match fetch_confirmed(&rpc_client, &signature).await {
+            Some((_, meta)) => {
+                fill = Some(reconcile(&meta, &wallet, &order.mint, &transactions));
+            }
+            None => warn!("No transaction details for {}, journaling without fill", signature),
+        }
+    }
+
+    let entry = TradeEntry {
+        timestamp: chrono::Utc::now().timestamp(),
+        route: order.route,
+        side: order.side,
+        wallet: wallet.to_string(),
+        mint: order.mint.to_string(),
+        amount: order.amount,
+        signature: report.signature().map(|s| s.to_string()),
+        slot,
+        status,
+        fill,
+        latency,
+    };
+
+    if let Err(e) = append(&entry) {
+        warn!("Cannot write to {}: {}", JOURNAL_PATH, e);
+    }
+    entry.fill
+}
+
+/// The fee payer is always account 0, so its SOL change is read from there.
+pub fn reconcile(
+    meta: &UiTransactionStatusMeta,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    transactions: &[VersionedTransaction],
+) -> Fill {
+    let sol_change = match (meta.pre_balances.first(), meta.post_balances.first()) {
+        (Some(pre), Some(post)) => *post as i64 - *pre as i64,
+        _ => 0,
+    };
+
+    let token_change = token_balance(&meta.post_token_balances, wallet, mint)
+        - token_balance(&meta.pre_token_balances, wallet, mint);
+
+    let tip_accounts = generate_tip_accounts(&tip_program_id());
+    let mut tip = 0;
+    let mut tax = 0;
+    for (destination, lamports) in transactions.iter().flat_map(system_transfers) {
+        if destination == TAX_ACCOUNT {
+            tax += lamports;
+        } else if tip_accounts.contains(&destination) {
+            tip += lamports;
+        }
+    }
+
+    Fill {
+        sol_change,
+        token_change,
+        fee: meta.fee,
+        tip,
+        tax,
+    }
+}
+
+fn token_balance(
+    balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+) -> i128 {
+    let wallet = wallet.to_string();
+    let mint = mint.to_string();
+    match balances {
+        OptionSerializer::Some(balances) => balances
+            .iter()
+            .filter(|b| b.mint == mint && b.owner.as_ref() == OptionSerializer::Some(&wallet))
+            .filter_map(|b| i128::from_str(&b.ui_token_amount.amount).ok())
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// Destination and amount of every top-level system transfer in `transaction`.
+fn system_transfers(transaction: &VersionedTransaction) -> Vec<(Pubkey, u64)> {
+    let keys = transaction.message.static_account_keys();
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .filter(|ix| keys.get(ix.program_id_index as usize) == Some(&system_program::id()))
+        .filter_map(|ix| match bincode::deserialize(&ix.data) {
+            Ok(SystemInstruction::Transfer { lamports }) => ix
+                .accounts
+                .get(1)
+                .and_then(|index| keys.get(*index as usize))
+                .map(|destination| (*destination, lamports)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{
+        hash::Hash,
+        message::{v0::Message, VersionedMessage},
+        signature::{Keypair, Signature},
+        signer::Signer,
+        system_instruction::transfer,
+    };
+
+    use super::*;
+
+    #[test]
+    fn tip_and_tax_are_split_out() {
+        let payer = Keypair::new();
+        let tip = generate_tip_accounts(&tip_program_id())[3];
+        let instructions = [
+            transfer(&payer.pubkey(), &TAX_ACCOUNT, 5_000),
+            transfer(&payer.pubkey(), &tip, 10_000),
+            transfer(&payer.pubkey(), &Pubkey::new_unique(), 1),
+        ];
+        let message = Message::try_compile(&payer.pubkey(), &instructions, &[], Hash::default())
+            .unwrap();
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(message),
+        };
+
+        let meta: UiTransactionStatusMeta = serde_json::from_value(serde_json::json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5000,
+            "preBalances": [1_000_000, 0, 0, 0, 0],
+            "postBalances": [975_000, 5_000, 10_000, 1, 0],
+        }))
+        .unwrap();
+
+        let fill = reconcile(&meta, &payer.pubkey(), &Pubkey::new_unique(), &[transaction]);
+        assert_eq!(fill.sol_change, -25_000);
+        assert_eq!(fill.token_change, 0);
+        assert_eq!((fill.fee, fill.tip, fill.tax), (5_000, 10_000, 5_000));
+    }
+
+    #[test]
+    fn query_filters_by_route_case_insensitively() {
+        let entry = TradeEntry {
+            timestamp: 0,
+            route: SniperRoute::PumpFun,
+            side: TradeSide::Buy,
+            wallet: "w".to_string(),
+            mint: "m".to_string(),
+            amount: 1,
+            signature: None,
+            slot: None,
+            status: TradeStatus::Expired,
+            fill: None,
+            latency: None,
+        };
+        let query = JournalQuery {
+            route: Some("pumpfun".to_string()),
+            ..Default::default()
+        };
+        assert!(query.matches(&entry));
+        assert!(!JournalQuery {
+            mint: Some("other".to_string()),
+            ..Default::default()
+        }
+        .matches(&entry));
+
+        let line = serde_json::to_string(&entry).unwrap();
+        assert_eq!(serde_json::from_str::<TradeEntry>(&line).unwrap(), entry);
+    }
+}
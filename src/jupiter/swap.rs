@@ -1,8 +1,11 @@
-use solana_sdk::transaction::VersionedTransaction;
+use std::sync::Arc;
 
 use crate::{
     app::config_init::get_config,
+    journal::{Order, TradeSide},
     jupiter::jup_utils::{quote, swap, QuoteConfig, Swap, SwapRequest},
+    router::SniperRoute,
+    sender::{SendReport, TransactionSender},
 };
 
 use super::interface::RouteKeys;
@@ -14,11 +17,14 @@ use {
         hash::Hash,
         pubkey,
         signature::{Keypair, Signer},
+        transaction::VersionedTransaction,
     },
     spl_token::{amount_to_ui_amount, ui_amount_to_amount},
 };
 
-pub async fn jup_swap(route: RouteKeys) -> Result<(), Box<dyn std::error::Error>> {
+/// Buys the destination mint of `route` for `trading.buy_amount` SOL, sending
+/// the transaction Jupiter builds through the [`TransactionSender`].
+pub async fn jup_swap(route: RouteKeys) -> eyre::Result<SendReport> {
     let config = get_config().await?;
 
     let sol = pubkey!("So11111111111111111111111111111111111111112");
@@ -26,42 +32,26 @@ pub async fn jup_swap(route: RouteKeys) -> Result<(), Box<dyn std::error::Error>
 
     let keypair = Keypair::from_base58_string(&config.engine.payer_keypair);
 
-    let rpc_client = RpcClient::new_with_commitment(
-        config.network.rpc_url.into(),
+    let rpc_client = Arc::new(RpcClient::new_with_commitment(
+        config.network.rpc_url.clone(),
         CommitmentConfig::confirmed(),
-    );
+    ));
 
-    let msol_token_address =
-        spl_associated_token_account::get_associated_token_address(&keypair.pubkey(), &output_mint);
-    println!(
-        "Pre-swap SOL balance: {}",
-        amount_to_ui_amount(rpc_client.get_balance(&keypair.pubkey()).await?, 9)
-    );
-    println!(
-        "Pre-swap mSOL balance: {}",
-        amount_to_ui_amount(
-            rpc_client
-                .get_token_account_balance(&msol_token_address)
-                .await?
-                .amount
-                .parse::<u64>()?,
-            9
-        )
-    );
-
-    let slippage_bps = 100;
+    let slippage_bps = config.slippage_bps(&SniperRoute::Jupiter) as u64;
     let only_direct_routes = true;
+    let amount = ui_amount_to_amount(config.trading.buy_amount, 9);
     let quotes = quote(
         sol,
         output_mint,
-        ui_amount_to_amount(config.trading.buy_amount, 9),
+        amount,
         QuoteConfig {
             only_direct_routes,
             slippage_bps: Some(slippage_bps),
             ..QuoteConfig::default()
         },
     )
-    .await?;
+    .await
+    .map_err(|e| eyre::eyre!("Jupiter quote failed: {}", e))?;
 
     let route = quotes.route_plan[0]
         .swap_info
@@ -69,11 +59,11 @@ pub async fn jup_swap(route: RouteKeys) -> Result<(), Box<dyn std::error::Error>
         .clone()
         .unwrap_or_else(|| "Unknown DEX".to_string());
     println!(
-        "Quote: {} SOL for {} mSOL via {} (worst case with slippage: {}). Impact: {:.2}%",
+        "Quote: {} SOL for {} tokens via {} (worst case with slippage: {}). Impact: {:.2}%",
         amount_to_ui_amount(quotes.in_amount, 9),
-        amount_to_ui_amount(quotes.out_amount, 9),
+        quotes.out_amount,
         route,
-        amount_to_ui_amount(quotes.other_amount_threshold, 9),
+        quotes.other_amount_threshold,
         quotes.price_impact_pct * 100.
     );
 
@@ -82,7 +72,9 @@ pub async fn jup_swap(route: RouteKeys) -> Result<(), Box<dyn std::error::Error>
     let Swap {
         mut swap_transaction,
         last_valid_block_height: _,
-    } = swap(request).await?;
+    } = swap(request)
+        .await
+        .map_err(|e| eyre::eyre!("Jupiter swap failed: {}", e))?;
 
     let recent_blockhash_for_swap: Hash = rpc_client.get_latest_blockhash().await?;
     swap_transaction
@@ -90,32 +82,16 @@ pub async fn jup_swap(route: RouteKeys) -> Result<(), Box<dyn std::error::Error>
         .set_recent_blockhash(recent_blockhash_for_swap); // Updating to latest blockhash to not error out
 
     let swap_transaction = VersionedTransaction::try_new(swap_transaction.message, &[&keypair])?;
-    println!(
-        "Simulating swap transaction: {}",
-        swap_transaction.signatures[0]
-    );
-    let response = rpc_client.simulate_transaction(&swap_transaction).await?;
-    println!("  {:#?}", response.value);
-    println!("Sending transaction: {}", swap_transaction.signatures[0]);
-    let _ = rpc_client
-        .send_and_confirm_transaction_with_spinner(&swap_transaction)
+    let report = TransactionSender::from_config(&config, rpc_client)
+        .with_order(Order::new(
+            SniperRoute::Jupiter,
+            TradeSide::Buy,
+            output_mint,
+            amount,
+        ))
+        .send_signed(&keypair, vec![swap_transaction])
         .await?;
+    report.log();
 
-    println!(
-        "Post-swap SOL balance: {}",
-        amount_to_ui_amount(rpc_client.get_balance(&keypair.pubkey()).await?, 9)
-    );
-    println!(
-        "Post-swap mSOL balance: {}",
-        amount_to_ui_amount(
-            rpc_client
-                .get_token_account_balance(&msol_token_address)
-                .await?
-                .amount
-                .parse::<u64>()?,
-            9
-        )
-    );
-
-    Ok(())
+    Ok(report)
 }
@@ -0,0 +1,166 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{info, warn};
+use serde_json::Value;
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use crate::env::{minter::BUNDLER_SETTINGS_PATH, CONFIG_PATH};
+
+use super::{is_key_ref, store_key};
+
+/// Folders that never hold wallets and are skipped when scanning `.`.
+const SKIPPED_DIRS: [&str; 3] = ["src", "clients", "target"];
+
+fn parse_plaintext(value: &str) -> Option<Keypair> {
+    if value.is_empty() || is_key_ref(value) {
+        return None;
+    }
+    let bytes = bs58::decode(value).into_vec().ok()?;
+    Keypair::from_bytes(&bytes).ok()
+}
+
+/// Moves every plaintext key this tool knows about into the keystore and
+/// rewrites the source file to reference it by label:
+///
+/// * `engine.payer_keypair` in `config.toml` as `payer`
+/// * deployer and buyer keys in `bundler_settings.json` as `deployer` / `buyer`
+/// * wallet folder files (`<folder>/wallet_1.json`) as `<folder>/wallet_1`
+///
+/// Returns the number of keys imported.
+pub fn migrate_plaintext_keys(wallet_dirs: &[PathBuf]) -> eyre::Result<usize> {
+    let mut imported = migrate_config()?;
+    imported += migrate_bundler_settings()?;
+
+    let dirs = if wallet_dirs.is_empty() {
+        wallet_folders()?
+    } else {
+        wallet_dirs.to_vec()
+    };
+    for dir in dirs {
+        imported += migrate_wallet_folder(&dir)?;
+    }
+
+    Ok(imported)
+}
+
+fn migrate_config() -> eyre::Result<usize> {
+    let content = match fs::read_to_string(CONFIG_PATH) {
+        Ok(content) => content,
+        Err(_) => return Ok(0),
+    };
+    let mut config: toml::Value = toml::from_str(&content)
+        .map_err(|e| eyre::eyre!("{} is not valid TOML: {}", CONFIG_PATH, e))?;
+
+    let payer = config
+        .get("engine")
+        .and_then(|engine| engine.get("payer_keypair"))
+        .and_then(|payer| payer.as_str())
+        .and_then(parse_plaintext);
+    let payer = match payer {
+        Some(payer) => payer,
+        None => return Ok(0),
+    };
+
+    let reference = store_key("payer", &payer)?;
+    config["engine"]["payer_keypair"] = toml::Value::String(reference);
+    fs::write(CONFIG_PATH, toml::to_string_pretty(&config)?)?;
+    info!("Imported payer {} from {}", payer.pubkey(), CONFIG_PATH);
+
+    Ok(1)
+}
+
+fn migrate_bundler_settings() -> eyre::Result<usize> {
+    let content = match fs::read_to_string(BUNDLER_SETTINGS_PATH) {
+        Ok(content) => content,
+        Err(_) => return Ok(0),
+    };
+    let mut settings: Value = serde_json::from_str(&content)
+        .map_err(|e| eyre::eyre!("{} is not valid JSON: {}", BUNDLER_SETTINGS_PATH, e))?;
+
+    let mut imported = 0;
+    for (field, label) in [("DEPLOYER-PRIVATE-KEY", "deployer"), ("BUYER-PRIVATE-KEY", "buyer")] {
+        let keypair = match settings[field].as_str().and_then(parse_plaintext) {
+            Some(keypair) => keypair,
+            None => continue,
+        };
+        settings[field] = Value::String(store_key(label, &keypair)?);
+        info!("Imported {} {} from {}", label, keypair.pubkey(), BUNDLER_SETTINGS_PATH);
+        imported += 1;
+    }
+
+    if imported > 0 {
+        fs::write(BUNDLER_SETTINGS_PATH, serde_json::to_string_pretty(&settings)?)?;
+    }
+
+    Ok(imported)
+}
+
+fn wallet_folders() -> eyre::Result<Vec<PathBuf>> {
+    let mut dirs = vec![];
+    for entry in fs::read_dir(".")? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if path.is_dir() && !name.starts_with('.') && !SKIPPED_DIRS.contains(&name) {
+            dirs.push(path);
+        }
+    }
+    Ok(dirs)
+}
+
+/// Handles both wallet file shapes: a bare JSON string written by
+/// `gen_wallet_save` and the `{"wallet": "..."}` objects under `./wallets`.
+fn migrate_wallet_folder(dir: &Path) -> eyre::Result<usize> {
+    let folder = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| eyre::eyre!("Invalid wallet folder {}", dir.display()))?
+        .to_string();
+
+    let mut imported = 0;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let mut json: Value = match fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+        {
+            Some(json) => json,
+            None => continue,
+        };
+
+        let slot = if json.is_string() {
+            &mut json
+        } else if json.get("wallet").is_some() {
+            &mut json["wallet"]
+        } else {
+            continue;
+        };
+        let keypair = match slot.as_str().and_then(parse_plaintext) {
+            Some(keypair) => keypair,
+            None => continue,
+        };
+
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let label = format!("{}/{}", folder, stem);
+        match store_key(&label, &keypair) {
+            Ok(reference) => *slot = Value::String(reference),
+            Err(e) => {
+                warn!("Skipping {}: {}", path.display(), e);
+                continue;
+            }
+        }
+        fs::write(&path, serde_json::to_string(&json)?)?;
+        imported += 1;
+    }
+
+    if imported > 0 {
+        info!("Imported {} wallet(s) from {}", imported, dir.display());
+    }
+
+    Ok(imported)
+}
@@ -0,0 +1,348 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::Mutex,
+};
+
+use aes_gcm_siv::{
+    aead::{Aead, NewAead},
+    Aes256GcmSiv, Nonce,
+};
+use log::info;
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use rpassword::prompt_password;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use crate::env::is_headless;
+
+pub mod migrate;
+
+pub const KEYSTORE_PATH: &str = "keystore.json";
+
+/// Config values starting with this prefix name a keystore label instead of a
+/// plaintext key, e.g. `payer_keypair = "keystore:payer-<pubkey>"`.
+pub const KEY_REF_PREFIX: &str = "keystore:";
+
+/// Lets headless runs unlock the keystore without a prompt.
+pub const PASSPHRASE_ENV: &str = "MEVARIK_KEYSTORE_PASSPHRASE";
+
+const CHECK_PLAINTEXT: &[u8] = b"mevarik-keystore";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    pub salt: String,
+}
+
+impl KdfParams {
+    fn generate(log_n: u8) -> Self {
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        KdfParams {
+            log_n,
+            r: 8,
+            p: 1,
+            salt: hex::encode(salt),
+        }
+    }
+
+    fn derive(&self, passphrase: &str) -> eyre::Result<[u8; 32]> {
+        let params = scrypt::Params::new(self.log_n, self.r, self.p)
+            .map_err(|e| eyre::eyre!("Invalid keystore kdf params: {}", e))?;
+        let salt = hex::decode(&self.salt)?;
+        let mut key = [0u8; 32];
+        scrypt::scrypt(passphrase.as_bytes(), &salt, &params, &mut key)
+            .map_err(|e| eyre::eyre!("Key derivation failed: {}", e))?;
+        Ok(key)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sealed {
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyEntry {
+    pub label: String,
+    pub pubkey: String,
+    #[serde(flatten)]
+    pub sealed: Sealed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeystoreFile {
+    pub version: u8,
+    pub kdf: KdfParams,
+    pub check: Sealed,
+    pub entries: Vec<KeyEntry>,
+}
+
+/// An unlocked keystore. Keys are only decrypted on `get`.
+pub struct Keystore {
+    file: KeystoreFile,
+    cipher: Aes256GcmSiv,
+}
+
+impl Keystore {
+    pub fn create(passphrase: &str) -> eyre::Result<Self> {
+        Self::create_with(passphrase, 15)
+    }
+
+    fn create_with(passphrase: &str, log_n: u8) -> eyre::Result<Self> {
+        let kdf = KdfParams::generate(log_n);
+        let cipher = Aes256GcmSiv::new_from_slice(&kdf.derive(passphrase)?)
+            .map_err(|_| eyre::eyre!("Invalid keystore key length"))?;
+        let check = seal(&cipher, CHECK_PLAINTEXT)?;
+
+        Ok(Keystore {
+            file: KeystoreFile {
+                version: 1,
+                kdf,
+                check,
+                entries: vec![],
+            },
+            cipher,
+        })
+    }
+
+    pub fn unlock(file: KeystoreFile, passphrase: &str) -> eyre::Result<Self> {
+        let cipher = Aes256GcmSiv::new_from_slice(&file.kdf.derive(passphrase)?)
+            .map_err(|_| eyre::eyre!("Invalid keystore key length"))?;
+        if open(&cipher, &file.check).ok().as_deref() != Some(CHECK_PLAINTEXT) {
+            return Err(eyre::eyre!("Wrong keystore passphrase"));
+        }
+        Ok(Keystore { file, cipher })
+    }
+
+    pub fn load(path: &Path, passphrase: &str) -> eyre::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let file: KeystoreFile = serde_json::from_str(&content)
+            .map_err(|e| eyre::eyre!("{} is not a valid keystore: {}", path.display(), e))?;
+        Self::unlock(file, passphrase)
+    }
+
+    /// Writes to a temporary file first so a crash never leaves a truncated keystore.
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&self.file)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn labels(&self) -> Vec<(String, String)> {
+        self.file
+            .entries
+            .iter()
+            .map(|e| (e.label.clone(), e.pubkey.clone()))
+            .collect()
+    }
+
+    pub fn contains(&self, label: &str) -> bool {
+        self.file.entries.iter().any(|e| e.label == label)
+    }
+
+    pub fn get(&self, label: &str) -> eyre::Result<Keypair> {
+        let entry = self
+            .file
+            .entries
+            .iter()
+            .find(|e| e.label == label)
+            .ok_or_else(|| eyre::eyre!("No key labelled `{}` in the keystore", label))?;
+        let secret = open(&self.cipher, &entry.sealed)?;
+        Keypair::from_bytes(&secret)
+            .map_err(|e| eyre::eyre!("Keystore entry `{}` is corrupt: {}", label, e))
+    }
+
+    /// Adds a key under `label`. Re-importing the same key is a no-op, reusing a
+    /// label for a different key is an error.
+    pub fn insert(&mut self, label: &str, keypair: &Keypair) -> eyre::Result<bool> {
+        let pubkey = keypair.pubkey().to_string();
+        if let Some(existing) = self.file.entries.iter().find(|e| e.label == label) {
+            if existing.pubkey == pubkey {
+                return Ok(false);
+            }
+            return Err(eyre::eyre!(
+                "Label `{}` already holds {}, refusing to replace it",
+                label,
+                existing.pubkey
+            ));
+        }
+
+        let sealed = seal(&self.cipher, &keypair.to_bytes())?;
+        self.file.entries.push(KeyEntry {
+            label: label.to_string(),
+            pubkey,
+            sealed,
+        });
+        Ok(true)
+    }
+}
+
+fn seal(cipher: &Aes256GcmSiv, plaintext: &[u8]) -> eyre::Result<Sealed> {
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| eyre::eyre!("Encryption failed"))?;
+    Ok(Sealed {
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+fn open(cipher: &Aes256GcmSiv, sealed: &Sealed) -> eyre::Result<Vec<u8>> {
+    let nonce = hex::decode(&sealed.nonce)?;
+    if nonce.len() != 12 {
+        return Err(eyre::eyre!("Invalid keystore nonce"));
+    }
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), hex::decode(&sealed.ciphertext)?.as_ref())
+        .map_err(|_| eyre::eyre!("Keystore entry failed authentication"))
+}
+
+/* ----------------------Session---------------------- */
+
+static SESSION: Lazy<Mutex<Option<Keystore>>> = Lazy::new(|| Mutex::new(None));
+static RESOLVED: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn read_passphrase(creating: bool) -> eyre::Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    if is_headless() {
+        return Err(eyre::eyre!(
+            "The keystore is locked, set {} to unlock it without a prompt",
+            PASSPHRASE_ENV
+        ));
+    }
+
+    if !creating {
+        return Ok(prompt_password("Keystore passphrase: ")?);
+    }
+
+    loop {
+        let passphrase = prompt_password("New keystore passphrase: ")?;
+        if passphrase.len() < 8 {
+            println!("Use at least 8 characters.");
+            continue;
+        }
+        if prompt_password("Repeat passphrase: ")? == passphrase {
+            return Ok(passphrase);
+        }
+        println!("Passphrases do not match.");
+    }
+}
+
+/// Runs `f` against the session keystore, unlocking it (or creating it) on first
+/// use. The passphrase is asked for at most once per process.
+pub fn with_keystore<T>(f: impl FnOnce(&mut Keystore) -> eyre::Result<T>) -> eyre::Result<T> {
+    let mut session = SESSION.lock().unwrap();
+    if session.is_none() {
+        let path = Path::new(KEYSTORE_PATH);
+        let keystore = if path.exists() {
+            Keystore::load(path, &read_passphrase(false)?)?
+        } else {
+            info!("No keystore found, creating {}", KEYSTORE_PATH);
+            let keystore = Keystore::create(&read_passphrase(true)?)?;
+            keystore.save(path)?;
+            keystore
+        };
+        *session = Some(keystore);
+    }
+    f(session.as_mut().unwrap())
+}
+
+pub fn key_ref(label: &str) -> String {
+    format!("{}{}", KEY_REF_PREFIX, label)
+}
+
+pub fn is_key_ref(value: &str) -> bool {
+    value.starts_with(KEY_REF_PREFIX)
+}
+
+/// Turns a config value into a base58 secret key. Plaintext keys pass through
+/// unchanged so existing setups keep working until they are migrated.
+pub fn resolve_secret(value: &str) -> eyre::Result<String> {
+    let label = match value.strip_prefix(KEY_REF_PREFIX) {
+        Some(label) => label,
+        None => return Ok(value.to_string()),
+    };
+
+    if let Some(secret) = RESOLVED.lock().unwrap().get(label) {
+        return Ok(secret.clone());
+    }
+    let secret = with_keystore(|keystore| Ok(keystore.get(label)?.to_base58_string()))?;
+    RESOLVED
+        .lock()
+        .unwrap()
+        .insert(label.to_string(), secret.clone());
+    Ok(secret)
+}
+
+/// Stores `keypair` in the session keystore and returns the reference to put in
+/// config files. The entry is labelled `label` suffixed with the pubkey, so a
+/// new key for the same role never collides with the one it replaces.
+pub fn store_key(label: &str, keypair: &Keypair) -> eyre::Result<String> {
+    let label = entry_label(label, keypair);
+    with_keystore(|keystore| {
+        if keystore.insert(&label, keypair)? {
+            keystore.save(Path::new(KEYSTORE_PATH))?;
+        }
+        Ok(key_ref(&label))
+    })
+}
+
+fn entry_label(label: &str, keypair: &Keypair) -> String {
+    format!("{}-{}", label, keypair.pubkey())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_through_file_format() {
+        let mut keystore = Keystore::create_with("correct horse", 4).unwrap();
+        let keypair = Keypair::new();
+        assert!(keystore.insert("payer", &keypair).unwrap());
+        assert!(!keystore.insert("payer", &keypair).unwrap());
+        assert!(keystore.insert("payer", &Keypair::new()).is_err());
+
+        let file: KeystoreFile =
+            serde_json::from_str(&serde_json::to_string(&keystore.file).unwrap()).unwrap();
+        let reopened = Keystore::unlock(file, "correct horse").unwrap();
+        assert_eq!(reopened.get("payer").unwrap().pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn new_keys_for_a_role_get_their_own_label() {
+        let mut keystore = Keystore::create_with("correct horse", 4).unwrap();
+        let (old, new) = (Keypair::new(), Keypair::new());
+        assert!(keystore.insert(&entry_label("payer", &old), &old).unwrap());
+        assert!(keystore.insert(&entry_label("payer", &new), &new).unwrap());
+        assert!(!keystore.insert(&entry_label("payer", &new), &new).unwrap());
+        assert_eq!(
+            keystore.get(&entry_label("payer", &new)).unwrap().pubkey(),
+            new.pubkey()
+        );
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let keystore = Keystore::create_with("correct horse", 4).unwrap();
+        assert!(Keystore::unlock(keystore.file.clone(), "battery staple").is_err());
+    }
+
+    #[test]
+    fn plaintext_values_pass_through() {
+        let secret = Keypair::new().to_base58_string();
+        assert_eq!(resolve_secret(&secret).unwrap(), secret);
+    }
+}
@@ -0,0 +1,250 @@
+pub mod report;
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+use yellowstone_grpc_proto::geyser::{subscribe_update::UpdateOneof, SubscribeUpdate};
+
+/// Newest block seen in a `blocks_meta` update, 0 before the first one.
+static TIP_SLOT: AtomicU64 = AtomicU64::new(0);
+
+tokio::task_local! {
+    /// Timeline of the trade the current task is working on.
+    static TIMELINE: Mutex<Option<Timeline>>;
+}
+
+/// Steps from a trigger arriving to its trade landing, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stage {
+    /// The update decoded into a transaction.
+    Parsed,
+    /// Instructions ready, quotes and account fetches included.
+    Built,
+    Signed,
+    /// Accepted by the RPC or the block engine.
+    Sent,
+    /// Confirmed on chain.
+    Landed,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::Parsed,
+        Stage::Built,
+        Stage::Signed,
+        Stage::Sent,
+        Stage::Landed,
+    ];
+
+    /// What the time up to this stage was spent on.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Parsed => "parse",
+            Stage::Built => "build",
+            Stage::Signed => "sign",
+            Stage::Sent => "send",
+            Stage::Landed => "land",
+        }
+    }
+}
+
+/// When each stage of a trade was reached, in microseconds after its trigger
+/// was received. Stages a trade never reached are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Latency {
+    /// Slot of the update that triggered the trade.
+    pub seen_slot: u64,
+    /// Newest block seen when the trigger was received, `None` on streams
+    /// without block updates.
+    pub tip_slot: Option<u64>,
+    /// Unix milliseconds.
+    pub received_at: i64,
+    pub parsed_us: Option<u64>,
+    pub built_us: Option<u64>,
+    pub signed_us: Option<u64>,
+    pub sent_us: Option<u64>,
+    pub landed_us: Option<u64>,
+    pub landed_slot: Option<u64>,
+}
+
+impl Latency {
+    pub fn at(&self, stage: Stage) -> Option<u64> {
+        match stage {
+            Stage::Parsed => self.parsed_us,
+            Stage::Built => self.built_us,
+            Stage::Signed => self.signed_us,
+            Stage::Sent => self.sent_us,
+            Stage::Landed => self.landed_us,
+        }
+    }
+
+    fn at_mut(&mut self, stage: Stage) -> &mut Option<u64> {
+        match stage {
+            Stage::Parsed => &mut self.parsed_us,
+            Stage::Built => &mut self.built_us,
+            Stage::Signed => &mut self.signed_us,
+            Stage::Sent => &mut self.sent_us,
+            Stage::Landed => &mut self.landed_us,
+        }
+    }
+
+    /// Slots the stream was behind the newest block when the trigger arrived.
+    pub fn detect_slots(&self) -> Option<u64> {
+        self.tip_slot.map(|tip| tip.saturating_sub(self.seen_slot))
+    }
+
+    /// Slots from the trigger to the trade landing.
+    pub fn landing_slots(&self) -> Option<u64> {
+        self.landed_slot
+            .map(|landed| landed.saturating_sub(self.seen_slot))
+    }
+
+    /// Microseconds spent on each stage reached, since the stage reached before
+    /// it.
+    pub fn stage_times(&self) -> Vec<(Stage, u64)> {
+        let mut previous = 0;
+        Stage::ALL
+            .iter()
+            .filter_map(|stage| {
+                let at = self.at(*stage)?;
+                let spent = at.saturating_sub(previous);
+                previous = at;
+                Some((*stage, spent))
+            })
+            .collect()
+    }
+
+    /// The stage the trade lost the most time on.
+    pub fn slowest_stage(&self) -> Option<(Stage, u64)> {
+        self.stage_times()
+            .into_iter()
+            .max_by_key(|(_, spent)| *spent)
+    }
+}
+
+/// A trade in flight, from the moment its trigger was received.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    received: Instant,
+    latency: Latency,
+}
+
+impl Timeline {
+    /// Starts timing a trigger seen in `seen_slot`, received now.
+    pub fn start(seen_slot: u64) -> Self {
+        let tip = TIP_SLOT.load(Ordering::Relaxed);
+        Timeline {
+            received: Instant::now(),
+            latency: Latency {
+                seen_slot,
+                tip_slot: (tip > 0).then_some(tip),
+                received_at: chrono::Utc::now().timestamp_millis(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Records that `stage` was reached now, unless it already was.
+    pub fn mark(&mut self, stage: Stage) {
+        let elapsed = self.received.elapsed().as_micros() as u64;
+        self.latency.at_mut(stage).get_or_insert(elapsed);
+    }
+
+    /// Runs `future` with this timeline as the one [`mark`] and [`take`] see.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        TIMELINE.scope(Mutex::new(Some(self)), future).await
+    }
+}
+
+/// Keeps the newest block of a stream, to tell how far behind later triggers
+/// arrive.
+pub fn observe(update: &SubscribeUpdate) {
+    if let Some(UpdateOneof::BlockMeta(meta)) = &update.update_oneof {
+        TIP_SLOT.fetch_max(meta.slot, Ordering::Relaxed);
+    }
+}
+
+/// Records that the trade of the current task reached `stage`. Does nothing
+/// outside a [`Timeline::scope`], e.g. for manual trades.
+pub fn mark(stage: Stage) {
+    let _ = TIMELINE.try_with(|timeline| {
+        if let Some(timeline) = timeline.lock().unwrap().as_mut() {
+            timeline.mark(stage);
+        }
+    });
+}
+
+/// Records that the trade of the current task landed in `slot`.
+pub fn landed(slot: u64) {
+    let _ = TIMELINE.try_with(|timeline| {
+        if let Some(timeline) = timeline.lock().unwrap().as_mut() {
+            timeline.mark(Stage::Landed);
+            timeline.latency.landed_slot = Some(slot);
+        }
+    });
+}
+
+/// Ends the timeline of the current task, so a later trade in the same task
+/// is not timed from the same trigger.
+pub fn take() -> Option<Latency> {
+    TIMELINE
+        .try_with(|timeline| timeline.lock().unwrap().take())
+        .ok()
+        .flatten()
+        .map(|timeline| timeline.latency)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stages_are_timed_once_within_the_scope() {
+        mark(Stage::Built);
+        assert_eq!(take(), None);
+
+        let mut timeline = Timeline::start(100);
+        timeline.mark(Stage::Parsed);
+        let latency = timeline
+            .scope(async {
+                mark(Stage::Built);
+                let built = TIMELINE.with(|t| t.lock().unwrap().as_ref().unwrap().latency.built_us);
+                mark(Stage::Built);
+                mark(Stage::Signed);
+                landed(103);
+                let latency = take().unwrap();
+                assert_eq!(latency.built_us, built);
+                assert_eq!(take(), None);
+                latency
+            })
+            .await;
+
+        assert_eq!(latency.seen_slot, 100);
+        assert_eq!(latency.landing_slots(), Some(3));
+        assert!(latency.sent_us.is_none());
+        let stages: Vec<Stage> = latency.stage_times().iter().map(|(s, _)| *s).collect();
+        assert_eq!(
+            stages,
+            [Stage::Parsed, Stage::Built, Stage::Signed, Stage::Landed]
+        );
+    }
+
+    #[test]
+    fn slowest_stage_is_measured_from_the_one_before() {
+        let latency = Latency {
+            parsed_us: Some(100),
+            built_us: Some(90_100),
+            signed_us: Some(91_000),
+            sent_us: Some(131_000),
+            ..Default::default()
+        };
+        assert_eq!(latency.slowest_stage(), Some((Stage::Built, 90_000)));
+    }
+}
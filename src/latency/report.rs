@@ -0,0 +1,172 @@
+use std::{collections::BTreeMap, fs::File, io::Write, path::Path};
+
+use histogram::Histogram;
+
+use crate::journal::TradeEntry;
+
+use super::{Latency, Stage};
+
+const PERCENTILES: [f64; 3] = [50.0, 90.0, 99.0];
+
+/// Latency distributions of the timed trades of one route.
+struct RouteLatency {
+    trades: u64,
+    /// Microseconds spent on each stage.
+    stages: Vec<Histogram>,
+    detect_slots: Histogram,
+    landing_slots: Histogram,
+}
+
+impl RouteLatency {
+    fn new() -> Self {
+        RouteLatency {
+            trades: 0,
+            stages: Stage::ALL.iter().map(|_| Histogram::new()).collect(),
+            detect_slots: Histogram::new(),
+            landing_slots: Histogram::new(),
+        }
+    }
+
+    fn add(&mut self, latency: &Latency) {
+        self.trades += 1;
+        for (stage, spent) in latency.stage_times() {
+            let _ = self.stages[stage as usize].increment(spent);
+        }
+        if let Some(slots) = latency.detect_slots() {
+            let _ = self.detect_slots.increment(slots);
+        }
+        if let Some(slots) = latency.landing_slots() {
+            let _ = self.landing_slots.increment(slots);
+        }
+    }
+}
+
+fn by_route(entries: &[TradeEntry]) -> BTreeMap<String, RouteLatency> {
+    let mut routes = BTreeMap::new();
+    for entry in entries {
+        if let Some(latency) = &entry.latency {
+            routes
+                .entry(format!("{:?}", entry.route))
+                .or_insert_with(RouteLatency::new)
+                .add(latency);
+        }
+    }
+    routes
+}
+
+/// `p50/p90/p99/max` of `histogram`, each formatted by `format`.
+fn distribution(histogram: &Histogram, format: impl Fn(u64) -> String) -> String {
+    if histogram.entries() == 0 {
+        return "-".to_string();
+    }
+    let mut values: Vec<String> = PERCENTILES
+        .iter()
+        .map(|percentile| format(histogram.percentile(*percentile).unwrap_or_default()))
+        .collect();
+    values.push(format(histogram.maximum().unwrap_or_default()));
+    values.join("/")
+}
+
+fn millis(micros: u64) -> String {
+    format!("{:.1}", micros as f64 / 1000.0)
+}
+
+/// Prints, per route, how long each stage of the timed trades took and how
+/// many slots they were behind.
+pub fn print_report(entries: &[TradeEntry]) {
+    let routes = by_route(entries);
+    if routes.is_empty() {
+        println!("No timed trades recorded yet.");
+        return;
+    }
+
+    println!("Stage times in ms, slots behind, as p50/p90/p99/max");
+    for (route, latency) in routes {
+        println!("{} ({} trade(s))", route, latency.trades);
+        for (stage, histogram) in Stage::ALL.iter().zip(&latency.stages) {
+            println!("  {:<8} {}", stage.name(), distribution(histogram, millis));
+        }
+        println!(
+            "  {:<8} {} slot(s) behind the tip when seen",
+            "detect",
+            distribution(&latency.detect_slots, |slots| slots.to_string())
+        );
+        println!(
+            "  {:<8} {} slot(s) from seen to landed",
+            "landing",
+            distribution(&latency.landing_slots, |slots| slots.to_string())
+        );
+    }
+}
+
+/// One line per timed trade saying where it lost time, for the journal view.
+pub fn describe(latency: &Latency) -> String {
+    let mut parts: Vec<String> = latency
+        .stage_times()
+        .iter()
+        .map(|(stage, spent)| format!("{} {}ms", stage.name(), millis(*spent)))
+        .collect();
+    if let Some(slots) = latency.detect_slots() {
+        parts.insert(0, format!("seen {} slot(s) behind", slots));
+    }
+    if let Some(slots) = latency.landing_slots() {
+        parts.push(format!("landed {} slot(s) after", slots));
+    }
+    if let Some((stage, _)) = latency.slowest_stage() {
+        parts.push(format!("slowest: {}", stage.name()));
+    }
+    parts.join(", ")
+}
+
+/// Writes every timed trade to `path` as CSV, stage times in microseconds.
+pub fn write_csv(entries: &[TradeEntry], path: &Path) -> eyre::Result<usize> {
+    let mut file = File::create(path)?;
+    write!(
+        file,
+        "timestamp,route,side,mint,signature,seen_slot,tip_slot,landed_slot"
+    )?;
+    for stage in Stage::ALL {
+        write!(file, ",{}_us", stage.name())?;
+    }
+    writeln!(file, ",slowest_stage")?;
+
+    let mut rows = 0;
+    for entry in entries {
+        let Some(latency) = &entry.latency else {
+            continue;
+        };
+        let spent: BTreeMap<usize, u64> = latency
+            .stage_times()
+            .into_iter()
+            .map(|(stage, spent)| (stage as usize, spent))
+            .collect();
+        write!(
+            file,
+            "{},{:?},{:?},{},{},{},{},{}",
+            entry.timestamp,
+            entry.route,
+            entry.side,
+            entry.mint,
+            entry.signature.as_deref().unwrap_or_default(),
+            latency.seen_slot,
+            optional(latency.tip_slot),
+            optional(latency.landed_slot),
+        )?;
+        for stage in Stage::ALL {
+            write!(file, ",{}", optional(spent.get(&(stage as usize)).copied()))?;
+        }
+        writeln!(
+            file,
+            ",{}",
+            latency
+                .slowest_stage()
+                .map_or("", |(stage, _)| stage.name())
+        )?;
+        rows += 1;
+    }
+    Ok(rows)
+}
+
+fn optional(value: Option<u64>) -> String {
+    value.map_or(String::new(), |value| value.to_string())
+}
@@ -1,17 +1,27 @@
 pub mod app;
 pub mod auth;
+pub mod cli;
 pub mod copytrade;
 pub mod env;
+pub mod geyser;
 pub mod instruction;
+pub mod journal;
 pub mod jupiter;
+pub mod keystore;
+pub mod latency;
 pub mod liquidity;
 pub mod moonshot;
 pub mod plugins;
+pub mod positions;
+pub mod price_feed;
 pub mod pumpfun;
 pub mod raydium_amm;
 // pub mod raydium_cpmm;
 pub mod router;
 pub mod rpc;
+pub mod sender;
+#[cfg(test)]
+pub mod testing;
 // pub mod screen;
 pub mod daos_fun;
 pub mod input;
@@ -1,7 +1,6 @@
-use std::{str::FromStr, sync::Arc};
+use std::str::FromStr;
 
-use jito_protos::searcher::SubscribeBundleResultsRequest;
-use jito_searcher_client::{get_searcher_client, send_bundle_with_confirmation};
+use bincode::serialize;
 use solana_address_lookup_table_program::instruction::extend_lookup_table;
 use solana_sdk::{
     instruction::Instruction,
@@ -22,8 +21,8 @@ use crate::{
         swap_ixs::load_pool_keys,
         utils::{tip_account, tip_txn},
     },
-    raydium_amm::swap::swapper::auth_keypair,
     rpc::HTTP_CLIENT,
+    sender::{SendOutcome, SendStrategy, TransactionSender},
 };
 
 use super::create_lut::create_lut;
@@ -202,33 +201,19 @@ pub async fn lut_caller(
         return Err(eyre::eyre!("Too many transactions"));
     }
 
-    let mut client = get_searcher_client(
-        "https://ny.mainnet.block-engine.jito.wtf",
-        &Arc::new(auth_keypair()),
-    )
-    .await?;
-
-    let mut bundle_results_subscription = client
-        .subscribe_bundle_results(SubscribeBundleResultsRequest {})
-        .await
-        .expect("subscribe to bundle results")
-        .into_inner();
-
-    use bincode::serialize;
+    // The tip already rides in its own transaction above
+    let sender = TransactionSender::new(
+        connection,
+        "https://ny.mainnet.block-engine.jito.wtf".to_string(),
+        SendStrategy::Bundle { tip_lamports: 0 },
+    );
 
-    let _ = match send_bundle_with_confirmation(
-        &versioned_txns,
-        &connection,
-        &mut client,
-        &mut bundle_results_subscription,
-    )
-    .await
-    {
-        Ok(results) => results,
-        Err(e) => {
-            return Err(eyre::eyre!("Error sending bundle: {:?}", e));
-        }
-    };
+    let report = sender.send_bundle(versioned_txns).await?;
+    match report.outcome {
+        SendOutcome::Landed { .. } => {}
+        SendOutcome::Failed { error } => return Err(eyre::eyre!("Error sending bundle: {}", error)),
+        SendOutcome::Expired => return Err(eyre::eyre!("LUT bundle expired before it landed")),
+    }
 
     Ok(lut_account)
 }
@@ -5,7 +5,10 @@ use log::info;
 use serde_json::Value;
 use solana_sdk::signature::Keypair;
 
-use crate::app::theme;
+use crate::{
+    app::theme,
+    keystore::{resolve_secret, store_key},
+};
 
 pub fn generate_wallets(count: i32) -> Vec<String> {
     let mut wallet: Vec<Keypair> = vec![];
@@ -61,7 +64,9 @@ pub async fn gen_wallet_save() -> eyre::Result<()> {
 
     for (i, wallet) in wallets.iter().enumerate() {
         let path = format!("{}/wallet_{}.json", folder_name, i + 1);
-        let data = serde_json::to_string(&wallet)?;
+        let label = format!("{}/wallet_{}", folder_name, i + 1);
+        let reference = store_key(&label, &Keypair::from_base58_string(wallet))?;
+        let data = serde_json::to_string(&reference)?;
         fs::write(path, data)?;
     }
 
@@ -125,13 +130,14 @@ pub async fn list_folders() -> Result<(String, Vec<Keypair>), Box<dyn Error>> {
     }
 
     let mut wallets = Vec::new();
-    json_values.iter().for_each(|x| {
-        let keypair = Keypair::from_base58_string(x.as_str().unwrap());
+    for x in json_values.iter() {
+        let secret = resolve_secret(x.as_str().unwrap()).map_err(|e| e.to_string())?;
+        let keypair = Keypair::from_base58_string(&secret);
         // println!("Wallet: {:?}", keypair.pubkey());
         // let associated = get_associated_token_address(&keypair.pubkey(), &SOLC_MINT);
         // println!("Associated: {:?}", associated);
         wallets.push(keypair);
-    });
+    }
 
     Ok((selected_option.clone(), wallets))
 }
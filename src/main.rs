@@ -4,6 +4,7 @@ use log::info;
 use pretty_env_logger::env_logger::fmt::Color;
 use std::io::Write;
 use Mevarik::app::main_menu;
+use Mevarik::cli::{build_cli, run_cli};
 use Mevarik::{
     app::{config_init::initialize_global_config, embeds::embed},
     env::utils::read_keys,
@@ -39,10 +40,24 @@ async fn main() {
         })
         .init();
 
+    let matches = build_cli().get_matches();
+    match run_cli(&matches).await {
+        Ok(false) => {}
+        Ok(true) => return,
+        Err(e) => {
+            log::error!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+
     clear_screen();
     embed();
 
-    initialize_global_config().await.unwrap();
+    if let Err(e) = initialize_global_config().await {
+        log::error!("{}", e);
+        let _ = read_keys().await;
+        return;
+    }
 
     // info!("Authenticating...");
     // let _ = match Mevarik::auth::auth_verification().await {
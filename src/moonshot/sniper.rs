@@ -3,21 +3,18 @@ use std::sync::Arc;
 use borsh::BorshDeserialize;
 use colorize::AnsiColor;
 use crossterm::style::Stylize;
-use futures::{channel::mpsc::SendError, Sink};
 use log::{error, info};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
-use yellowstone_grpc_proto::geyser::{SubscribeRequest, SubscribeUpdateTransaction};
 
 use crate::{
     app::MevApe,
     env::load_config,
+    geyser::ParsedTransaction,
     moonshot::instructions::instructions::TokenMintIxData,
     raydium_amm::{
-        subscribe::auto_sniper_stream,
         swap::{metadata::decode_metadata, swap_in::PriorityTip},
     },
-    router::{grpc_pair_sub, SniperRoute},
+    router::{pair_sub, SniperRoute},
     user_inputs::{
         amounts::{bundle_priority_tip, priority_fee, sol_amount},
         tokens::token_env,
@@ -35,10 +32,6 @@ pub async fn moonshot_sniper(manual_snipe: bool) -> eyre::Result<()> {
         }
     };
 
-    if args.network.grpc_url.is_empty() {
-        let _ = auto_sniper_stream(manual_snipe).await?;
-        return Ok(());
-    }
     let sol_amount = sol_amount("Snipe Amount:").await;
 
     let token;
@@ -99,7 +92,7 @@ pub async fn moonshot_sniper(manual_snipe: bool) -> eyre::Result<()> {
         wallet: args.engine.payer_keypair.clone(),
     };
 
-    let _ = match grpc_pair_sub(
+    let _ = match pair_sub(
         args,
         manual_snipe,
         token,
@@ -117,41 +110,11 @@ pub async fn moonshot_sniper(manual_snipe: bool) -> eyre::Result<()> {
 
 pub async fn moonshot_parser(
     rpc_client: Arc<RpcClient>,
-    tx: SubscribeUpdateTransaction,
+    tx: &ParsedTransaction,
     manual_snipe: bool,
-    subscribe_tx: tokio::sync::MutexGuard<
-        '_,
-        impl Sink<SubscribeRequest, Error = SendError> + std::marker::Unpin,
-    >,
 ) -> eyre::Result<()> {
-    let info = tx.clone().transaction.unwrap_or_default();
-    let accounts = info
-        .transaction
-        .clone()
-        .unwrap_or_default()
-        .message
-        .unwrap_or_default()
-        .account_keys
-        .iter()
-        .map(|i| {
-            let mut array = [0; 32];
-            let bytes = &i[..array.len()]; // panics if not enough data
-            array.copy_from_slice(bytes);
-            Pubkey::new_from_array(array)
-        })
-        .collect::<Vec<Pubkey>>();
-    let outer_instructions = {
-        let transaction = info.transaction.clone().unwrap_or_default();
-        let message = transaction.message.unwrap_or_default();
-        let instructions = message.instructions.iter();
-        instructions.cloned().collect::<Vec<_>>()
-    };
-
-    let inner_instructions = {
-        let transaction = info.meta.unwrap_or_default();
-        let message = transaction.inner_instructions;
-        message
-    };
+    let accounts = tx.account_keys();
+    let outer_instructions = &tx.instructions;
 
     let mut coin_found = false;
 
@@ -173,7 +136,7 @@ pub async fn moonshot_parser(
         return Ok(());
     }
 
-    let signature = bs58::encode(&info.signature).into_string();
+    let signature = tx.signature;
 
     info!(
         "Transaction: {}\nCoin: {:?}\nMaker: {}\nMint: {}\nConfig: {}",
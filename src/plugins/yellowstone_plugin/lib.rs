@@ -42,7 +42,7 @@ impl Interceptor for InterceptorXToken {
 #[derive(Debug, thiserror::Error)]
 pub enum GeyserGrpcClientError {
     #[error("Invalid URI: {0}")]
-    InvalidUri(#[from] http::uri::InvalidUri),
+    InvalidUri(#[from] tonic::codegen::http::uri::InvalidUri),
     #[error("Failed to parse x-token: {0}")]
     MetadataValueError(#[from] InvalidMetadataValue),
     #[error("Invalid X-Token length: {0}, expected 28")]
@@ -76,7 +76,7 @@ impl GeyserGrpcClient<()> {
         E: Into<Bytes>,
         T: TryInto<AsciiMetadataValue, Error = InvalidMetadataValue>,
     {
-        let mut endpoint = Channel::from_shared(endpoint).unwrap();
+        let mut endpoint = Channel::from_shared(endpoint)?;
 
         if let Some(tls_config) = tls_config {
             endpoint = endpoint.tls_config(tls_config)?;
@@ -0,0 +1,204 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+use once_cell::sync::Lazy;
+use solana_sdk::native_token::lamports_to_sol;
+
+use crate::{
+    env::{CurveExitStep, SettingsConfig},
+    jupiter::jup_utils::price,
+    pumpfun::{
+        events::{INITIAL_REAL_TOKEN_RESERVES, TOKEN_TOTAL_SUPPLY},
+        pump_interface::accounts::BondingCurve,
+    },
+    raydium_amm::swap::instructions::{SOLC_MINT, USDC_MINT},
+};
+
+use super::rules::{Exit, ExitReason, FULL_EXIT_BPS};
+
+/// How long a SOL/USD price is reused before it is fetched again.
+const SOL_USD_TTL: Duration = Duration::from_secs(60);
+
+static SOL_USD: Lazy<Mutex<Option<(f64, Instant)>>> = Lazy::new(|| Mutex::new(None));
+
+/// How far a bonding curve is from migrating.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurveProgress {
+    /// Share of the curve's tokens sold, in percent.
+    pub completion: f64,
+    /// Lamports the whole supply is worth at the curve price.
+    pub market_cap: u64,
+    pub complete: bool,
+}
+
+impl CurveProgress {
+    pub fn of(curve: &BondingCurve) -> Self {
+        let sold = INITIAL_REAL_TOKEN_RESERVES.saturating_sub(curve.real_token_reserves);
+        let completion = if curve.complete {
+            100.0
+        } else {
+            sold as f64 / INITIAL_REAL_TOKEN_RESERVES as f64 * 100.0
+        };
+        let supply = match curve.token_total_supply {
+            0 => TOKEN_TOTAL_SUPPLY,
+            supply => supply,
+        };
+        let market_cap = match curve.virtual_token_reserves {
+            0 => 0,
+            reserves => {
+                (curve.virtual_sol_reserves as u128 * supply as u128 / reserves as u128) as u64
+            }
+        };
+
+        CurveProgress {
+            completion,
+            market_cap,
+            complete: curve.complete,
+        }
+    }
+
+    /// `market cap in SOL`, plus USD when the SOL price is known.
+    pub fn describe(&self, sol_usd: Option<f64>) -> String {
+        let sol = lamports_to_sol(self.market_cap);
+        match sol_usd {
+            Some(usd) => format!(
+                "Curve {:.1}% | Mcap {:.2} SOL (${:.0})",
+                self.completion,
+                sol,
+                sol * usd
+            ),
+            None => format!("Curve {:.1}% | Mcap {:.2} SOL", self.completion, sol),
+        }
+    }
+}
+
+/// When a Pump.fun position is sold on account of its curve, read from the
+/// trading settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurveRules {
+    pub steps: Vec<CurveExitStep>,
+    /// Completion in percent at which everything is sold.
+    pub exit_before_migration: Option<f64>,
+    pub follow_migration: bool,
+}
+
+impl CurveRules {
+    pub fn from_config(args: &SettingsConfig) -> Self {
+        let curve_exit = &args.trading.curve_exit;
+        CurveRules {
+            steps: curve_exit.steps.clone(),
+            exit_before_migration: (curve_exit.exit_before_migration_percentage > 0.0)
+                .then_some(curve_exit.exit_before_migration_percentage),
+            follow_migration: curve_exit.follow_migration,
+        }
+    }
+
+    /// The exit due at `completion` with `steps_taken` steps already sold.
+    /// The pre-migration exit wins, and at most one step fires per check.
+    pub fn evaluate(&self, completion: f64, steps_taken: usize) -> Option<Exit> {
+        if self
+            .exit_before_migration
+            .is_some_and(|at| completion >= at)
+        {
+            return Some(Exit {
+                reason: ExitReason::BeforeMigration,
+                bps: FULL_EXIT_BPS,
+            });
+        }
+
+        let step = self.steps.get(steps_taken)?;
+        (completion >= step.completion_percentage).then(|| Exit {
+            reason: ExitReason::CurveStep(steps_taken),
+            bps: (step.sell_percentage * 100.0)
+                .round()
+                .clamp(1.0, FULL_EXIT_BPS as f64) as u16,
+        })
+    }
+}
+
+/// USD per SOL, fetched at most once a minute. `None` while the price API
+/// cannot be reached and no earlier price is known.
+pub async fn sol_usd() -> Option<f64> {
+    let cached = *SOL_USD.lock().unwrap();
+    if let Some((usd, at)) = cached {
+        if at.elapsed() < SOL_USD_TTL {
+            return Some(usd);
+        }
+    }
+
+    match price(SOLC_MINT, USDC_MINT, 1.0).await {
+        Ok(quote) => {
+            *SOL_USD.lock().unwrap() = Some((quote.price, Instant::now()));
+            Some(quote.price)
+        }
+        Err(e) => {
+            warn!("Cannot fetch the SOL price: {}", e);
+            // Retry after another TTL rather than on every status line.
+            let stale = cached.map(|(usd, _)| usd);
+            if let Some(usd) = stale {
+                *SOL_USD.lock().unwrap() = Some((usd, Instant::now()));
+            }
+            stale
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completion_drives_the_curve_exits() {
+        let mut curve = BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: INITIAL_REAL_TOKEN_RESERVES,
+            real_sol_reserves: 0,
+            token_total_supply: TOKEN_TOTAL_SUPPLY,
+            complete: false,
+        };
+        let fresh = CurveProgress::of(&curve);
+        assert_eq!(fresh.completion, 0.0);
+        // 30 SOL over 1.073B tokens, for a 1B supply
+        assert_eq!(fresh.market_cap, 27_958_993_476);
+
+        curve.real_token_reserves = INITIAL_REAL_TOKEN_RESERVES / 4;
+        assert_eq!(CurveProgress::of(&curve).completion, 75.0);
+
+        let rules = CurveRules {
+            steps: vec![
+                CurveExitStep {
+                    completion_percentage: 50.0,
+                    sell_percentage: 25.0,
+                },
+                CurveExitStep {
+                    completion_percentage: 70.0,
+                    sell_percentage: 50.0,
+                },
+            ],
+            exit_before_migration: Some(90.0),
+            follow_migration: false,
+        };
+        assert_eq!(rules.evaluate(40.0, 0), None);
+        // Crossing both thresholds at once still sells one step per check
+        assert_eq!(
+            rules.evaluate(75.0, 0),
+            Some(Exit {
+                reason: ExitReason::CurveStep(0),
+                bps: 2_500
+            })
+        );
+        assert_eq!(rules.evaluate(75.0, 1).map(|exit| exit.bps), Some(5_000));
+        assert_eq!(rules.evaluate(75.0, 2), None);
+        assert_eq!(
+            rules.evaluate(92.0, 2),
+            Some(Exit {
+                reason: ExitReason::BeforeMigration,
+                bps: FULL_EXIT_BPS
+            })
+        );
+    }
+}
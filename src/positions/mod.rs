@@ -0,0 +1,381 @@
+pub mod curve;
+pub mod rules;
+pub mod venue;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    native_token::lamports_to_sol, pubkey::Pubkey, signature::Keypair, signer::Signer,
+};
+use tokio::{task::JoinHandle, time::sleep};
+
+use crate::{app::config_init::get_config, env::SettingsConfig, price_feed, sender::SendReport};
+
+use curve::{sol_usd, CurveProgress, CurveRules};
+use rules::{ExitRules, Progress, FULL_EXIT_BPS};
+use venue::{pump_curve, Venue};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A confirmed buy can take a moment to show in the balance.
+const SETTLE_TIME: Duration = Duration::from_secs(30);
+
+/// Failed or expired exit sends in a row before the watcher gives up.
+const MAX_EXIT_ATTEMPTS: u32 = 3;
+
+static POSITIONS: Lazy<Mutex<HashMap<Pubkey, Position>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Tokens bought with a confirmed order, watched until they are all sold.
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub mint: Pubkey,
+    pub venue: Venue,
+    /// Lamports paid for `tokens`.
+    pub cost: u64,
+    /// Raw tokens bought, 0 until read from the balance.
+    pub tokens: u64,
+    pub opened_at: Instant,
+    pub peak_gain: f64,
+    pub steps_taken: usize,
+    /// Curve completion steps already sold.
+    pub curve_steps_taken: usize,
+    /// Set once any exit has landed.
+    pub sold: bool,
+}
+
+impl Position {
+    /// What the `held` tokens cost, sells included.
+    fn cost_of(&self, held: u64) -> u64 {
+        if self.tokens == 0 {
+            return self.cost;
+        }
+        (self.cost as u128 * held as u128 / self.tokens as u128) as u64
+    }
+}
+
+/// Opens a position for a confirmed buy of `mint` that cost `cost` lamports,
+/// taking the tokens bought from the report's fill. Returns the watcher when a
+/// new position was opened; a buy into an open position is added to it.
+pub fn open(venue: Venue, mint: Pubkey, cost: u64, report: &SendReport) -> Option<JoinHandle<()>> {
+    let tokens = report
+        .fill
+        .as_ref()
+        .map(|fill| fill.token_change.clamp(0, u64::MAX as i128) as u64)
+        .unwrap_or(0);
+
+    let mut positions = POSITIONS.lock().unwrap();
+    if let Some(position) = positions.get_mut(&mint) {
+        position.cost += cost;
+        // Without the fill the basis is read from the balance again.
+        position.tokens = if tokens > 0 && position.tokens > 0 {
+            position.tokens + tokens
+        } else {
+            0
+        };
+        info!("Added {} SOL to the {} position", lamports_to_sol(cost), mint);
+        return None;
+    }
+
+    positions.insert(
+        mint,
+        Position {
+            mint,
+            venue,
+            cost,
+            tokens,
+            opened_at: Instant::now(),
+            peak_gain: 0.0,
+            steps_taken: 0,
+            curve_steps_taken: 0,
+            sold: false,
+        },
+    );
+    info!("Opened a {} SOL position in {}", lamports_to_sol(cost), mint);
+
+    Some(tokio::spawn(watch(mint)))
+}
+
+pub fn is_open(mint: &Pubkey) -> bool {
+    POSITIONS.lock().unwrap().contains_key(mint)
+}
+
+/// What the `held` tokens of the open position in `mint` cost.
+pub fn cost_basis(mint: &Pubkey, held: u64) -> Option<u64> {
+    position(mint).map(|position| position.cost_of(held))
+}
+
+/// Venue of the open position in `mint`, if any.
+pub fn venue_of(mint: &Pubkey) -> Option<Venue> {
+    position(mint).map(|position| position.venue)
+}
+
+/// Sells `bps` of the live `mint` balance on the venue of its open position,
+/// or on the Pump.fun or daos.fun curve it trades on.
+pub async fn sell_fraction(mint: Pubkey, bps: u16) -> eyre::Result<SendReport> {
+    let venue = match venue_of(&mint) {
+        Some(venue) => venue,
+        None => {
+            let config = get_config().await?;
+            Venue::detect(&RpcClient::new(config.network.rpc_url), &mint).await?
+        }
+    };
+    sell_fraction_on(venue, mint, bps).await
+}
+
+/// Sells `bps` of the live `mint` balance on `venue`. A partial sell leaves the
+/// position open for the rest.
+pub async fn sell_fraction_on(venue: Venue, mint: Pubkey, bps: u16) -> eyre::Result<SendReport> {
+    let config = get_config().await?;
+    let wallet = Arc::new(Keypair::from_base58_string(&config.engine.payer_keypair));
+    let rpc_client = RpcClient::new(config.network.rpc_url.clone());
+
+    let balance = venue.balance(&rpc_client, &wallet.pubkey(), &mint).await?;
+    if balance == 0 {
+        return Err(eyre::eyre!("No {} tokens to sell", mint));
+    }
+
+    let report = venue.sell(&wallet, &config, mint, balance, bps).await?;
+    if report.landed() {
+        update(&mint, |position| position.sold = true);
+    }
+    Ok(report)
+}
+
+fn position(mint: &Pubkey) -> Option<Position> {
+    POSITIONS.lock().unwrap().get(mint).cloned()
+}
+
+fn update(mint: &Pubkey, change: impl FnOnce(&mut Position)) {
+    if let Some(position) = POSITIONS.lock().unwrap().get_mut(mint) {
+        change(position);
+    }
+}
+
+async fn watch(mint: Pubkey) {
+    let config = get_config().await.ok();
+    if let (Some(config), Some(position)) = (&config, position(&mint)) {
+        let wallet = Keypair::from_base58_string(&config.engine.payer_keypair);
+        stream_price(config, &position.venue, &wallet.pubkey(), mint).await;
+    }
+
+    if let Err(e) = watch_until_closed(mint).await {
+        error!("Stopped watching the {} position: {}", mint, e);
+    }
+    POSITIONS.lock().unwrap().remove(&mint);
+    if let Some(config) = &config {
+        price_feed::untrack(config, &mint);
+    }
+}
+
+/// Quotes, the curve and the `wallet` balance come from account updates while
+/// the feed streams them.
+async fn stream_price(config: &SettingsConfig, venue: &Venue, wallet: &Pubkey, mint: Pubkey) {
+    let Some(source) = venue.price_source(mint) else {
+        return;
+    };
+    if !config.network.grpc_url.is_empty() {
+        let holding = venue.token_account(wallet, &mint);
+        if let Err(e) = price_feed::track(config, source, holding).await {
+            warn!("Cannot stream the {} price, quoting over RPC: {}", mint, e);
+        }
+    }
+}
+
+/// Moves the position in `mint` to the Raydium pool its bonding curve
+/// migrated to. Returns `false` while the pool does not exist yet.
+async fn follow_migration(
+    config: &SettingsConfig,
+    rpc_client: &RpcClient,
+    wallet: &Pubkey,
+    mint: Pubkey,
+) -> eyre::Result<bool> {
+    let Some(venue) = Venue::migrated(rpc_client, &mint).await? else {
+        return Ok(false);
+    };
+    if let Venue::RaydiumAMM(pool_keys) = &venue {
+        info!("{} migrated to Raydium pool {}, selling there from now on", mint, pool_keys.id);
+    }
+    price_feed::untrack(config, &mint);
+    stream_price(config, &venue, wallet, mint).await;
+    update(&mint, |position| position.venue = venue);
+    Ok(true)
+}
+
+async fn watch_until_closed(mint: Pubkey) -> eyre::Result<()> {
+    let config = get_config().await?;
+    let rules = ExitRules::from_config(&config);
+    let curve_rules = CurveRules::from_config(&config);
+    let wallet = Arc::new(Keypair::from_base58_string(&config.engine.payer_keypair));
+    let rpc_client = Arc::new(RpcClient::new(config.network.rpc_url.clone()));
+
+    let mut failed_exits = 0;
+    let mut last_status: Option<Instant> = None;
+    let mut last_warning: Option<Instant> = None;
+    let mut last_migration_check: Option<Instant> = None;
+
+    loop {
+        sleep(POLL_INTERVAL).await;
+        let Some(position) = position(&mint) else {
+            return Ok(());
+        };
+
+        let balance = match position.venue.balance(&rpc_client, &wallet.pubkey(), &mint).await {
+            Ok(balance) => balance,
+            Err(e) => {
+                warn!("Cannot read the {} balance: {}", mint, e);
+                continue;
+            }
+        };
+        if balance == 0 {
+            if position.sold || position.opened_at.elapsed() > SETTLE_TIME {
+                info!("Position in {} closed", mint);
+                return Ok(());
+            }
+            continue;
+        }
+        if position.tokens == 0 {
+            update(&mint, |position| position.tokens = balance);
+        }
+
+        let mut curve = None;
+        if let Venue::PumpFun = position.venue {
+            let state = match pump_curve(&rpc_client, &mint).await {
+                Ok(state) => CurveProgress::of(&state),
+                Err(e) => {
+                    if last_warning.is_none_or(|at| at.elapsed() >= STATUS_INTERVAL) {
+                        warn!("Cannot read the {} bonding curve: {}", mint, e);
+                        last_warning = Some(Instant::now());
+                    }
+                    continue;
+                }
+            };
+            if state.complete {
+                if !curve_rules.follow_migration {
+                    return Err(eyre::eyre!(
+                        "bonding curve completed with {} tokens held",
+                        balance
+                    ));
+                }
+                if last_migration_check.is_none_or(|at| at.elapsed() >= STATUS_INTERVAL) {
+                    last_migration_check = Some(Instant::now());
+                    match follow_migration(&config, &rpc_client, &wallet.pubkey(), mint).await {
+                        Ok(true) => {}
+                        Ok(false) => info!("{} completed its curve, waiting for the Raydium pool", mint),
+                        Err(e) => warn!("Cannot find the Raydium pool of {}: {}", mint, e),
+                    }
+                }
+                continue;
+            }
+            curve = Some(state);
+        }
+
+        let value = match position.venue.quote(&rpc_client, &wallet, &mint, balance).await {
+            Ok(value) => value,
+            Err(e) => {
+                if last_warning.is_none_or(|at| at.elapsed() >= STATUS_INTERVAL) {
+                    warn!("Cannot quote {}: {}", mint, e);
+                    last_warning = Some(Instant::now());
+                }
+                continue;
+            }
+        };
+        let Some(position) = position_with_value(&mint, balance, value) else {
+            return Ok(());
+        };
+        let progress = Progress {
+            gain: gain_percentage(value, position.cost_of(balance)),
+            peak_gain: position.peak_gain,
+            held_for: position.opened_at.elapsed(),
+            steps_taken: position.steps_taken,
+        };
+
+        if last_status.is_none_or(|at| at.elapsed() >= STATUS_INTERVAL) {
+            let curve_status = match &curve {
+                Some(curve) => format!(" | {}", curve.describe(sol_usd().await)),
+                None => String::new(),
+            };
+            info!(
+                "{} | Cost {:.4} SOL | Worth {:.4} SOL | Profit {:.2}%{}",
+                mint,
+                lamports_to_sol(position.cost_of(balance)),
+                lamports_to_sol(value),
+                progress.gain,
+                curve_status
+            );
+            last_status = Some(Instant::now());
+        }
+
+        // Full exits on the curve win, like full exits win over take-profit.
+        let curve_exit = curve
+            .and_then(|curve| curve_rules.evaluate(curve.completion, position.curve_steps_taken));
+        let exit = match curve_exit {
+            Some(exit) if exit.bps == FULL_EXIT_BPS => Some(exit),
+            curve_exit => rules.evaluate(&progress).or(curve_exit),
+        };
+        let Some(exit) = exit else {
+            continue;
+        };
+        info!(
+            "{} hit for {} at {:.2}%, selling {}%",
+            exit.reason,
+            mint,
+            progress.gain,
+            exit.bps as f64 / 100.0
+        );
+
+        match sell_fraction_on(position.venue.clone(), mint, exit.bps).await {
+            Ok(report) if report.landed() => {
+                failed_exits = 0;
+                match exit.reason {
+                    rules::ExitReason::TakeProfit(step) => {
+                        update(&mint, |position| position.steps_taken = step + 1)
+                    }
+                    rules::ExitReason::CurveStep(step) => {
+                        update(&mint, |position| position.curve_steps_taken = step + 1)
+                    }
+                    _ => {}
+                }
+                if exit.bps == FULL_EXIT_BPS {
+                    info!("Position in {} closed", mint);
+                    return Ok(());
+                }
+            }
+            Ok(report) => {
+                report.log();
+                failed_exits += 1;
+            }
+            Err(e) => {
+                error!("Exit for {} failed: {}", mint, e);
+                failed_exits += 1;
+            }
+        }
+        if failed_exits >= MAX_EXIT_ATTEMPTS {
+            return Err(eyre::eyre!("{} exits in a row did not land", failed_exits));
+        }
+    }
+}
+
+/// Records the peak of the latest quote and returns the updated position.
+fn position_with_value(mint: &Pubkey, balance: u64, value: u64) -> Option<Position> {
+    let mut positions = POSITIONS.lock().unwrap();
+    let position = positions.get_mut(mint)?;
+    let gain = gain_percentage(value, position.cost_of(balance));
+    position.peak_gain = position.peak_gain.max(gain);
+    Some(position.clone())
+}
+
+fn gain_percentage(value: u64, cost: u64) -> f64 {
+    if cost == 0 {
+        return 0.0;
+    }
+    (value as f64 - cost as f64) / cost as f64 * 100.0
+}
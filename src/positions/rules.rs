@@ -0,0 +1,223 @@
+use std::{fmt, time::Duration};
+
+use crate::env::{SettingsConfig, TakeProfitStep};
+
+/// Basis points of the tokens held, 10_000 sells everything.
+pub const FULL_EXIT_BPS: u16 = 10_000;
+
+/// When a position is sold, read from the trading settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExitRules {
+    pub enabled: bool,
+    pub take_profit: Vec<TakeProfitStep>,
+    /// Gain in percent at or below which everything is sold, e.g. -50.
+    pub stop_loss: Option<f64>,
+    /// Drop in percent from the highest value since the buy.
+    pub trailing_stop: Option<f64>,
+    pub max_hold: Option<Duration>,
+}
+
+/// Where a position stands, as seen by the watcher.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    /// Gain in percent of the tokens held over what they cost.
+    pub gain: f64,
+    /// Highest `gain` since the buy, never below 0.
+    pub peak_gain: f64,
+    pub held_for: Duration,
+    /// Take-profit steps already sold.
+    pub steps_taken: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitReason {
+    /// Index of the take-profit step.
+    TakeProfit(usize),
+    StopLoss,
+    TrailingStop,
+    MaxHold,
+    /// Index of the curve completion step.
+    CurveStep(usize),
+    /// The bonding curve is about to migrate.
+    BeforeMigration,
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitReason::TakeProfit(step) => write!(f, "Take-profit step {}", step + 1),
+            ExitReason::StopLoss => write!(f, "Stop-loss"),
+            ExitReason::TrailingStop => write!(f, "Trailing stop"),
+            ExitReason::MaxHold => write!(f, "Max hold time"),
+            ExitReason::CurveStep(step) => write!(f, "Curve step {}", step + 1),
+            ExitReason::BeforeMigration => write!(f, "Pre-migration exit"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exit {
+    pub reason: ExitReason,
+    /// Share of the tokens held to sell.
+    pub bps: u16,
+}
+
+impl ExitRules {
+    /// Without a `take_profit` ladder, `profit_threshold_percentage` is a single
+    /// step that sells everything.
+    pub fn from_config(args: &SettingsConfig) -> Self {
+        let trading = &args.trading;
+
+        let take_profit = if trading.take_profit.is_empty() {
+            (trading.profit_threshold_percentage > 0.0)
+                .then_some(TakeProfitStep {
+                    gain_percentage: trading.profit_threshold_percentage,
+                    sell_percentage: 100.0,
+                })
+                .into_iter()
+                .collect()
+        } else {
+            trading.take_profit.clone()
+        };
+
+        ExitRules {
+            enabled: trading.auto_exit,
+            take_profit,
+            stop_loss: (trading.loss_threshold_percentage < 0.0)
+                .then_some(trading.loss_threshold_percentage),
+            trailing_stop: (trading.trailing_stop_percentage > 0.0)
+                .then_some(trading.trailing_stop_percentage),
+            max_hold: (trading.max_hold_secs > 0).then(|| Duration::from_secs(trading.max_hold_secs)),
+        }
+    }
+
+    /// The exit due at `progress`, if any. Full exits win over take-profit, and
+    /// at most one take-profit step fires per check.
+    pub fn evaluate(&self, progress: &Progress) -> Option<Exit> {
+        if !self.enabled {
+            return None;
+        }
+
+        let full = |reason| {
+            Some(Exit {
+                reason,
+                bps: FULL_EXIT_BPS,
+            })
+        };
+        if self.stop_loss.is_some_and(|loss| progress.gain <= loss) {
+            return full(ExitReason::StopLoss);
+        }
+        if self
+            .trailing_stop
+            .is_some_and(|trail| drawdown(progress.peak_gain, progress.gain) >= trail)
+        {
+            return full(ExitReason::TrailingStop);
+        }
+        if self.max_hold.is_some_and(|max| progress.held_for >= max) {
+            return full(ExitReason::MaxHold);
+        }
+
+        let step = self.take_profit.get(progress.steps_taken)?;
+        (progress.gain >= step.gain_percentage).then(|| Exit {
+            reason: ExitReason::TakeProfit(progress.steps_taken),
+            bps: (step.sell_percentage * 100.0).round().clamp(1.0, FULL_EXIT_BPS as f64) as u16,
+        })
+    }
+}
+
+/// Percent lost from the value at `peak_gain` to the value at `gain`.
+fn drawdown(peak_gain: f64, gain: f64) -> f64 {
+    let peak = 1.0 + peak_gain / 100.0;
+    if peak <= 0.0 {
+        return 0.0;
+    }
+    (1.0 - (1.0 + gain / 100.0) / peak) * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> ExitRules {
+        ExitRules {
+            enabled: true,
+            take_profit: vec![
+                TakeProfitStep {
+                    gain_percentage: 100.0,
+                    sell_percentage: 50.0,
+                },
+                TakeProfitStep {
+                    gain_percentage: 300.0,
+                    sell_percentage: 100.0,
+                },
+            ],
+            stop_loss: Some(-50.0),
+            trailing_stop: Some(40.0),
+            max_hold: Some(Duration::from_secs(600)),
+        }
+    }
+
+    fn at(gain: f64, peak_gain: f64, steps_taken: usize) -> Progress {
+        Progress {
+            gain,
+            peak_gain,
+            held_for: Duration::from_secs(60),
+            steps_taken,
+        }
+    }
+
+    #[test]
+    fn ladder_and_stops() {
+        let rules = rules();
+
+        assert_eq!(rules.evaluate(&at(20.0, 20.0, 0)), None);
+        assert_eq!(
+            rules.evaluate(&at(120.0, 120.0, 0)),
+            Some(Exit {
+                reason: ExitReason::TakeProfit(0),
+                bps: 5_000
+            })
+        );
+        assert_eq!(rules.evaluate(&at(150.0, 150.0, 1)), None);
+        assert_eq!(
+            rules.evaluate(&at(310.0, 310.0, 1)).map(|exit| exit.bps),
+            Some(FULL_EXIT_BPS)
+        );
+
+        assert_eq!(
+            rules.evaluate(&at(-50.0, 0.0, 0)).map(|exit| exit.reason),
+            Some(ExitReason::StopLoss)
+        );
+        // Down over 40% from a 3x peak, while still up 70%.
+        assert_eq!(
+            rules.evaluate(&at(70.0, 200.0, 1)).map(|exit| exit.reason),
+            Some(ExitReason::TrailingStop)
+        );
+
+        let mut held = at(0.0, 0.0, 0);
+        held.held_for = Duration::from_secs(600);
+        assert_eq!(rules.evaluate(&held).map(|exit| exit.reason), Some(ExitReason::MaxHold));
+
+        let disabled = ExitRules {
+            enabled: false,
+            ..rules
+        };
+        assert_eq!(disabled.evaluate(&at(-90.0, 0.0, 0)), None);
+    }
+
+    #[test]
+    fn threshold_is_the_default_ladder() {
+        let rules = ExitRules::from_config(&SettingsConfig::default());
+
+        assert_eq!(rules.stop_loss, Some(-50.0));
+        assert_eq!(rules.trailing_stop, None);
+        assert_eq!(rules.max_hold, None);
+        assert_eq!(
+            rules.take_profit,
+            vec![TakeProfitStep {
+                gain_percentage: 100.0,
+                sell_percentage: 100.0
+            }]
+        );
+    }
+}
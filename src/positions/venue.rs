@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, program_pack::Pack, pubkey::Pubkey, signature::Keypair,
+    signer::Signer,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+use crate::{
+    daos_fun::{
+        daos_transaction::DAOS_PROGRAM,
+        inx_builder::{create_sell_instruction, fetch_curve, sell_quote},
+    },
+    env::SettingsConfig,
+    journal::{Order, TradeSide},
+    price_feed::{self, PriceSource},
+    pumpfun::{
+        events,
+        pump_interface::{
+            accounts::{BondingCurve, BondingCurveAccount},
+            builder::{
+                fetch_bonding_curve, fetch_global, generate_pump_sell_ix, get_bonding_curve,
+                PumpAmount, PUMP_PROGRAM,
+            },
+            quote::BondingCurveQuote,
+        },
+    },
+    raydium_amm::{
+        pool_searcher::amm_keys::{find_sol_pool, pool_keys_fetcher},
+        subscribe::PoolKeysSniper,
+        swap::{
+            instructions::{token_price_data, SwapDirection},
+            raydium_swap_out::raydium_out,
+        },
+    },
+    router::SniperRoute,
+    sender::{SendReport, TransactionSender},
+    utils::slippage::min_out,
+};
+
+use super::rules::FULL_EXIT_BPS;
+
+/// Where a position was bought, and so where it is quoted and sold.
+#[derive(Debug, Clone)]
+pub enum Venue {
+    PumpFun,
+    RaydiumAMM(Box<PoolKeysSniper>),
+    DaosFun { mint_program: Pubkey },
+}
+
+impl Venue {
+    pub fn route(&self) -> SniperRoute {
+        match self {
+            Venue::PumpFun => SniperRoute::PumpFun,
+            Venue::RaydiumAMM(_) => SniperRoute::RaydiumAMM,
+            Venue::DaosFun { .. } => SniperRoute::DaosFun,
+        }
+    }
+
+    /// Where `mint` trades, for tokens bought outside this session: its open
+    /// Pump.fun or daos.fun curve, else its Raydium SOL pool.
+    pub async fn detect(rpc_client: &RpcClient, mint: &Pubkey) -> eyre::Result<Venue> {
+        let bonding_curve = get_bonding_curve(*mint, &PUMP_PROGRAM);
+        if let Ok(data) = rpc_client.get_account_data(&bonding_curve).await {
+            if !BondingCurveAccount::deserialize(&data)?.0.complete {
+                return Ok(Venue::PumpFun);
+            }
+        }
+
+        if fetch_curve(rpc_client, mint).await.is_ok() {
+            let mint_program = rpc_client.get_account(mint).await?.owner;
+            return Ok(Venue::DaosFun { mint_program });
+        }
+
+        match Self::migrated(rpc_client, mint).await? {
+            Some(venue) => Ok(venue),
+            None => Err(eyre::eyre!(
+                "{} has no open position and is not on a Pump.fun or daos.fun curve or a Raydium SOL pool",
+                mint
+            )),
+        }
+    }
+
+    /// The Raydium SOL pool of `mint`, e.g. the one a completed Pump.fun curve
+    /// migrated to. `None` until such a pool exists.
+    pub async fn migrated(rpc_client: &RpcClient, mint: &Pubkey) -> eyre::Result<Option<Venue>> {
+        match find_sol_pool(rpc_client, mint).await? {
+            Some(pool) => Ok(Some(Venue::RaydiumAMM(Box::new(pool_keys_fetcher(pool).await?)))),
+            None => Ok(None),
+        }
+    }
+
+    /// Accounts the price of `mint` on this venue is streamed from.
+    pub fn price_source(&self, mint: Pubkey) -> Option<PriceSource> {
+        match self {
+            Venue::PumpFun => Some(PriceSource::PumpFun { mint }),
+            Venue::RaydiumAMM(pool_keys) => Some(PriceSource::RaydiumAMM(pool_keys.clone())),
+            Venue::DaosFun { .. } => None,
+        }
+    }
+
+    fn token_program(&self) -> Pubkey {
+        match self {
+            Venue::DaosFun { mint_program } => *mint_program,
+            _ => spl_token::id(),
+        }
+    }
+
+    /// The wallet's associated account of `mint`.
+    pub fn token_account(&self, wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(wallet, mint, &self.token_program())
+    }
+
+    /// Raw tokens of `mint` in the wallet's associated account, 0 once it is
+    /// closed. Read from the price feed while it streams the account.
+    pub async fn balance(
+        &self,
+        rpc_client: &RpcClient,
+        wallet: &Pubkey,
+        mint: &Pubkey,
+    ) -> eyre::Result<u64> {
+        if let Some(balance) = price_feed::balance(mint) {
+            return Ok(balance);
+        }
+
+        let token_account = self.token_account(wallet, mint);
+        let account = rpc_client
+            .get_account_with_commitment(&token_account, CommitmentConfig::confirmed())
+            .await?
+            .value;
+
+        match account {
+            Some(account) => Ok(spl_token::state::Account::unpack_from_slice(&account.data)?.amount),
+            None => Ok(0),
+        }
+    }
+
+    /// Lamports `tokens` of `mint` would sell for right now.
+    pub async fn quote(
+        &self,
+        rpc_client: &Arc<RpcClient>,
+        wallet: &Arc<Keypair>,
+        mint: &Pubkey,
+        tokens: u64,
+    ) -> eyre::Result<u64> {
+        if let Some(price) = price_feed::latest(mint) {
+            return Ok(price.sell_quote(tokens));
+        }
+
+        match self {
+            Venue::PumpFun => {
+                let (curve, global) =
+                    tokio::try_join!(pump_curve(rpc_client, mint), fetch_global(rpc_client))?;
+                if curve.complete {
+                    return Err(eyre::eyre!("bonding curve of {} is complete", mint));
+                }
+
+                Ok(BondingCurveQuote::with_global(&curve, &global)
+                    .sell_exact_tokens(tokens)?
+                    .sol_amount)
+            }
+            Venue::RaydiumAMM(pool_keys) => Ok(token_price_data(
+                rpc_client.clone(),
+                *pool_keys.clone(),
+                wallet.clone(),
+                tokens,
+                SwapDirection::Coin2PC,
+            )
+            .await? as u64),
+            Venue::DaosFun { .. } => Ok(sell_quote(&fetch_curve(rpc_client, mint).await?, tokens)),
+        }
+    }
+
+    /// Sells exactly `bps` of the `balance` held, without prompting.
+    pub async fn sell(
+        &self,
+        wallet: &Arc<Keypair>,
+        args: &SettingsConfig,
+        mint: Pubkey,
+        balance: u64,
+        bps: u16,
+    ) -> eyre::Result<SendReport> {
+        let tokens = (balance as u128 * bps.min(FULL_EXIT_BPS) as u128 / FULL_EXIT_BPS as u128) as u64;
+        let rpc_client = Arc::new(RpcClient::new(args.network.rpc_url.clone()));
+        let slippage_bps = args.slippage_bps(&self.route());
+
+        match self {
+            Venue::PumpFun => {
+                let (sell_ix, _) = generate_pump_sell_ix(
+                    &rpc_client,
+                    mint,
+                    PumpAmount::Tokens(tokens),
+                    wallet.clone(),
+                    slippage_bps,
+                )
+                .await?;
+                TransactionSender::from_config(args, rpc_client)
+                    .with_order(Order::new(SniperRoute::PumpFun, TradeSide::Sell, mint, tokens))
+                    .send(wallet, &sell_ix, &[])
+                    .await
+            }
+            Venue::RaydiumAMM(pool_keys) => raydium_out(*pool_keys.clone(), tokens).await,
+            Venue::DaosFun { mint_program } => {
+                let curve = fetch_curve(&rpc_client, &mint).await?;
+                let sell_ix = create_sell_instruction(
+                    &DAOS_PROGRAM,
+                    &wallet.pubkey(),
+                    &mint,
+                    mint_program,
+                    tokens,
+                    min_out(sell_quote(&curve, tokens), slippage_bps),
+                )?;
+                TransactionSender::from_config(args, rpc_client)
+                    .with_order(Order::new(SniperRoute::DaosFun, TradeSide::Sell, mint, tokens))
+                    .send(wallet, &[sell_ix], &[])
+                    .await
+            }
+        }
+    }
+}
+
+/// The bonding curve of `mint`, from the price feed or the trade events while
+/// either streams it, and over RPC otherwise.
+pub async fn pump_curve(rpc_client: &RpcClient, mint: &Pubkey) -> eyre::Result<BondingCurve> {
+    let bonding_curve = get_bonding_curve(*mint, &PUMP_PROGRAM);
+    if let Some(data) = price_feed::account_data(mint, &bonding_curve) {
+        return Ok(BondingCurveAccount::deserialize(&data)?.0);
+    }
+
+    match events::live_curve(mint) {
+        Some(curve) => Ok(curve.bonding_curve()),
+        None => {
+            let curve = fetch_bonding_curve(rpc_client, mint).await?;
+            events::seed(*mint, &curve);
+            Ok(curve)
+        }
+    }
+}
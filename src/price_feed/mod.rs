@@ -0,0 +1,338 @@
+pub mod source;
+
+use std::{collections::HashMap, str::FromStr, sync::Mutex};
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use tokio::{sync::watch, task::JoinHandle};
+use yellowstone_grpc_proto::geyser::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterAccounts, SubscribeUpdateAccount,
+};
+
+use crate::{
+    env::SettingsConfig,
+    geyser::{GeyserSubscription, RequestHandle, StreamState},
+};
+
+pub use source::{Price, PriceSource};
+
+static FEED: Lazy<Mutex<Feed>> = Lazy::new(|| Mutex::new(Feed::default()));
+
+/// Mints being priced, and the one Geyser stream that follows all their
+/// accounts.
+#[derive(Default)]
+struct Feed {
+    tracked: HashMap<Pubkey, Tracked>,
+    stream: Option<JoinHandle<()>>,
+    requests: Option<RequestHandle>,
+    state: Option<watch::Receiver<StreamState>>,
+}
+
+struct Tracked {
+    source: PriceSource,
+    /// Token account of the mint whose balance is followed with the price.
+    holding: Pubkey,
+    /// Latest data of each account, and the slot it was written in.
+    data: HashMap<Pubkey, Vec<u8>>,
+    slots: HashMap<Pubkey, u64>,
+    price: watch::Sender<Option<Price>>,
+    /// Set while updates may have been missed, until the accounts are read
+    /// again.
+    stale: bool,
+}
+
+impl Tracked {
+    fn accounts(&self) -> Vec<Pubkey> {
+        let mut accounts = self.source.accounts();
+        accounts.push(self.holding);
+        accounts
+    }
+
+    fn apply(&mut self, account: Pubkey, slot: u64, data: Vec<u8>) {
+        if self.slots.get(&account).is_some_and(|seen| *seen > slot) {
+            return;
+        }
+        self.slots.insert(account, slot);
+        self.data.insert(account, data);
+
+        match self.source.price(&self.data) {
+            Ok(Some(mut price)) => {
+                price.slot = self.slots.values().copied().max().unwrap_or(slot);
+                self.price.send_if_modified(|current| {
+                    let changed = *current != Some(price);
+                    *current = Some(price);
+                    changed
+                });
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Cannot price {}: {}", self.source.mint(), e),
+        }
+    }
+}
+
+/// Starts pricing the mint of `source` from account updates, and returns its
+/// prices. The balance of `holding`, a token account of the mint, is followed
+/// along with them. The accounts are read once over RPC so there is a price
+/// before the first update.
+pub async fn track(
+    args: &SettingsConfig,
+    source: PriceSource,
+    holding: Pubkey,
+) -> eyre::Result<watch::Receiver<Option<Price>>> {
+    if args.network.grpc_url.is_empty() {
+        return Err(eyre::eyre!("Set network.grpc_url to stream prices"));
+    }
+    let mint = source.mint();
+    if let Some(prices) = subscribe(&mint) {
+        return Ok(prices);
+    }
+
+    let rpc_client = RpcClient::new(args.network.rpc_url.clone());
+    let (slot, accounts) = read(&rpc_client, &source, &holding).await?;
+
+    let (price, _) = watch::channel(None);
+    let mut tracked = Tracked {
+        source,
+        holding,
+        data: HashMap::new(),
+        slots: HashMap::new(),
+        price,
+        stale: false,
+    };
+    for (account, data) in accounts {
+        tracked.apply(account, slot, data);
+    }
+
+    let mut feed = FEED.lock().unwrap();
+    feed.tracked.entry(mint).or_insert(tracked);
+    resubscribe(&mut feed, args);
+    Ok(feed.tracked[&mint].price.subscribe())
+}
+
+/// Stops pricing `mint`.
+pub fn untrack(args: &SettingsConfig, mint: &Pubkey) {
+    let mut feed = FEED.lock().unwrap();
+    if feed.tracked.remove(mint).is_some() {
+        resubscribe(&mut feed, args);
+    }
+}
+
+/// Prices of a tracked `mint`.
+pub fn subscribe(mint: &Pubkey) -> Option<watch::Receiver<Option<Price>>> {
+    FEED.lock()
+        .unwrap()
+        .tracked
+        .get(mint)
+        .map(|tracked| tracked.price.subscribe())
+}
+
+/// The price of `mint`, while the stream is up to keep it current and no
+/// update since the last reconnect can be missing from it.
+pub fn latest(mint: &Pubkey) -> Option<Price> {
+    let feed = FEED.lock().unwrap();
+    let price = *live(&feed, mint)?.price.borrow();
+    price
+}
+
+/// Raw tokens in the account followed with `mint`, 0 once it is closed. Kept
+/// current like [`latest`].
+pub fn balance(mint: &Pubkey) -> Option<u64> {
+    let feed = FEED.lock().unwrap();
+    let tracked = live(&feed, mint)?;
+    let data = tracked.data.get(&tracked.holding)?;
+    if data.is_empty() {
+        return Some(0);
+    }
+    source::token_account(data).ok().map(|(_, amount)| amount)
+}
+
+/// Latest data of `account`, one of those `mint` is priced from, e.g. its
+/// bonding curve. Kept current like [`latest`].
+pub fn account_data(mint: &Pubkey, account: &Pubkey) -> Option<Vec<u8>> {
+    let feed = FEED.lock().unwrap();
+    live(&feed, mint)?.data.get(account).cloned()
+}
+
+fn live<'a>(feed: &'a Feed, mint: &Pubkey) -> Option<&'a Tracked> {
+    let connected = feed
+        .state
+        .as_ref()
+        .is_some_and(|state| *state.borrow() == StreamState::Connected);
+    if !connected {
+        return None;
+    }
+    feed.tracked.get(mint).filter(|tracked| !tracked.stale)
+}
+
+/// Points the stream at the mints now tracked. The open stream is kept and
+/// only sent the new filters, so other mints keep their updates; a stream is
+/// only started when there is none.
+fn resubscribe(feed: &mut Feed, args: &SettingsConfig) {
+    if feed.tracked.is_empty() {
+        if let Some(stream) = feed.stream.take() {
+            stream.abort();
+        }
+        feed.requests = None;
+        feed.state = None;
+        return;
+    }
+
+    let request = price_request(&feed.tracked);
+    let running = feed
+        .stream
+        .as_ref()
+        .is_some_and(|stream| !stream.is_finished());
+    if let (true, Some(requests)) = (running, &feed.requests) {
+        requests.update(request);
+        return;
+    }
+
+    let mut stream = match GeyserSubscription::from_config(args, request).start() {
+        Ok(stream) => stream,
+        Err(e) => {
+            warn!("Cannot stream prices: {}", e);
+            return;
+        }
+    };
+    info!("Streaming prices of {} mints", feed.tracked.len());
+
+    let rpc_url = args.network.rpc_url.clone();
+    let mut state = stream.watch_state();
+    feed.state = Some(stream.watch_state());
+    feed.requests = Some(stream.requests());
+    feed.stream = Some(tokio::spawn(async move {
+        let mut reconnecting = false;
+        loop {
+            tokio::select! {
+                message = stream.next() => {
+                    let Some(message) = message else {
+                        break;
+                    };
+                    if let Some(UpdateOneof::Account(update)) = message.update_oneof {
+                        apply(&message.filters, update);
+                    }
+                }
+                Ok(()) = state.changed() => {
+                    match *state.borrow_and_update() {
+                        StreamState::Reconnecting { .. } if !reconnecting => {
+                            reconnecting = true;
+                            mark_stale();
+                        }
+                        StreamState::Connected if reconnecting => {
+                            reconnecting = false;
+                            tokio::spawn(reseed(rpc_url.clone()));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }));
+}
+
+/// Follows the accounts of every tracked mint. Filters are named after the
+/// mint whose accounts they follow.
+fn price_request(tracked: &HashMap<Pubkey, Tracked>) -> SubscribeRequest {
+    let accounts = tracked
+        .iter()
+        .map(|(mint, tracked)| {
+            (
+                mint.to_string(),
+                SubscribeRequestFilterAccounts {
+                    account: tracked
+                        .accounts()
+                        .iter()
+                        .map(Pubkey::to_string)
+                        .collect(),
+                    owner: vec![],
+                    filters: vec![],
+                },
+            )
+        })
+        .collect();
+    SubscribeRequest {
+        accounts,
+        commitment: Some(CommitmentLevel::Processed.into()),
+        ..Default::default()
+    }
+}
+
+/// Reads the accounts of `source` and `holding` at the commitment the stream
+/// follows, with the slot they were read at. A missing `holding` reads as
+/// empty, a closed account holds nothing.
+async fn read(
+    rpc_client: &RpcClient,
+    source: &PriceSource,
+    holding: &Pubkey,
+) -> eyre::Result<(u64, Vec<(Pubkey, Vec<u8>)>)> {
+    let mut accounts = source.accounts();
+    accounts.push(*holding);
+    let response = rpc_client
+        .get_multiple_accounts_with_commitment(&accounts, CommitmentConfig::processed())
+        .await?;
+    let data = accounts
+        .into_iter()
+        .zip(response.value)
+        .filter_map(|(account, data)| match data {
+            Some(data) => Some((account, data.data)),
+            None if account == *holding => Some((account, vec![])),
+            None => None,
+        })
+        .collect();
+    Ok((response.context.slot, data))
+}
+
+fn mark_stale() {
+    for tracked in FEED.lock().unwrap().tracked.values_mut() {
+        tracked.stale = true;
+    }
+}
+
+/// Reads every tracked mint again after a reconnect, for the updates missed
+/// while the stream was down.
+async fn reseed(rpc_url: String) {
+    let rpc_client = RpcClient::new(rpc_url);
+    let sources: Vec<(PriceSource, Pubkey)> = FEED
+        .lock()
+        .unwrap()
+        .tracked
+        .values()
+        .map(|tracked| (tracked.source.clone(), tracked.holding))
+        .collect();
+    for (source, holding) in sources {
+        let mint = source.mint();
+        match read(&rpc_client, &source, &holding).await {
+            Ok((slot, accounts)) => {
+                let mut feed = FEED.lock().unwrap();
+                if let Some(tracked) = feed.tracked.get_mut(&mint) {
+                    for (account, data) in accounts {
+                        tracked.apply(account, slot, data);
+                    }
+                    tracked.stale = false;
+                }
+            }
+            Err(e) => warn!("Cannot read {} after reconnecting: {}", mint, e),
+        }
+    }
+}
+
+fn apply(filters: &[String], update: SubscribeUpdateAccount) {
+    let Some(account) = update.account else {
+        return;
+    };
+    let Ok(pubkey) = Pubkey::try_from(account.pubkey.as_slice()) else {
+        return;
+    };
+    let mut feed = FEED.lock().unwrap();
+    for mint in filters
+        .iter()
+        .filter_map(|name| Pubkey::from_str(name).ok())
+    {
+        if let Some(tracked) = feed.tracked.get_mut(&mint) {
+            tracked.apply(pubkey, update.slot, account.data.clone());
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+
+use crate::{
+    pumpfun::pump_interface::{
+        accounts::{BondingCurveAccount, GlobalAccount},
+        builder::{get_bonding_curve, GLOBAL_STATE, PUMP_PROGRAM},
+    },
+    raydium_amm::{
+        subscribe::PoolKeysSniper,
+        swap::instructions::{RAYDIUM_FEE_BPS, SOLC_MINT},
+    },
+    utils::slippage::{constant_product_out, MAX_BPS},
+};
+
+/// Offsets of `native_coin_total` and `native_pc_total` in a padded Serum
+/// `OpenOrders` account.
+const OPEN_ORDERS_COIN_TOTAL: usize = 85;
+const OPEN_ORDERS_PC_TOTAL: usize = 101;
+
+/// Accounts a mint is priced from.
+#[derive(Debug, Clone)]
+pub enum PriceSource {
+    /// Coin and pc vaults, plus the funds the pool keeps on its market.
+    RaydiumAMM(Box<PoolKeysSniper>),
+    /// The bonding curve, and the global account that sets its fee.
+    PumpFun {
+        mint: Pubkey,
+    },
+}
+
+/// A pool or curve as its latest account updates left it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Price {
+    pub sol_reserves: u64,
+    pub token_reserves: u64,
+    /// Most SOL a sell can take out, the real reserves of a bonding curve.
+    pub max_sol_out: u64,
+    pub fee_bps: u64,
+    /// Slot of the newest account update that went into the price.
+    pub slot: u64,
+}
+
+impl Price {
+    /// Lamports `tokens` sell for, net of the trade fee.
+    pub fn sell_quote(&self, tokens: u64) -> u64 {
+        let sol_out = constant_product_out(self.token_reserves, self.sol_reserves, tokens, 0)
+            .min(self.max_sol_out);
+        sol_out - sol_out * self.fee_bps.min(MAX_BPS) / MAX_BPS
+    }
+}
+
+impl PriceSource {
+    /// The token priced, never wrapped SOL.
+    pub fn mint(&self) -> Pubkey {
+        match self {
+            PriceSource::RaydiumAMM(pool_keys) if pool_keys.base_mint == SOLC_MINT => {
+                pool_keys.quote_mint
+            }
+            PriceSource::RaydiumAMM(pool_keys) => pool_keys.base_mint,
+            PriceSource::PumpFun { mint } => *mint,
+        }
+    }
+
+    pub fn accounts(&self) -> Vec<Pubkey> {
+        match self {
+            PriceSource::RaydiumAMM(pool_keys) => vec![
+                pool_keys.base_vault,
+                pool_keys.quote_vault,
+                pool_keys.open_orders,
+            ],
+            PriceSource::PumpFun { mint } => {
+                vec![get_bonding_curve(*mint, &PUMP_PROGRAM), GLOBAL_STATE]
+            }
+        }
+    }
+
+    /// The price once the data of every account is known. A completed bonding
+    /// curve has none.
+    pub fn price(&self, data: &HashMap<Pubkey, Vec<u8>>) -> eyre::Result<Option<Price>> {
+        let accounts = self.accounts();
+        let Some(data) = accounts
+            .iter()
+            .map(|account| data.get(account))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Ok(None);
+        };
+
+        let price = match self {
+            PriceSource::RaydiumAMM(_) => {
+                let (coin_mint, coin_vault) = token_account(data[0])?;
+                let (_, pc_vault) = token_account(data[1])?;
+                let coin = coin_vault + read_u64(data[2], OPEN_ORDERS_COIN_TOTAL)?;
+                let pc = pc_vault + read_u64(data[2], OPEN_ORDERS_PC_TOTAL)?;
+                let (sol_reserves, token_reserves) = if coin_mint == SOLC_MINT {
+                    (coin, pc)
+                } else {
+                    (pc, coin)
+                };
+                Price {
+                    sol_reserves,
+                    token_reserves,
+                    max_sol_out: sol_reserves,
+                    fee_bps: RAYDIUM_FEE_BPS,
+                    slot: 0,
+                }
+            }
+            PriceSource::PumpFun { .. } => {
+                let curve = BondingCurveAccount::deserialize(data[0])?.0;
+                if curve.complete {
+                    return Ok(None);
+                }
+                let global = GlobalAccount::deserialize(data[1])?.0;
+                Price {
+                    sol_reserves: curve.virtual_sol_reserves,
+                    token_reserves: curve.virtual_token_reserves,
+                    max_sol_out: curve.real_sol_reserves,
+                    fee_bps: global.fee_basis_points,
+                    slot: 0,
+                }
+            }
+        };
+        Ok(Some(price))
+    }
+}
+
+/// Mint and amount of an SPL token account. Token-2022 extensions follow the
+/// base layout and are ignored.
+pub(super) fn token_account(data: &[u8]) -> eyre::Result<(Pubkey, u64)> {
+    let base = data
+        .get(..spl_token::state::Account::LEN)
+        .ok_or_else(|| eyre::eyre!("Token account of {} bytes", data.len()))?;
+    let account = spl_token::state::Account::unpack_from_slice(base)?;
+    Ok((account.mint, account.amount))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> eyre::Result<u64> {
+    data.get(offset..offset + 8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or_else(|| eyre::eyre!("Open orders account of {} bytes", data.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::program_option::COption;
+
+    use super::*;
+    use crate::{pumpfun::pump_interface::accounts::BondingCurve, testing::pump_global};
+
+    fn vault(mint: Pubkey, amount: u64) -> Vec<u8> {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint,
+            owner: Pubkey::new_unique(),
+            amount,
+            delegate: COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        data
+    }
+
+    #[test]
+    fn amm_reserves_include_the_open_orders() {
+        let mint = Pubkey::new_unique();
+        let pool_keys = PoolKeysSniper {
+            base_mint: mint,
+            quote_mint: SOLC_MINT,
+            base_vault: Pubkey::new_unique(),
+            quote_vault: Pubkey::new_unique(),
+            open_orders: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        let source = PriceSource::RaydiumAMM(Box::new(pool_keys.clone()));
+
+        let mut open_orders = vec![0; OPEN_ORDERS_PC_TOTAL + 8];
+        open_orders[OPEN_ORDERS_COIN_TOTAL..OPEN_ORDERS_COIN_TOTAL + 8]
+            .copy_from_slice(&1_000u64.to_le_bytes());
+        open_orders[OPEN_ORDERS_PC_TOTAL..].copy_from_slice(&500u64.to_le_bytes());
+
+        let mut data = HashMap::new();
+        data.insert(pool_keys.base_vault, vault(mint, 999_000));
+        assert_eq!(source.price(&data).unwrap(), None);
+
+        data.insert(pool_keys.quote_vault, vault(SOLC_MINT, 1_999_500));
+        data.insert(pool_keys.open_orders, open_orders);
+        let price = source.price(&data).unwrap().unwrap();
+        assert_eq!(source.mint(), mint);
+        assert_eq!(
+            (price.sol_reserves, price.token_reserves),
+            (2_000_000, 1_000_000)
+        );
+        // 1/3 of the SOL, less the 0.25% fee
+        assert_eq!(price.sell_quote(500_000), 665_000);
+    }
+
+    #[test]
+    fn curve_fee_comes_from_the_global_account() {
+        let mint = Pubkey::new_unique();
+        let source = PriceSource::PumpFun { mint };
+        let curve = BondingCurve {
+            virtual_token_reserves: 1_000_000,
+            virtual_sol_reserves: 2_000_000,
+            real_token_reserves: 800_000,
+            real_sol_reserves: 2_000_000,
+            token_total_supply: 1_000_000,
+            complete: false,
+        };
+        let mut global = pump_global();
+        global.fee_basis_points = 200;
+
+        let mut data = HashMap::new();
+        data.insert(
+            get_bonding_curve(mint, &PUMP_PROGRAM),
+            BondingCurveAccount(curve).try_to_vec().unwrap(),
+        );
+        assert_eq!(source.price(&data).unwrap(), None);
+
+        data.insert(GLOBAL_STATE, GlobalAccount(global).try_to_vec().unwrap());
+        let price = source.price(&data).unwrap().unwrap();
+        assert_eq!(price.fee_bps, 200);
+        // Half the virtual SOL, less the 2% fee
+        assert_eq!(price.sell_quote(1_000_000), 980_000);
+    }
+}
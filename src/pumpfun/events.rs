@@ -0,0 +1,294 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use once_cell::sync::Lazy;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::geyser::ParsedTransaction;
+
+use super::pump_interface::{
+    accounts::BondingCurve,
+    builder::PUMP_PROGRAM,
+    events::{
+        CompleteEvent, CompleteEventEvent, TradeEvent, TradeEventEvent, COMPLETE_EVENT_EVENT_DISCM,
+        TRADE_EVENT_EVENT_DISCM,
+    },
+};
+
+/// Anchor's `emit_cpi!` tag, ahead of the event discriminator in the data of
+/// the self-CPI.
+pub const EVENT_IX_TAG: [u8; 8] = [228, 69, 165, 46, 81, 203, 154, 29];
+
+/// Curve parameters every launch starts from.
+const INITIAL_VIRTUAL_SOL_RESERVES: u64 = 30_000_000_000;
+const INITIAL_VIRTUAL_TOKEN_RESERVES: u64 = 1_073_000_000_000_000;
+pub const INITIAL_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000;
+pub const TOKEN_TOTAL_SUPPLY: u64 = 1_000_000_000_000_000;
+
+/// Pump.fun trades around the clock, a feed quiet for this long is down.
+const FEED_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Curves kept before those not traded in `STALE_SLOTS` are dropped.
+const MAX_CURVES: usize = 50_000;
+
+/// About an hour.
+const STALE_SLOTS: u64 = 9_000;
+
+static CURVES: Lazy<Mutex<HashMap<Pubkey, CurveState>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+static LAST_EVENT: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PumpEvent {
+    Trade(TradeEvent),
+    Complete(CompleteEvent),
+}
+
+impl PumpEvent {
+    /// Decodes an event from its discriminator onwards, `None` for events
+    /// other than trades and completions.
+    pub fn decode(data: &[u8]) -> Option<PumpEvent> {
+        let mut buf = data;
+        match data.get(..8)? {
+            discm if discm == TRADE_EVENT_EVENT_DISCM => TradeEventEvent::deserialize(&mut buf)
+                .ok()
+                .map(|event| PumpEvent::Trade(event.0)),
+            discm if discm == COMPLETE_EVENT_EVENT_DISCM => {
+                CompleteEventEvent::deserialize(&mut buf)
+                    .ok()
+                    .map(|event| PumpEvent::Complete(event.0))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn mint(&self) -> Pubkey {
+        match self {
+            PumpEvent::Trade(trade) => trade.mint,
+            PumpEvent::Complete(complete) => complete.mint,
+        }
+    }
+}
+
+/// Events `tx` emitted, from its self-CPIs or, for transactions from before
+/// `emit_cpi!`, its "Program data:" logs.
+pub fn events(tx: &ParsedTransaction) -> Vec<PumpEvent> {
+    if tx.failed {
+        return Vec::new();
+    }
+
+    let events: Vec<PumpEvent> = tx
+        .find_all_instructions(&PUMP_PROGRAM)
+        .filter_map(|instruction| instruction.data.strip_prefix(&EVENT_IX_TAG))
+        .filter_map(PumpEvent::decode)
+        .collect();
+    if events.is_empty() {
+        return events_from_logs(&tx.logs);
+    }
+    events
+}
+
+/// Events in the "Program data:" lines Pump.fun itself logged.
+pub fn events_from_logs(logs: &[String]) -> Vec<PumpEvent> {
+    let program = PUMP_PROGRAM.to_string();
+    let mut invoked: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs {
+        if let Some(data) = log.strip_prefix("Program data: ") {
+            if invoked.last() == Some(&program.as_str()) {
+                events.extend(
+                    STANDARD
+                        .decode(data)
+                        .ok()
+                        .and_then(|data| PumpEvent::decode(&data)),
+                );
+            }
+            continue;
+        }
+
+        let mut words = log.split(' ');
+        if let (Some("Program"), Some(id), Some(status)) =
+            (words.next(), words.next(), words.next())
+        {
+            match status {
+                "invoke" => invoked.push(id),
+                "success" | "failed:" => {
+                    invoked.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    events
+}
+
+/// A curve as its latest event left it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurveState {
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    pub complete: bool,
+    /// Slot of the latest event, 0 when seeded from the account.
+    pub slot: u64,
+}
+
+impl CurveState {
+    /// The account this state stands for. Real reserves follow from the
+    /// virtual ones since every curve starts from the same parameters.
+    pub fn bonding_curve(&self) -> BondingCurve {
+        BondingCurve {
+            virtual_token_reserves: self.virtual_token_reserves,
+            virtual_sol_reserves: self.virtual_sol_reserves,
+            real_token_reserves: self
+                .virtual_token_reserves
+                .saturating_sub(INITIAL_VIRTUAL_TOKEN_RESERVES - INITIAL_REAL_TOKEN_RESERVES),
+            real_sol_reserves: self
+                .virtual_sol_reserves
+                .saturating_sub(INITIAL_VIRTUAL_SOL_RESERVES),
+            token_total_supply: TOKEN_TOTAL_SUPPLY,
+            complete: self.complete,
+        }
+    }
+}
+
+/// Applies the events `tx` emitted to the live curves.
+pub fn observe(tx: &ParsedTransaction) {
+    for event in events(tx) {
+        apply(&event, tx.slot);
+    }
+}
+
+/// Applies an event emitted in `slot`. Events from before the latest one seen
+/// for the mint are stale and ignored.
+pub fn apply(event: &PumpEvent, slot: u64) {
+    *LAST_EVENT.lock().unwrap() = Some(Instant::now());
+
+    let mut curves = CURVES.lock().unwrap();
+    if curves.len() >= MAX_CURVES && !curves.contains_key(&event.mint()) {
+        curves.retain(|_, curve| curve.slot + STALE_SLOTS >= slot);
+    }
+
+    let current = curves.get(&event.mint()).copied();
+    if current.is_some_and(|current| current.slot > slot) {
+        return;
+    }
+
+    let next = match event {
+        PumpEvent::Trade(trade) => CurveState {
+            virtual_sol_reserves: trade.virtual_sol_reserves,
+            virtual_token_reserves: trade.virtual_token_reserves,
+            complete: current.is_some_and(|current| current.complete),
+            slot,
+        },
+        PumpEvent::Complete(_) => CurveState {
+            complete: true,
+            slot,
+            ..current.unwrap_or(CurveState {
+                virtual_sol_reserves: 0,
+                virtual_token_reserves: 0,
+                complete: true,
+                slot,
+            })
+        },
+    };
+    curves.insert(event.mint(), next);
+}
+
+/// Seeds `mint` from its account, for curves not traded since the feed
+/// started. Any event overrides it.
+pub fn seed(mint: Pubkey, curve: &BondingCurve) {
+    CURVES.lock().unwrap().entry(mint).or_insert(CurveState {
+        virtual_sol_reserves: curve.virtual_sol_reserves,
+        virtual_token_reserves: curve.virtual_token_reserves,
+        complete: curve.complete,
+        slot: 0,
+    });
+}
+
+/// The live state of `mint`, `None` when it was never seen or no event has
+/// arrived lately to keep it current.
+pub fn live_curve(mint: &Pubkey) -> Option<CurveState> {
+    let live = LAST_EVENT
+        .lock()
+        .unwrap()
+        .is_some_and(|last| last.elapsed() < FEED_TIMEOUT);
+    if !live {
+        return None;
+    }
+    CURVES.lock().unwrap().get(mint).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSerialize;
+
+    use crate::geyser::ParsedInstruction;
+
+    use super::*;
+
+    fn trade(mint: Pubkey, virtual_sol_reserves: u64) -> TradeEvent {
+        TradeEvent {
+            mint,
+            sol_amount: 1_000_000_000,
+            token_amount: 34_612_903_225_806,
+            is_buy: true,
+            user: Pubkey::new_unique(),
+            timestamp: 1_700_000_000,
+            virtual_sol_reserves,
+            virtual_token_reserves: 1_038_387_096_774_194,
+        }
+    }
+
+    #[test]
+    fn trades_decode_from_the_cpi_and_the_logs() {
+        let mint = Pubkey::new_unique();
+        let event = TradeEventEvent(trade(mint, 31_000_000_000))
+            .try_to_vec()
+            .unwrap();
+
+        let tx = ParsedTransaction {
+            instructions: vec![ParsedInstruction {
+                program_id: PUMP_PROGRAM,
+                accounts: vec![],
+                data: vec![],
+                inner: vec![ParsedInstruction {
+                    program_id: PUMP_PROGRAM,
+                    accounts: vec![],
+                    data: [EVENT_IX_TAG.to_vec(), event.clone()].concat(),
+                    inner: vec![],
+                }],
+            }],
+            ..Default::default()
+        };
+        let decoded = events(&tx);
+        assert!(matches!(&decoded[..], [PumpEvent::Trade(t)] if t.mint == mint && t.is_buy));
+
+        let logs = vec![
+            format!("Program {} invoke [1]", PUMP_PROGRAM),
+            format!("Program data: {}", STANDARD.encode(&event)),
+            format!("Program {} success", PUMP_PROGRAM),
+            "Program 11111111111111111111111111111111 invoke [1]".to_string(),
+            format!("Program data: {}", STANDARD.encode(&event)),
+            "Program 11111111111111111111111111111111 success".to_string(),
+        ];
+        assert_eq!(events_from_logs(&logs).len(), 1);
+    }
+
+    #[test]
+    fn stale_events_do_not_roll_the_curve_back() {
+        let mint = Pubkey::new_unique();
+        apply(&PumpEvent::Trade(trade(mint, 31_000_000_000)), 10);
+        apply(&PumpEvent::Trade(trade(mint, 32_000_000_000)), 9);
+
+        let curve = live_curve(&mint).unwrap().bonding_curve();
+        assert_eq!(curve.virtual_sol_reserves, 31_000_000_000);
+        assert_eq!(curve.real_sol_reserves, 1_000_000_000);
+        assert_eq!(curve.real_token_reserves, 758_487_096_774_194);
+        assert!(!curve.complete);
+    }
+}
@@ -0,0 +1,405 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use futures::{stream, StreamExt};
+use once_cell::sync::Lazy;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+    signature::Signature,
+};
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::{
+    env::{LaunchFilters, SettingsConfig},
+    geyser::ParsedTransaction,
+    pumpfun::{
+        events::{events, PumpEvent},
+        pump_interface::{
+            builder::PUMP_PROGRAM,
+            instructions::{BuyIxData, CreateIxArgs, CreateIxData},
+        },
+    },
+};
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// Most recent transactions of a creator searched for earlier launches.
+const PRIOR_LAUNCH_LOOKBACK: usize = 100;
+
+/// Transactions fetched at once while counting earlier launches.
+const PRIOR_LAUNCH_FETCHES: usize = 10;
+
+/// Position of the creator in the accounts of a Pump.fun create.
+pub const CREATE_USER_INDEX: usize = 7;
+
+/// Launches seen in the past hour per creator, for the hourly rate.
+static HISTORY: Lazy<Mutex<LaunchHistory>> = Lazy::new(|| Mutex::new(LaunchHistory::default()));
+
+/// What the filters look at in a launch transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Launch {
+    pub mint: Pubkey,
+    pub creator: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    /// Lamports the creator holds after the launch.
+    pub creator_balance: u64,
+    /// Lamports the creator spent buying in the launch transaction.
+    pub dev_buy: Option<u64>,
+}
+
+impl Launch {
+    /// Reads a launch of `mint` by `creator`, the user of its create
+    /// instruction, who need not be the fee payer of `tx`.
+    pub fn from_transaction(
+        tx: &ParsedTransaction,
+        mint: Pubkey,
+        creator: Pubkey,
+        coin: &CreateIxArgs,
+    ) -> Self {
+        let creator_balance = tx
+            .accounts
+            .iter()
+            .find(|account| account.pubkey == creator)
+            .map_or(0, |account| account.post_balance);
+
+        let traded: Vec<u64> = events(tx)
+            .into_iter()
+            .filter_map(|event| match event {
+                PumpEvent::Trade(trade)
+                    if trade.is_buy && trade.user == creator && trade.mint == mint =>
+                {
+                    Some(trade.sol_amount)
+                }
+                _ => None,
+            })
+            .collect();
+        // Without events, the most the creator allowed the buy to cost.
+        let dev_buy = if traded.is_empty() {
+            tx.find_all_instructions(&PUMP_PROGRAM)
+                .filter(|ix| ix.accounts.get(2) == Some(&mint))
+                .filter_map(|ix| BuyIxData::deserialize(&ix.data).ok())
+                .map(|buy| buy.0.max_sol_cost)
+                .reduce(|total, cost| total + cost)
+        } else {
+            Some(traded.iter().sum())
+        };
+
+        Launch {
+            mint,
+            creator,
+            name: coin.name.clone(),
+            symbol: coin.symbol.clone(),
+            uri: coin.uri.clone(),
+            creator_balance,
+            dev_buy,
+        }
+    }
+}
+
+/// The rule a launch failed and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejection {
+    pub rule: &'static str,
+    pub reason: String,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.rule, self.reason)
+    }
+}
+
+fn reject(rule: &'static str, reason: String) -> Result<(), Rejection> {
+    Err(Rejection { rule, reason })
+}
+
+/// Times each creator launched in the past hour, newest last.
+#[derive(Debug, Default)]
+pub struct LaunchHistory {
+    launches: HashMap<Pubkey, Vec<Instant>>,
+}
+
+impl LaunchHistory {
+    /// Adds a launch by `creator` at `at`, and returns how many it made in the
+    /// hour up to `at`, this one included. Launches older than that are
+    /// forgotten, for every creator.
+    pub fn record(&mut self, creator: Pubkey, at: Instant) -> usize {
+        self.launches.retain(|_, launches| {
+            launches.retain(|launch| at.saturating_duration_since(*launch) < HOUR);
+            !launches.is_empty()
+        });
+        let launches = self.launches.entry(creator).or_default();
+        launches.push(at);
+        launches.len()
+    }
+}
+
+/// Records `launch` and checks it against every rule, in the order they are
+/// listed in the config, except `max_prior_launches`: it needs RPC, so it is
+/// only checked once every other rule passed. Every launch counts towards its
+/// creator's hourly rate, rejected or not.
+pub async fn screen(args: &SettingsConfig, launch: &Launch) -> Result<(), Rejection> {
+    let filters = &args.sniper.pump_fun_filters;
+    let last_hour = HISTORY
+        .lock()
+        .unwrap()
+        .record(launch.creator, Instant::now());
+    check(filters, launch, last_hour)?;
+
+    let Some(max) = filters.max_prior_launches else {
+        return Ok(());
+    };
+    let rpc_client = RpcClient::new(args.network.rpc_url.clone());
+    match prior_launches(&rpc_client, launch, max as usize + 1).await {
+        Ok(prior) => check_prior_launches(max, prior),
+        Err(e) => reject(
+            "max_prior_launches",
+            format!("cannot count the creator's launches: {}", e),
+        ),
+    }
+}
+
+/// Counts Pump.fun coins `launch.creator` created before this one, among its
+/// last [`PRIOR_LAUNCH_LOOKBACK`] transactions. Stops counting at `enough`.
+pub async fn prior_launches(
+    rpc_client: &RpcClient,
+    launch: &Launch,
+    enough: usize,
+) -> eyre::Result<usize> {
+    let signatures = rpc_client
+        .get_signatures_for_address_with_config(
+            &launch.creator,
+            GetConfirmedSignaturesForAddress2Config {
+                limit: Some(PRIOR_LAUNCH_LOOKBACK),
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    let mut transactions = stream::iter(
+        signatures
+            .into_iter()
+            .filter(|status| status.err.is_none())
+            .filter_map(|status| status.signature.parse::<Signature>().ok()),
+    )
+    .map(|signature| async move {
+        let transaction = rpc_client
+            .get_transaction_with_config(&signature, config)
+            .await?;
+        ParsedTransaction::try_from(transaction)
+    })
+    .buffered(PRIOR_LAUNCH_FETCHES);
+
+    let mut prior = 0;
+    while let Some(transaction) = transactions.next().await {
+        if created_by(&transaction?, &launch.creator)
+            .is_some_and(|mint| mint != launch.mint)
+        {
+            prior += 1;
+            if prior >= enough {
+                break;
+            }
+        }
+    }
+    Ok(prior)
+}
+
+/// The mint of a Pump.fun create by `creator` in `tx`, if there is one.
+fn created_by(tx: &ParsedTransaction, creator: &Pubkey) -> Option<Pubkey> {
+    tx.find_instructions(&PUMP_PROGRAM).find_map(|ix| {
+        CreateIxData::deserialize(&ix.data).ok()?;
+        (ix.accounts.get(CREATE_USER_INDEX) == Some(creator)).then_some(*ix.accounts.first()?)
+    })
+}
+
+fn check_prior_launches(max: u32, prior: usize) -> Result<(), Rejection> {
+    if prior > max as usize {
+        return reject(
+            "max_prior_launches",
+            format!("creator launched {} coins before", prior),
+        );
+    }
+    Ok(())
+}
+
+/// Checks `launch` by a creator with `last_hour` launches in the past hour
+/// counting this one, against every rule but `max_prior_launches`.
+pub fn check(filters: &LaunchFilters, launch: &Launch, last_hour: usize) -> Result<(), Rejection> {
+    let creator = launch.creator.to_string();
+    if !filters.creator_allow.is_empty() && !filters.creator_allow.contains(&creator) {
+        return reject("creator_allow", format!("{} is not allowed", creator));
+    }
+    if filters.creator_deny.contains(&creator) {
+        return reject("creator_deny", format!("{} is denied", creator));
+    }
+
+    for (rule, pattern, value) in [
+        ("name_regex", &filters.name_regex, &launch.name),
+        ("symbol_regex", &filters.symbol_regex, &launch.symbol),
+    ] {
+        if !pattern.is_match(value) {
+            return reject(rule, format!("`{}` does not match `{}`", value, pattern));
+        }
+    }
+    let text = format!("{} {}", launch.name, launch.symbol).to_lowercase();
+    if let Some(keyword) = filters
+        .blocked_keywords
+        .iter()
+        .find(|keyword| !keyword.is_empty() && text.contains(&keyword.to_lowercase()))
+    {
+        return reject("blocked_keywords", format!("contains `{}`", keyword));
+    }
+
+    let domain = uri_domain(&launch.uri);
+    if !filters.uri_allowed_domains.is_empty()
+        && !filters
+            .uri_allowed_domains
+            .iter()
+            .any(|allowed| on_domain(&domain, allowed))
+    {
+        return reject(
+            "uri_allowed_domains",
+            format!("`{}` is not hosted on an allowed domain", launch.uri),
+        );
+    }
+    if let Some(blocked) = filters
+        .uri_blocked_domains
+        .iter()
+        .find(|blocked| on_domain(&domain, blocked))
+    {
+        return reject(
+            "uri_blocked_domains",
+            format!("`{}` is on {}", launch.uri, blocked),
+        );
+    }
+
+    let balance = launch.creator_balance;
+    if filters.min_creator_sol > 0.0 && balance < sol_to_lamports(filters.min_creator_sol) {
+        return reject(
+            "min_creator_sol",
+            format!("creator holds {} SOL", lamports_to_sol(balance)),
+        );
+    }
+    if filters.max_creator_sol > 0.0 && balance > sol_to_lamports(filters.max_creator_sol) {
+        return reject(
+            "max_creator_sol",
+            format!("creator holds {} SOL", lamports_to_sol(balance)),
+        );
+    }
+    let dev_buy = launch.dev_buy.unwrap_or(0);
+    if filters.min_dev_buy > 0.0 && dev_buy < sol_to_lamports(filters.min_dev_buy) {
+        return reject(
+            "min_dev_buy",
+            format!("dev bought {} SOL", lamports_to_sol(dev_buy)),
+        );
+    }
+    if filters.max_dev_buy > 0.0 && dev_buy > sol_to_lamports(filters.max_dev_buy) {
+        return reject(
+            "max_dev_buy",
+            format!("dev bought {} SOL", lamports_to_sol(dev_buy)),
+        );
+    }
+
+    if filters.max_launches_per_hour > 0 && last_hour > filters.max_launches_per_hour as usize {
+        return reject(
+            "max_launches_per_hour",
+            format!("creator launched {} coins in the last hour", last_hour),
+        );
+    }
+    Ok(())
+}
+
+/// Lowercased host of `uri`, without scheme, credentials or port.
+fn uri_domain(uri: &str) -> String {
+    let rest = uri.split_once("://").map_or(uri, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    host.split(':').next().unwrap_or_default().to_lowercase()
+}
+
+fn on_domain(host: &str, domain: &str) -> bool {
+    let domain = domain.trim().trim_start_matches('.').to_lowercase();
+    !domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launch() -> Launch {
+        Launch {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            name: "Good Coin".to_string(),
+            symbol: "GOOD".to_string(),
+            uri: "https://ipfs.io/ipfs/Qm".to_string(),
+            creator_balance: sol_to_lamports(2.0),
+            dev_buy: Some(sol_to_lamports(0.5)),
+        }
+    }
+
+    #[test]
+    fn rejections_name_the_rule() {
+        let launch = launch();
+        let filters = LaunchFilters {
+            name_regex: "(?i)coin".parse().unwrap(),
+            blocked_keywords: vec!["rug".to_string()],
+            uri_allowed_domains: vec!["ipfs.io".to_string()],
+            min_creator_sol: 1.0,
+            min_dev_buy: 0.1,
+            max_dev_buy: 1.0,
+            max_launches_per_hour: 2,
+            ..Default::default()
+        };
+        assert_eq!(check(&filters, &launch, 1), Ok(()));
+
+        let rule = |filters: &LaunchFilters, launch: &Launch, last_hour| {
+            check(filters, launch, last_hour).unwrap_err().rule
+        };
+        let mut rugged = launch.clone();
+        rugged.symbol = "RUGME".to_string();
+        assert_eq!(rule(&filters, &rugged, 1), "blocked_keywords");
+
+        let mut elsewhere = launch.clone();
+        elsewhere.uri = "https://user@ipfs.io.evil.com:443/x".to_string();
+        assert_eq!(rule(&filters, &elsewhere, 1), "uri_allowed_domains");
+
+        let mut no_buy = launch.clone();
+        no_buy.dev_buy = None;
+        assert_eq!(rule(&filters, &no_buy, 1), "min_dev_buy");
+        assert_eq!(rule(&filters, &launch, 3), "max_launches_per_hour");
+
+        assert_eq!(check_prior_launches(1, 1), Ok(()));
+        assert_eq!(
+            check_prior_launches(1, 2).unwrap_err().rule,
+            "max_prior_launches"
+        );
+
+        let mut history = LaunchHistory::default();
+        let start = Instant::now();
+        let other = Pubkey::new_unique();
+        history.record(other, start);
+        history.record(launch.creator, start);
+        assert_eq!(history.record(launch.creator, start + HOUR), 1);
+        assert_eq!(history.record(launch.creator, start + HOUR), 2);
+        assert!(!history.launches.contains_key(&other));
+    }
+}
@@ -0,0 +1,431 @@
+use std::{fs, path::Path, sync::Arc};
+
+use demand::{DemandOption, Select};
+use log::{error, info};
+use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0::Message, VersionedMessage},
+    native_token::lamports_to_sol,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction::transfer,
+    system_program,
+    transaction::VersionedTransaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+
+use crate::{
+    env::load_config,
+    input::{amount_input, gas_input},
+    journal::{Order, TradeSide},
+    liquidity::{
+        option::wallet_gen::{list_folders, list_json_wallets},
+        utils::tip_account,
+    },
+    pumpfun::pump_interface::{
+        accounts::{BondingCurve, Global},
+        builder::{
+            fetch_global, generate_pump_multi_buy_ix, get_bonding_curve, pump_buy_quote,
+            EVENT_AUTH, GLOBAL_STATE, PUMP_PROGRAM,
+        },
+        instructions::{create_ix, CreateIxArgs, CreateKeys},
+    },
+    router::SniperRoute,
+    sender::{SendStrategy, TransactionSender},
+};
+
+/// Name, symbol and metadata uri of the coin to launch.
+pub const METADATA_PATH: &str = "metadata.json";
+
+/// Mint authority of every Pump.fun coin.
+pub const MINT_AUTHORITY: Pubkey = pubkey!("TSLvdd1pWpHVjahSpsvCXUbgwsL3JAcvokwaKt1eokM");
+
+/// Most transactions the block engine takes in one bundle.
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LaunchMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+impl LaunchMetadata {
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| eyre::eyre!("Could not read {}: {}", path.display(), e))?;
+        let metadata: LaunchMetadata = serde_json::from_str(&json)
+            .map_err(|e| eyre::eyre!("Invalid {}: {}", path.display(), e))?;
+        if metadata.name.is_empty() || metadata.symbol.is_empty() || metadata.uri.is_empty() {
+            return Err(eyre::eyre!(
+                "{} needs a name, a symbol and a uri",
+                path.display()
+            ));
+        }
+        Ok(metadata)
+    }
+}
+
+/// Instructions that have to land in the same transaction, and the keypairs
+/// besides the payer that sign them.
+pub struct Step {
+    pub instructions: Vec<Instruction>,
+    pub signers: Vec<Arc<Keypair>>,
+}
+
+/// Creates `mint` with `metadata`, `creator` paying and owning the curve.
+pub fn create_instruction(
+    creator: &Pubkey,
+    mint: &Pubkey,
+    metadata: &LaunchMetadata,
+) -> eyre::Result<Instruction> {
+    let bonding_curve = get_bonding_curve(*mint, &PUMP_PROGRAM);
+    let (metadata_account, _) = mpl_token_metadata::accounts::Metadata::find_pda(mint);
+
+    Ok(create_ix(
+        CreateKeys {
+            mint: *mint,
+            mint_authority: MINT_AUTHORITY,
+            bonding_curve,
+            associated_bonding_curve: get_associated_token_address(&bonding_curve, mint),
+            global: GLOBAL_STATE,
+            mpl_token_metadata: mpl_token_metadata::ID,
+            metadata: metadata_account,
+            user: *creator,
+            system_program: system_program::id(),
+            token_program: spl_token::id(),
+            associated_token_program: spl_associated_token_account::id(),
+            rent: solana_program::sysvar::rent::id(),
+            event_authority: EVENT_AUTH,
+            program: PUMP_PROGRAM,
+        },
+        CreateIxArgs {
+            name: metadata.name.clone(),
+            symbol: metadata.symbol.clone(),
+            uri: metadata.uri.clone(),
+        },
+    )?)
+}
+
+/// The curve every coin starts from.
+pub fn fresh_curve(global: &Global) -> BondingCurve {
+    BondingCurve {
+        virtual_token_reserves: global.initial_virtual_token_reserves,
+        virtual_sol_reserves: global.initial_virtual_sol_reserves,
+        real_token_reserves: global.initial_real_token_reserves,
+        real_sol_reserves: 0,
+        token_total_supply: global.token_total_supply,
+        complete: false,
+    }
+}
+
+/// One step per buyer, in order, each quoted on `curve` as the buys before it
+/// leave it, with the fee `global` sets.
+pub async fn chained_buys(
+    mint: Pubkey,
+    buyers: &[(Arc<Keypair>, u64)],
+    mut curve: BondingCurve,
+    global: &Global,
+    slippage_bps: u16,
+) -> eyre::Result<Vec<Step>> {
+    let mut steps = vec![];
+    for (buyer, sol_amount) in buyers {
+        let mut instructions = vec![create_associated_token_account_idempotent(
+            &buyer.pubkey(),
+            &buyer.pubkey(),
+            &mint,
+            &spl_token::id(),
+        )];
+        instructions.extend(
+            generate_pump_multi_buy_ix(
+                mint,
+                *sol_amount,
+                buyer.clone(),
+                &curve,
+                global,
+                slippage_bps,
+            )
+            .await?,
+        );
+        curve = pump_buy_quote(&curve, global, *sol_amount)?.curve;
+        steps.push(Step {
+            instructions,
+            signers: vec![buyer.clone()],
+        });
+    }
+    Ok(steps)
+}
+
+/// Fits `steps`, in order, into as few transactions paid by `payer` as the
+/// packet size allows. A step is never split across transactions.
+pub fn pack(
+    payer: &Keypair,
+    steps: Vec<Step>,
+    blockhash: Hash,
+) -> eyre::Result<Vec<VersionedTransaction>> {
+    let mut transactions = vec![];
+    let mut current: Option<(Step, VersionedTransaction)> = None;
+
+    for step in steps {
+        if let Some((packed, transaction)) = current.take() {
+            let mut instructions = packed.instructions.clone();
+            instructions.extend(step.instructions.iter().cloned());
+            let mut signers = packed.signers.clone();
+            signers.extend(step.signers.iter().cloned());
+
+            if let Some(merged) = compile(payer, &instructions, &signers, blockhash)? {
+                current = Some((
+                    Step {
+                        instructions,
+                        signers,
+                    },
+                    merged,
+                ));
+                continue;
+            }
+            transactions.push(transaction);
+        }
+        let transaction = compile(payer, &step.instructions, &step.signers, blockhash)?
+            .ok_or_else(|| eyre::eyre!("A single step does not fit in one transaction"))?;
+        current = Some((step, transaction));
+    }
+    transactions.extend(current.map(|(_, transaction)| transaction));
+    Ok(transactions)
+}
+
+/// Signed transaction of `instructions`, or `None` when it is over the packet
+/// size.
+fn compile(
+    payer: &Keypair,
+    instructions: &[Instruction],
+    signers: &[Arc<Keypair>],
+    blockhash: Hash,
+) -> eyre::Result<Option<VersionedTransaction>> {
+    let message = Message::try_compile(&payer.pubkey(), instructions, &[], blockhash)?;
+    let mut keypairs = vec![payer];
+    for signer in signers {
+        if keypairs.iter().all(|k| k.pubkey() != signer.pubkey()) {
+            keypairs.push(signer);
+        }
+    }
+    let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &keypairs)?;
+    let size = bincode::serialize(&transaction)?.len();
+    Ok((size <= PACKET_DATA_SIZE).then_some(transaction))
+}
+
+/// Creates a coin from `metadata.json` and buys it from the payer and every
+/// wallet of a folder, all in one bundle.
+pub async fn pump_launch() -> eyre::Result<()> {
+    let settings = match load_config().await {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Error: {}", e);
+            return Ok(());
+        }
+    };
+    let metadata = LaunchMetadata::load(Path::new(METADATA_PATH))?;
+    info!(
+        "Launching {} ({}) with {}",
+        metadata.name, metadata.symbol, metadata.uri
+    );
+
+    let mint = Arc::new(mint_keypair().await?);
+    let (folder, wallets) = list_folders().await.map_err(|e| eyre::eyre!("{}", e))?;
+    let dev_buy = amount_input("Dev Buy Sol: ").await;
+    let wallet_buy = amount_input("Sol per Wallet: ").await;
+    let bundle_tip = gas_input("Bundle Priority Tip: ").await;
+
+    let rpc_client = Arc::new(RpcClient::new(settings.network.rpc_url.clone()));
+    let payer = Arc::new(Keypair::from_base58_string(&settings.engine.payer_keypair));
+
+    let global = fetch_global(&rpc_client).await?;
+
+    let mut buyers = vec![(payer.clone(), dev_buy)];
+    buyers.extend(
+        wallets
+            .into_iter()
+            .map(|wallet| (Arc::new(wallet), wallet_buy)),
+    );
+    let buys = chained_buys(
+        mint.pubkey(),
+        &buyers,
+        fresh_curve(&global),
+        &global,
+        settings.slippage_bps(&SniperRoute::PumpFun),
+    )
+    .await?;
+
+    let mut steps = vec![Step {
+        instructions: vec![create_instruction(
+            &payer.pubkey(),
+            &mint.pubkey(),
+            &metadata,
+        )?],
+        signers: vec![mint.clone()],
+    }];
+    steps.extend(buys);
+    steps.push(Step {
+        instructions: vec![transfer(&payer.pubkey(), &tip_account(), bundle_tip)],
+        signers: vec![],
+    });
+
+    let (blockhash, _) = rpc_client
+        .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+        .await?;
+    let transactions = pack(&payer, steps, blockhash)?;
+    if transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+        return Err(eyre::eyre!(
+            "{} buyers from {} need {} transactions, a bundle takes {}",
+            buyers.len(),
+            folder,
+            transactions.len(),
+            MAX_BUNDLE_TRANSACTIONS
+        ));
+    }
+    info!(
+        "Mint {}: {} buyer(s), {} SOL in total, {} transaction(s)",
+        mint.pubkey(),
+        buyers.len(),
+        lamports_to_sol(buyers.iter().map(|(_, sol)| sol).sum()),
+        transactions.len()
+    );
+
+    // The tip already rides in the last transaction
+    let sender = TransactionSender::from_config(&settings, rpc_client)
+        .with_strategy(SendStrategy::Bundle { tip_lamports: 0 })
+        .with_order(Order::new(
+            SniperRoute::PumpFun,
+            TradeSide::Buy,
+            mint.pubkey(),
+            dev_buy,
+        ));
+    let report = sender.send_bundle(transactions).await?;
+    report.log();
+
+    Ok(())
+}
+
+/// A fresh mint, or one read from a keypair file, e.g. a vanity address.
+async fn mint_keypair() -> eyre::Result<Keypair> {
+    let selected = Select::new("Mint")
+        .description("Select the Mint Keypair")
+        .option(DemandOption::new("New").label("▪ Generate a New Mint"))
+        .option(DemandOption::new("File").label("▪ Mint Keypair from JSON"))
+        .run()?;
+
+    if selected == "New" {
+        return Ok(Keypair::new());
+    }
+    list_json_wallets()
+        .await
+        .map_err(|e| eyre::eyre!("{}", e))?
+        .pop()
+        .ok_or_else(|| eyre::eyre!("No mint keypair in the selected file"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pumpfun::pump_interface::{
+            builder::pump_buy_args,
+            instructions::{BuyIxArgs, BuyIxData},
+        },
+        testing::pump_global,
+    };
+
+    #[tokio::test]
+    async fn buys_are_chained_and_packed_in_order() {
+        let payer = Arc::new(Keypair::new());
+        let mint = Arc::new(Keypair::new());
+        let fresh = BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        };
+        let buyers: Vec<(Arc<Keypair>, u64)> = std::iter::once(payer.clone())
+            .chain((0..6).map(|_| Arc::new(Keypair::new())))
+            .map(|buyer| (buyer, 1_000_000_000))
+            .collect();
+
+        let global = pump_global();
+        let buys = chained_buys(mint.pubkey(), &buyers, fresh, &global, 500)
+            .await
+            .unwrap();
+        let amounts: Vec<u64> = buys
+            .iter()
+            .map(|step| {
+                BuyIxData::deserialize(&step.instructions[1].data)
+                    .unwrap()
+                    .0
+                    .amount
+            })
+            .collect();
+        assert_eq!(amounts[0], 34_612_903_225_806);
+        // The second buy is quoted on the curve the first one left
+        let after_first = BondingCurve {
+            virtual_sol_reserves: 31_000_000_000,
+            virtual_token_reserves: 1_073_000_000_000_000 - 34_612_903_225_806,
+            real_token_reserves: 793_100_000_000_000 - 34_612_903_225_806,
+            real_sol_reserves: 1_000_000_000,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        };
+        let BuyIxArgs { amount, .. } = pump_buy_args(&after_first, &global, 1_000_000_000, 500).unwrap();
+        assert_eq!(amounts[1], amount);
+        assert!(amounts.windows(2).all(|pair| pair[0] > pair[1]));
+
+        let metadata = LaunchMetadata {
+            name: "Good Coin".to_string(),
+            symbol: "GOOD".to_string(),
+            uri: "https://ipfs.io/ipfs/Qm".to_string(),
+        };
+        let mut steps = vec![Step {
+            instructions: vec![
+                create_instruction(&payer.pubkey(), &mint.pubkey(), &metadata).unwrap(),
+            ],
+            signers: vec![mint.clone()],
+        }];
+        steps.extend(buys);
+        let transactions = pack(&payer, steps, Hash::default()).unwrap();
+
+        assert!(transactions.len() > 1 && transactions.len() <= MAX_BUNDLE_TRANSACTIONS);
+        // Create comes first, then every buyer in turn
+        let programs: Vec<(Pubkey, Option<Pubkey>)> = transactions
+            .iter()
+            .flat_map(|tx| {
+                let keys = tx.message.static_account_keys();
+                tx.message
+                    .instructions()
+                    .iter()
+                    .filter(|ix| keys[ix.program_id_index as usize] == PUMP_PROGRAM)
+                    .map(|ix| {
+                        let user = BuyIxData::deserialize(&ix.data)
+                            .ok()
+                            .map(|_| keys[ix.accounts[6] as usize]);
+                        (keys[ix.accounts[0] as usize], user)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(programs[0], (mint.pubkey(), None));
+        let users: Vec<Pubkey> = programs[1..].iter().filter_map(|(_, user)| *user).collect();
+        let expected: Vec<Pubkey> = buyers.iter().map(|(buyer, _)| buyer.pubkey()).collect();
+        assert_eq!(users, expected);
+        assert!(transactions
+            .iter()
+            .all(|tx| bincode::serialize(tx).unwrap().len() <= PACKET_DATA_SIZE));
+    }
+}
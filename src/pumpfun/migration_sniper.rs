@@ -1,5 +1,6 @@
 use {
     crate::{
+        geyser::ParsedTransaction,
         instruction::instruction::{
             AmmInstruction, InitializePoolAccounts, INITIALIZE_POOL_ACCOUNTS_LEN,
         },
@@ -11,17 +12,12 @@ use {
         utils::transaction_history::add_transaction_to_history,
     },
     chrono::{LocalResult, TimeZone, Utc},
-    futures::{channel::mpsc::SendError, Sink},
     log::{info, warn},
     solana_client::nonblocking::rpc_client::RpcClient,
     solana_program::pubkey::Pubkey,
     solana_sdk::{program_pack::Pack, pubkey, system_program},
     spl_token::state::Mint,
     std::{str::FromStr, sync::Arc},
-    yellowstone_grpc_proto::{
-        geyser::SubscribeUpdateTransaction, prelude::SubscribeRequest,
-        solana::storage::confirmed_block::CompiledInstruction,
-    },
 };
 
 pub const PUMPFUN_MIGRATION_SIGNER: Pubkey =
@@ -29,43 +25,18 @@ pub const PUMPFUN_MIGRATION_SIGNER: Pubkey =
 
 pub async fn pumpfun_migration_snipe_parser(
     rpc_client: Arc<RpcClient>,
-    tx: SubscribeUpdateTransaction,
+    tx: &ParsedTransaction,
     manual_snipe: bool,
     base_mint: Option<Pubkey>,
     route: SniperRoute,
-    subscribe_tx: tokio::sync::MutexGuard<
-        '_,
-        impl Sink<SubscribeRequest, Error = SendError> + std::marker::Unpin,
-    >,
+    amount_in: u64,
 ) -> eyre::Result<()> {
-    let info = tx.clone().transaction.unwrap_or_default();
-    let accounts = info
-        .transaction
-        .clone()
-        .unwrap_or_default()
-        .message
-        .unwrap_or_default()
-        .account_keys
-        .iter()
-        .map(|i| {
-            let mut array = [0; 32];
-            let bytes = &i[..array.len()]; // panics if not enough data
-            array.copy_from_slice(bytes);
-            Pubkey::new_from_array(array)
-        })
-        .collect::<Vec<Pubkey>>();
-    let outer_instructions = {
-        let transaction = info.transaction.unwrap_or_default();
-        let message = transaction.message.unwrap_or_default();
-        let instructions = message.instructions.iter();
-        instructions.cloned().collect::<Vec<_>>()
-    };
+    let accounts = tx.account_keys();
+    let outer_instructions = &tx.instructions;
 
-    add_transaction_to_history(tx);
+    add_transaction_to_history(tx.clone());
 
-    let meta = info.meta.unwrap_or_default();
-
-    let log_messages = meta.clone().log_messages;
+    let log_messages = &tx.logs;
     let open_time_match = log_messages.iter().find(|m| m.contains("open_time"));
 
     let open_time_split = open_time_match
@@ -109,19 +80,6 @@ pub async fn pumpfun_migration_snipe_parser(
         }
     };
 
-    let inner_instructions: Vec<CompiledInstruction> = meta
-        .clone()
-        .inner_instructions
-        .iter()
-        .flat_map(|inner| {
-            inner.instructions.iter().map(|instr| CompiledInstruction {
-                program_id_index: instr.program_id_index,
-                accounts: instr.accounts.clone(),
-                data: instr.data.clone(),
-            })
-        })
-        .collect();
-
     let mut coin_args_amm: Option<crate::instruction::instruction::InitializeInstruction2> = None;
     let mut raydium_accounts: Option<InitializePoolAccounts> = None;
     let mut trade_route: Option<SniperRoute> = None;
@@ -138,25 +96,25 @@ pub async fn pumpfun_migration_snipe_parser(
                         spl_associated_token_account: spl_associated_token_account::id(),
                         system_program: system_program::id(),
                         rent: Pubkey::from_str("SysvarRent111111111111111111111111111111111")?,
-                        amm_pool: accounts[instructions.accounts[4] as usize],
-                        amm_authority: accounts[instructions.accounts[5] as usize],
-                        amm_open_orders: accounts[instructions.accounts[6] as usize],
-                        amm_lp_mint: accounts[instructions.accounts[7] as usize],
-                        amm_coin_mint: accounts[instructions.accounts[8] as usize],
-                        amm_pc_mint: accounts[instructions.accounts[9] as usize],
-                        amm_coin_vault: accounts[instructions.accounts[10] as usize],
-                        amm_pc_vault: accounts[instructions.accounts[11] as usize],
-                        amm_target_orders: accounts[instructions.accounts[12] as usize],
-                        amm_config: accounts[instructions.accounts[13] as usize],
-                        create_fee_destination: accounts[instructions.accounts[14] as usize],
+                        amm_pool: instructions.accounts[4],
+                        amm_authority: instructions.accounts[5],
+                        amm_open_orders: instructions.accounts[6],
+                        amm_lp_mint: instructions.accounts[7],
+                        amm_coin_mint: instructions.accounts[8],
+                        amm_pc_mint: instructions.accounts[9],
+                        amm_coin_vault: instructions.accounts[10],
+                        amm_pc_vault: instructions.accounts[11],
+                        amm_target_orders: instructions.accounts[12],
+                        amm_config: instructions.accounts[13],
+                        create_fee_destination: instructions.accounts[14],
                         market_program: Pubkey::from_str(
                             "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX",
                         )?,
-                        market: accounts[instructions.accounts[16] as usize],
-                        user_wallet: accounts[instructions.accounts[17] as usize],
-                        user_token_coin: accounts[instructions.accounts[18] as usize],
-                        user_token_pc: accounts[instructions.accounts[19] as usize],
-                        user_token_lp: accounts[instructions.accounts[20] as usize],
+                        market: instructions.accounts[16],
+                        user_wallet: instructions.accounts[17],
+                        user_token_coin: instructions.accounts[18],
+                        user_token_pc: instructions.accounts[19],
+                        user_token_lp: instructions.accounts[20],
                         // Additional fields from the previous RaydiumAmmAccounts
                     });
                 }
@@ -209,7 +167,7 @@ pub async fn pumpfun_migration_snipe_parser(
         return Ok(());
     }
 
-    let _ = sniper_txn_in_2(accounts.clone(), open_time, datetime, route).await;
+    let _ = sniper_txn_in_2(accounts.clone(), open_time, datetime, route, amount_in).await;
 
     Ok(())
 }
@@ -1,4 +1,7 @@
+pub mod events;
 pub mod executor;
+pub mod filters;
+pub mod launch;
 pub mod migration_sniper;
 pub mod pump_interface;
 pub mod sniper;
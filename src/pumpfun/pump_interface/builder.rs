@@ -1,78 +1,77 @@
-use std::{
-    ops::{Add, Mul, Sub},
-    sync::Arc,
-};
+use std::sync::Arc;
 
-use borsh::BorshDeserialize;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_program::pubkey;
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction, instruction::Instruction,
-    native_token::sol_to_lamports, pubkey::Pubkey, signature::Keypair, signer::Signer,
-    system_program,
+    instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer, system_program,
 };
 use spl_associated_token_account::get_associated_token_address;
 
 use crate::{
-    app::config_init::get_config,
     pumpfun::pump_interface::{
-        accounts::BondingCurve,
+        accounts::{BondingCurve, BondingCurveAccount, Global, GlobalAccount},
         instructions::{buy_ix_with_program_id, BuyIxArgs, BuyKeys},
     },
+    utils::slippage::{max_in, min_out, MAX_BPS},
 };
 
-use super::instructions::{sell_ix_with_program_id, SellIxArgs, SellKeys};
+use super::{
+    instructions::{sell_ix_with_program_id, SellIxArgs, SellKeys},
+    quote::{BondingCurveQuote, Quote},
+};
 
 pub const GLOBAL_STATE: Pubkey = pubkey!("4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf");
 pub const FEE_RECEPIENT: Pubkey = pubkey!("CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM");
 pub const EVENT_AUTH: Pubkey = pubkey!("Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1");
 pub const PUMP_PROGRAM: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
 
+/// Every Pump.fun mint has 6 decimals.
+pub const PUMP_TOKEN_DECIMALS: u8 = 6;
+
 #[derive(PartialEq)]
 pub enum PumpFunDirection {
     Buy,
     Sell,
 }
 
+/// How big a Pump.fun trade is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PumpAmount {
+    /// Lamports to buy with, the trade fee on top.
+    Sol(u64),
+    /// Raw token amount to buy or sell.
+    Tokens(u64),
+    /// Share of the token balance to sell, in percent.
+    Percentage(f64),
+}
+
+/// Buys `amount` of `token`, either what a SOL amount gets or an exact number
+/// of tokens. Returns the quote the slippage bound was set from.
 pub async fn generate_pump_buy_ix(
     rpc_client: Arc<RpcClient>,
     token: Pubkey,
-    sol_amount: u64,
+    amount: PumpAmount,
     main_signer: Arc<Keypair>,
-) -> eyre::Result<Vec<Instruction>> {
-    let config = get_config().await?;
-    let priority_fee = sol_to_lamports(config.trading.priority_fee);
-
+    slippage_bps: u16,
+) -> eyre::Result<(Vec<Instruction>, Quote)> {
     let bonding_curve_pda = get_bonding_curve(token, &PUMP_PROGRAM);
     let bonding_curve_ata = get_associated_token_address(&bonding_curve_pda, &token);
     let signer_ata = get_associated_token_address(&main_signer.pubkey(), &token);
 
-    let account_data = rpc_client.get_account_data(&bonding_curve_pda).await?;
-
-    let sliced_data: &mut &[u8] = &mut account_data.as_slice();
-
-    let reserves = BondingCurve::deserialize_reader(sliced_data)?;
-
-    let reserves = (
-        reserves.real_token_reserves as u128,
-        reserves.virtual_sol_reserves as u128,
-        reserves.real_sol_reserves as u128,
-    );
-
-    let price: (u128, (u128, u128, u128)) = calculate_buy_price(sol_amount as u128, reserves);
-
-    println!("Price: {:?}", price);
-    let amount = (price.0) as u64;
-    let fee_bps = sol_amount * 1 / 100;
+    let (bonding_curve, global) = fetch_open_curve(&rpc_client, &token).await?;
+    let quote = match amount {
+        PumpAmount::Sol(sol_amount) => pump_buy_quote(&bonding_curve, &global, sol_amount)?,
+        PumpAmount::Tokens(token_amount) => {
+            BondingCurveQuote::with_global(&bonding_curve, &global).buy_exact_tokens(token_amount)?
+        }
+        PumpAmount::Percentage(_) => {
+            return Err(eyre::eyre!("Pump.fun buys are sized in SOL or tokens"))
+        }
+    };
+    let args = buy_args(&quote, slippage_bps);
 
     let mut swap_instructions = Vec::new();
 
-    let unit_limit = ComputeBudgetInstruction::set_compute_unit_limit(80000);
-    let compute_price = ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
-
-    swap_instructions.push(unit_limit);
-    swap_instructions.push(compute_price);
-
     swap_instructions.push(buy_ix_with_program_id(
         PUMP_PROGRAM,
         BuyKeys {
@@ -89,32 +88,26 @@ pub async fn generate_pump_buy_ix(
             event_authority: EVENT_AUTH,
             program: PUMP_PROGRAM,
         },
-        BuyIxArgs {
-            amount,
-            max_sol_cost: sol_amount + fee_bps,
-        },
+        args,
     )?);
 
-    Ok(swap_instructions)
+    Ok((swap_instructions, quote))
 }
 
+/// Buys on `bonding_curve` as it will be when the instruction runs, rather than
+/// as it is now, e.g. after earlier buys in the same bundle.
 pub async fn generate_pump_multi_buy_ix(
-    rpc_client: Arc<RpcClient>,
     token: Pubkey,
     sol_amount: u64,
     main_signer: Arc<Keypair>,
-    reserves: (u128, u128, u128),
+    bonding_curve: &BondingCurve,
+    global: &Global,
+    slippage_bps: u16,
 ) -> eyre::Result<Vec<Instruction>> {
     let bonding_curve_pda = get_bonding_curve(token, &PUMP_PROGRAM);
     let bonding_curve_ata = get_associated_token_address(&bonding_curve_pda, &token);
     let signer_ata = get_associated_token_address(&main_signer.pubkey(), &token);
 
-    let price: (u128, (u128, u128, u128)) = calculate_buy_price(sol_amount as u128, reserves);
-
-    println!("Price: {:?}", price);
-    let amount = (price.0) as u64;
-    let fee_bps = sol_amount * 1 / 100;
-
     let buy_ix = buy_ix_with_program_id(
         PUMP_PROGRAM,
         BuyKeys {
@@ -131,24 +124,40 @@ pub async fn generate_pump_multi_buy_ix(
             event_authority: EVENT_AUTH,
             program: PUMP_PROGRAM,
         },
-        BuyIxArgs {
-            amount,
-            max_sol_cost: sol_amount + fee_bps,
-        },
+        pump_buy_args(bonding_curve, global, sol_amount, slippage_bps)?,
     )?;
 
     Ok([buy_ix].to_vec())
 }
 
+/// Sells `amount` of `token`, an exact number of tokens or a share of the
+/// signer's balance, for at least the live quote less `slippage_bps`. Returns
+/// the quote the bound was set from.
 pub async fn generate_pump_sell_ix(
+    rpc_client: &RpcClient,
     token: Pubkey,
-    token_amount: u64,
+    amount: PumpAmount,
     main_signer: Arc<Keypair>,
-) -> eyre::Result<Vec<Instruction>> {
+    slippage_bps: u16,
+) -> eyre::Result<(Vec<Instruction>, Quote)> {
     let bonding_curve_pda = get_bonding_curve(token, &PUMP_PROGRAM);
     let bonding_curve_ata = get_associated_token_address(&bonding_curve_pda, &token);
     let signer_ata = get_associated_token_address(&main_signer.pubkey(), &token);
 
+    let (bonding_curve, global) = fetch_open_curve(rpc_client, &token).await?;
+    let token_amount = match amount {
+        PumpAmount::Tokens(token_amount) => token_amount,
+        PumpAmount::Percentage(percentage) => {
+            let balance = rpc_client.get_token_account_balance(&signer_ata).await?;
+            share_of(balance.amount.parse::<u64>()?, percentage)?
+        }
+        PumpAmount::Sol(_) => return Err(eyre::eyre!("Pump.fun sells are sized in tokens")),
+    };
+    if token_amount == 0 {
+        return Err(eyre::eyre!("Nothing to sell"));
+    }
+    let quote = BondingCurveQuote::with_global(&bonding_curve, &global).sell_exact_tokens(token_amount)?;
+
     let sell_ix = sell_ix_with_program_id(
         PUMP_PROGRAM,
         SellKeys {
@@ -165,77 +174,186 @@ pub async fn generate_pump_sell_ix(
             event_authority: EVENT_AUTH,
             program: PUMP_PROGRAM,
         },
-        SellIxArgs {
-            amount: 18446744073709551615,
-            min_sol_output: 0,
-        },
+        sell_args(&quote, slippage_bps),
     )?;
 
-    Ok([sell_ix].to_vec())
+    Ok(([sell_ix].to_vec(), quote))
+}
+
+/// Reads the bonding curve of `mint` as it is now.
+pub async fn fetch_bonding_curve(rpc_client: &RpcClient, mint: &Pubkey) -> eyre::Result<BondingCurve> {
+    let bonding_curve_pda = get_bonding_curve(*mint, &PUMP_PROGRAM);
+    let account_data = rpc_client.get_account_data(&bonding_curve_pda).await?;
+    Ok(BondingCurveAccount::deserialize(&account_data)?.0)
+}
+
+/// Reads the global account, which sets the trade fee.
+pub async fn fetch_global(rpc_client: &RpcClient) -> eyre::Result<Global> {
+    let account_data = rpc_client.get_account_data(&GLOBAL_STATE).await?;
+    Ok(GlobalAccount::deserialize(&account_data)?.0)
+}
+
+/// Reads the bonding curve of `mint` and the global account in one request.
+pub async fn fetch_curve_and_global(
+    rpc_client: &RpcClient,
+    mint: &Pubkey,
+) -> eyre::Result<(BondingCurve, Global)> {
+    let bonding_curve_pda = get_bonding_curve(*mint, &PUMP_PROGRAM);
+    let mut accounts = rpc_client
+        .get_multiple_accounts(&[bonding_curve_pda, GLOBAL_STATE])
+        .await?
+        .into_iter();
+    let curve = accounts
+        .next()
+        .flatten()
+        .ok_or_else(|| eyre::eyre!("No bonding curve for {}", mint))?;
+    let global = accounts
+        .next()
+        .flatten()
+        .ok_or_else(|| eyre::eyre!("No Pump.fun global account"))?;
+    Ok((
+        BondingCurveAccount::deserialize(&curve.data)?.0,
+        GlobalAccount::deserialize(&global.data)?.0,
+    ))
+}
+
+/// Reads the bonding curve of `mint` to trade on, and the global account it is
+/// priced with. Once the curve is complete the program refuses trades, and the
+/// coin is on Raydium instead.
+async fn fetch_open_curve(
+    rpc_client: &RpcClient,
+    mint: &Pubkey,
+) -> eyre::Result<(BondingCurve, Global)> {
+    let (bonding_curve, global) = fetch_curve_and_global(rpc_client, mint).await?;
+    if bonding_curve.complete {
+        return Err(eyre::eyre!(
+            "The bonding curve of {} is complete, trade it on the Raydium AMM route instead",
+            mint
+        ));
+    }
+    Ok((bonding_curve, global))
+}
+
+/// Buys what `sol_amount` gets on `bonding_curve`, paying at most `slippage_bps`
+/// more than that plus the trade fee `global` sets.
+pub fn pump_buy_args(
+    bonding_curve: &BondingCurve,
+    global: &Global,
+    sol_amount: u64,
+    slippage_bps: u16,
+) -> eyre::Result<BuyIxArgs> {
+    Ok(buy_args(
+        &pump_buy_quote(bonding_curve, global, sol_amount)?,
+        slippage_bps,
+    ))
+}
+
+/// The buy of [`pump_buy_args`], with `sol_amount` going into the curve and
+/// the fee on top.
+pub fn pump_buy_quote(
+    bonding_curve: &BondingCurve,
+    global: &Global,
+    sol_amount: u64,
+) -> eyre::Result<Quote> {
+    BondingCurveQuote::with_global(bonding_curve, global)
+        .buy_exact_sol(with_fee(sol_amount, global.fee_basis_points))
+}
+
+/// Sells `token_amount`, taking at least `slippage_bps` less than the quote net
+/// of the trade fee `global` sets.
+pub fn pump_sell_args(
+    bonding_curve: &BondingCurve,
+    global: &Global,
+    token_amount: u64,
+    slippage_bps: u16,
+) -> eyre::Result<SellIxArgs> {
+    let quote = BondingCurveQuote::with_global(bonding_curve, global).sell_exact_tokens(token_amount)?;
+    Ok(sell_args(&quote, slippage_bps))
+}
+
+fn buy_args(quote: &Quote, slippage_bps: u16) -> BuyIxArgs {
+    BuyIxArgs {
+        amount: quote.token_amount,
+        max_sol_cost: max_in(quote.sol_amount, slippage_bps),
+    }
+}
+
+fn sell_args(quote: &Quote, slippage_bps: u16) -> SellIxArgs {
+    SellIxArgs {
+        amount: quote.token_amount,
+        min_sol_output: min_out(quote.sol_amount, slippage_bps),
+    }
+}
+
+/// `percentage` of `balance`, rounded to a basis point.
+pub fn share_of(balance: u64, percentage: f64) -> eyre::Result<u64> {
+    if !(percentage > 0.0 && percentage <= 100.0) {
+        return Err(eyre::eyre!("Sell percentage must be in (0, 100], got {}", percentage));
+    }
+    let bps = (percentage * 100.0).round() as u128;
+    Ok((balance as u128 * bps / MAX_BPS as u128) as u64)
+}
+
+fn with_fee(sol_amount: u64, fee_bps: u64) -> u64 {
+    sol_amount + (sol_amount as u128 * fee_bps.min(MAX_BPS) as u128 / MAX_BPS as u128) as u64
 }
 
 pub fn get_bonding_curve(mint: Pubkey, program_id: &Pubkey) -> Pubkey {
     Pubkey::find_program_address(&[b"bonding-curve", &mint.to_bytes()], program_id).0
 }
 
-pub fn calculate_buy_price(
-    sol_amount: u128,
-    bonding_curve: (u128, u128, u128),
-) -> (u128, (u128, u128, u128)) {
-    if sol_amount == 0 {
-        return (0, bonding_curve);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::pump_global;
+
+    fn curve() -> BondingCurve {
+        BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 10_000_000_000,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        }
     }
 
-    let (virtual_sol_reserves, virtual_token_reserves, real_token_reserves) = bonding_curve;
-
-    // Calculate the product of virtual reserves
-    let product = virtual_sol_reserves.mul(virtual_token_reserves);
-    // Update the virtual SOL reserves with the new amount of SOL added
-    let new_sol_reserves = virtual_sol_reserves.add(sol_amount);
-    // Calculate the new token amount based on the new virtual SOL reserves
-    let new_token_amount = product.checked_div(new_sol_reserves).unwrap() + 1;
-    // Determine the number of tokens to be given out
-    let token_amount = virtual_token_reserves - new_token_amount;
-    // Ensure the token amount does not exceed the real token reserves
-    let token_amount = std::cmp::min(token_amount, real_token_reserves);
-
-    // Update the real token reserves by subtracting the token amount to be given out
-    let new_reserves = (
-        new_sol_reserves,                   // Updated virtual SOL reserves
-        virtual_token_reserves,             // Virtual token reserves remain the same
-        real_token_reserves - token_amount, // Updated real token reserves
-    );
-
-    // Return the token amount to be given out and the new reserves
-    (token_amount, new_reserves)
-}
-
-pub fn calculate_sell_price(
-    token_amount: u128,
-    bonding_curve: (u128, u128, u128),
-) -> (u128, (u128, u128, u128)) {
-    if token_amount == 0 {
-        return (0, bonding_curve);
+    #[test]
+    fn slippage_bounds_follow_the_curve() {
+        let global = pump_global();
+        let buy = pump_buy_args(&curve(), &global, 1_000_000_000, 500).unwrap();
+        assert_eq!(buy.amount, 34_612_903_225_806);
+        // 1% fee, then 5% slippage on top
+        assert_eq!(buy.max_sol_cost, 1_060_500_000);
+
+        let sell = pump_sell_args(&curve(), &global, 34_612_903_225_806, 500).unwrap();
+        assert_eq!(sell.amount, 34_612_903_225_806);
+        // 937_499_999 quoted, 928_125_000 after the fee
+        assert_eq!(sell.min_sol_output, 881_718_750);
+
+        assert_eq!(
+            pump_sell_args(&curve(), &global, 34_612_903_225_806, 10_000)
+                .unwrap()
+                .min_sol_output,
+            0
+        );
     }
 
-    let (virtual_sol_reserves, virtual_token_reserves, real_sol_reserves) = bonding_curve;
-
-    // Calculate the product of virtual reserves
-    let product = virtual_sol_reserves.mul(virtual_token_reserves);
-    // Update the virtual token reserves with the new amount of tokens removed
-    let new_token_reserves = virtual_token_reserves.sub(token_amount);
-    // Calculate the new SOL amount based on the new virtual token reserves
-    let new_sol_amount = product.checked_div(new_token_reserves).unwrap();
-    // Determine the amount of SOL to be given out
-    let sol_amount = new_sol_amount - virtual_sol_reserves;
-
-    // Update the reserves
-    let new_reserves = (
-        virtual_sol_reserves,           // Virtual SOL reserves remain the same
-        new_token_reserves,             // Updated virtual token reserves
-        real_sol_reserves - sol_amount, // Updated real SOL reserves
-    );
-
-    // Return the SOL amount to be given out and the new reserves
-    (sol_amount, new_reserves)
+    #[test]
+    fn sells_size_by_share_of_the_balance() {
+        assert_eq!(share_of(34_612_903_225_806, 100.0).unwrap(), 34_612_903_225_806);
+        assert_eq!(share_of(1_000_000, 33.333).unwrap(), 333_300);
+        assert!(share_of(1_000_000, 0.0).is_err());
+        assert!(share_of(1_000_000, 150.0).is_err());
+
+        // Buying the same tokens exactly is bounded like buying them with SOL
+        let global = pump_global();
+        let quote = BondingCurveQuote::with_global(&curve(), &global)
+            .buy_exact_tokens(34_612_903_225_806)
+            .unwrap();
+        assert_eq!(
+            buy_args(&quote, 500),
+            pump_buy_args(&curve(), &global, 1_000_000_000, 500).unwrap()
+        );
+    }
 }
@@ -3,12 +3,12 @@ use solana_program::pubkey::Pubkey;
 pub const CREATE_EVENT_EVENT_DISCM: [u8; 8] = [27, 114, 169, 77, 222, 235, 99, 118];
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
 pub struct CreateEvent {
-    name: String,
-    symbol: String,
-    uri: String,
-    mint: Pubkey,
-    bonding_curve: Pubkey,
-    user: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub user: Pubkey,
 }
 #[derive(Clone, Debug, PartialEq)]
 pub struct CreateEventEvent(pub CreateEvent);
@@ -38,14 +38,14 @@ impl CreateEventEvent {
 pub const TRADE_EVENT_EVENT_DISCM: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
 pub struct TradeEvent {
-    mint: Pubkey,
-    sol_amount: u64,
-    token_amount: u64,
-    is_buy: bool,
-    user: Pubkey,
-    timestamp: i64,
-    virtual_sol_reserves: u64,
-    virtual_token_reserves: u64,
+    pub mint: Pubkey,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub user: Pubkey,
+    pub timestamp: i64,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
 }
 #[derive(Clone, Debug, PartialEq)]
 pub struct TradeEventEvent(pub TradeEvent);
@@ -75,10 +75,10 @@ impl TradeEventEvent {
 pub const COMPLETE_EVENT_EVENT_DISCM: [u8; 8] = [95, 114, 97, 156, 212, 46, 152, 8];
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
 pub struct CompleteEvent {
-    user: Pubkey,
-    mint: Pubkey,
-    bonding_curve: Pubkey,
-    timestamp: i64,
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub timestamp: i64,
 }
 #[derive(Clone, Debug, PartialEq)]
 pub struct CompleteEventEvent(pub CompleteEvent);
@@ -108,12 +108,12 @@ impl CompleteEventEvent {
 pub const SET_PARAMS_EVENT_EVENT_DISCM: [u8; 8] = [223, 195, 159, 246, 62, 48, 143, 131];
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
 pub struct SetParamsEvent {
-    fee_recipient: Pubkey,
-    initial_virtual_token_reserves: u64,
-    initial_virtual_sol_reserves: u64,
-    initial_real_token_reserves: u64,
-    token_total_supply: u64,
-    fee_basis_points: u64,
+    pub fee_recipient: Pubkey,
+    pub initial_virtual_token_reserves: u64,
+    pub initial_virtual_sol_reserves: u64,
+    pub initial_real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub fee_basis_points: u64,
 }
 #[derive(Clone, Debug, PartialEq)]
 pub struct SetParamsEventEvent(pub SetParamsEvent);
@@ -7,3 +7,4 @@ pub mod builder;
 pub mod errors;
 pub mod events;
 pub mod instructions;
+pub mod quote;
@@ -0,0 +1,258 @@
+use crate::utils::slippage::MAX_BPS;
+
+use super::accounts::{BondingCurve, Global};
+
+/// A trade priced on a bonding curve, and the curve it leaves behind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    /// Lamports paid on a buy or received on a sell, the fee included.
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    /// Lamports of the trade that go to the fee recipient.
+    pub fee: u64,
+    pub curve: BondingCurve,
+}
+
+/// Prices trades the way the Pump.fun program settles them: the curve moves by
+/// the SOL net of the fee, and the fee is charged on top of a buy and taken
+/// out of a sell.
+#[derive(Debug, Clone, Copy)]
+pub struct BondingCurveQuote<'a> {
+    curve: &'a BondingCurve,
+    fee_bps: u64,
+}
+
+impl<'a> BondingCurveQuote<'a> {
+    pub fn new(curve: &'a BondingCurve, fee_bps: u64) -> Self {
+        BondingCurveQuote {
+            curve,
+            fee_bps: fee_bps.min(MAX_BPS),
+        }
+    }
+
+    /// Charges the fee the global account is set to.
+    pub fn with_global(curve: &'a BondingCurve, global: &Global) -> Self {
+        Self::new(curve, global.fee_basis_points)
+    }
+
+    fn fee(&self, sol_amount: u64) -> u64 {
+        (sol_amount as u128 * self.fee_bps as u128 / MAX_BPS as u128) as u64
+    }
+
+    fn open(&self) -> eyre::Result<&BondingCurve> {
+        if self.curve.complete {
+            return Err(eyre::eyre!("bonding curve is complete"));
+        }
+        Ok(self.curve)
+    }
+
+    /// Spends `sol_amount` lamports, fee included, on as many tokens as they
+    /// buy. Past the tokens left on the curve, the rest are bought for less.
+    pub fn buy_exact_sol(&self, sol_amount: u64) -> eyre::Result<Quote> {
+        let curve = self.open()?;
+        let sol_in =
+            (sol_amount as u128 * MAX_BPS as u128 / (MAX_BPS + self.fee_bps) as u128) as u64;
+        if sol_in == 0 {
+            return Ok(self.buy(0, 0));
+        }
+
+        let product = curve.virtual_sol_reserves as u128 * curve.virtual_token_reserves as u128;
+        let new_token_reserves =
+            product / (curve.virtual_sol_reserves as u128 + sol_in as u128) + 1;
+        let tokens =
+            (curve.virtual_token_reserves as u128).saturating_sub(new_token_reserves) as u64;
+        if tokens >= curve.real_token_reserves {
+            return self.buy_exact_tokens(curve.real_token_reserves);
+        }
+        Ok(self.buy(tokens, sol_in))
+    }
+
+    /// What buying exactly `token_amount` tokens costs, fee included.
+    pub fn buy_exact_tokens(&self, token_amount: u64) -> eyre::Result<Quote> {
+        let curve = self.open()?;
+        if token_amount > curve.real_token_reserves {
+            return Err(eyre::eyre!(
+                "only {} tokens are left on the curve, cannot buy {}",
+                curve.real_token_reserves,
+                token_amount
+            ));
+        }
+        if token_amount == 0 {
+            return Ok(self.buy(0, 0));
+        }
+
+        let sol_in = token_amount as u128 * curve.virtual_sol_reserves as u128
+            / (curve.virtual_token_reserves - token_amount) as u128
+            + 1;
+        Ok(self.buy(token_amount, sol_in as u64))
+    }
+
+    fn buy(&self, token_amount: u64, sol_in: u64) -> Quote {
+        let fee = self.fee(sol_in);
+        let mut curve = self.curve.clone();
+        curve.virtual_sol_reserves += sol_in;
+        curve.real_sol_reserves += sol_in;
+        curve.virtual_token_reserves -= token_amount;
+        curve.real_token_reserves -= token_amount;
+
+        Quote {
+            sol_amount: sol_in + fee,
+            token_amount,
+            fee,
+            curve,
+        }
+    }
+
+    /// What selling exactly `token_amount` tokens pays out, net of the fee.
+    pub fn sell_exact_tokens(&self, token_amount: u64) -> eyre::Result<Quote> {
+        let curve = self.open()?;
+        let sol_out = (token_amount as u128 * curve.virtual_sol_reserves as u128
+            / (curve.virtual_token_reserves as u128 + token_amount as u128))
+            as u64;
+        let sol_out = sol_out.min(curve.real_sol_reserves);
+        let fee = self.fee(sol_out);
+
+        let mut after = curve.clone();
+        after.virtual_sol_reserves -= sol_out;
+        after.real_sol_reserves -= sol_out;
+        after.virtual_token_reserves += token_amount;
+        after.real_token_reserves += token_amount;
+
+        Ok(Quote {
+            sol_amount: sol_out - fee,
+            token_amount,
+            fee,
+            curve: after,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+    use crate::{pumpfun::pump_interface::events::TradeEvent, testing::pump_global};
+
+    /// Every curve starts here.
+    fn fresh() -> BondingCurve {
+        BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        }
+    }
+
+    #[test]
+    fn quotes_follow_the_program() {
+        let curve = fresh();
+        let global = pump_global();
+        let quote = BondingCurveQuote::with_global(&curve, &global);
+
+        // 1 SOL into a fresh curve buys 34.6M tokens, for 1.01 SOL with the fee
+        let buy = quote.buy_exact_sol(1_010_000_000).unwrap();
+        assert_eq!(buy.token_amount, 34_612_903_225_806);
+        assert_eq!((buy.sol_amount, buy.fee), (1_010_000_000, 10_000_000));
+        assert_eq!(quote.buy_exact_tokens(buy.token_amount).unwrap(), buy);
+
+        // Curves complete at about 85 SOL once all 793.1M tokens are sold
+        let all = quote.buy_exact_tokens(793_100_000_000_000).unwrap();
+        assert_eq!(all.curve.real_sol_reserves, 85_005_359_057);
+        assert_eq!(all.fee, 850_053_590);
+        assert_eq!(quote.buy_exact_sol(100_000_000_000).unwrap(), all);
+        assert!(quote.buy_exact_tokens(793_100_000_000_001).is_err());
+
+        // Selling the tokens straight back loses the fee both ways
+        let after = buy.curve.clone();
+        let sell = BondingCurveQuote::with_global(&after, &global)
+            .sell_exact_tokens(buy.token_amount)
+            .unwrap();
+        assert_eq!((sell.sol_amount, sell.fee), (990_000_000, 9_999_999));
+        assert_eq!(
+            sell.curve.virtual_token_reserves,
+            curve.virtual_token_reserves
+        );
+
+        let mut complete = fresh();
+        complete.complete = true;
+        assert!(BondingCurveQuote::with_global(&complete, &global)
+            .sell_exact_tokens(1)
+            .is_err());
+    }
+
+    /// A trade as its `TradeEvent` logs it: SOL net of the fee, and the
+    /// virtual reserves it left behind.
+    fn trade(is_buy: bool, sol_amount: u64, token_amount: u64, reserves: (u64, u64)) -> TradeEvent {
+        TradeEvent {
+            mint: Pubkey::default(),
+            sol_amount,
+            token_amount,
+            is_buy,
+            user: Pubkey::default(),
+            timestamp: 0,
+            virtual_sol_reserves: reserves.0,
+            virtual_token_reserves: reserves.1,
+        }
+    }
+
+    #[test]
+    fn quotes_reproduce_logged_trades() {
+        let global = pump_global();
+        // Buys sized in SOL and sells, one after another on a fresh curve
+        let trades = [
+            trade(
+                true,
+                495_049_504,
+                17_418_831_135_929,
+                (30_495_049_504, 1_055_581_168_864_071),
+            ),
+            trade(
+                false,
+                286_182_323,
+                10_000_000_000_000,
+                (30_208_867_181, 1_065_581_168_864_071),
+            ),
+            trade(
+                true,
+                1_980_198_019,
+                65_552_127_921_637,
+                (32_189_065_200, 1_000_029_040_942_434),
+            ),
+            trade(
+                false,
+                160_139_975,
+                5_000_000_000_000,
+                (32_028_925_225, 1_005_029_040_942_434),
+            ),
+        ];
+
+        let mut curve = fresh();
+        for trade in trades {
+            let quote = BondingCurveQuote::with_global(&curve, &global);
+            let settled = if trade.is_buy {
+                let buy = quote.buy_exact_tokens(trade.token_amount).unwrap();
+                assert_eq!(buy.sol_amount - buy.fee, trade.sol_amount);
+                // The SOL the buyer spent, the fee on top of the logged amount
+                let spent =
+                    (trade.sol_amount * (MAX_BPS + global.fee_basis_points)).div_ceil(MAX_BPS);
+                assert_eq!(quote.buy_exact_sol(spent).unwrap(), buy);
+                buy
+            } else {
+                let sell = quote.sell_exact_tokens(trade.token_amount).unwrap();
+                assert_eq!(sell.sol_amount + sell.fee, trade.sol_amount);
+                sell
+            };
+            assert_eq!(
+                (
+                    settled.curve.virtual_sol_reserves,
+                    settled.curve.virtual_token_reserves
+                ),
+                (trade.virtual_sol_reserves, trade.virtual_token_reserves)
+            );
+            curve = settled.curve;
+        }
+    }
+}
@@ -4,24 +4,27 @@ use colorize::AnsiColor;
 use crossterm::style::Stylize;
 use log::{error, info};
 use solana_sdk::{
-    native_token::{lamports_to_sol, sol_to_lamports},
+    native_token::lamports_to_sol,
     pubkey::Pubkey,
     signature::Keypair,
 };
-use yellowstone_grpc_proto::geyser::SubscribeUpdateTransaction;
 
 use crate::{
     app::config_init::{get_config, update_config_field},
     env::{load_config, SettingsConfig},
+    geyser::ParsedTransaction,
     pumpfun::{
         executor::pump_swap,
-        pump_interface::{builder::PumpFunDirection, instructions::CreateIxData},
+        filters::{screen, Launch, CREATE_USER_INDEX},
+        pump_interface::{
+            builder::{PumpAmount, PumpFunDirection, PUMP_PROGRAM},
+            instructions::CreateIxData,
+        },
     },
     raydium_amm::{
-        subscribe::auto_sniper_stream,
         swap::{metadata::decode_metadata, raydium_amm_sniper::RAYDIUM_AMM_FEE_COLLECTOR},
     },
-    router::{grpc_pair_sub, SniperRoute},
+    router::{pair_sub, SniperRoute},
     user_inputs::{amounts::sol_amount, tokens::token_env},
 };
 
@@ -37,12 +40,8 @@ pub async fn pumpfun_sniper(manual_snipe: bool, route: SniperRoute) -> eyre::Res
         }
     };
 
-    if args.network.grpc_url.is_empty() {
-        let _ = auto_sniper_stream(manual_snipe).await?;
-        return Ok(());
-    }
     let sol_amount = sol_amount("Snipe Amount:").await;
-
+    // Only an amount typed at the prompt is kept for next time.
     update_config_field(|c| &mut c.trading.buy_amount, lamports_to_sol(sol_amount)).await?;
 
     let token;
@@ -79,6 +78,18 @@ pub async fn pumpfun_sniper(manual_snipe: bool, route: SniperRoute) -> eyre::Res
         token = None;
     }
 
+    pumpfun_sniper_run(args, token, sol_amount, route).await
+}
+
+pub async fn pumpfun_sniper_run(
+    mut args: SettingsConfig,
+    token: Option<Pubkey>,
+    sol_amount: u64,
+    route: SniperRoute,
+) -> eyre::Result<()> {
+    let manual_snipe = token.is_some();
+    args.trading.buy_amount = lamports_to_sol(sol_amount);
+
     let contract = if route == SniperRoute::PumpFun {
         PUMPFUN_CONTRACT
     } else if route == SniperRoute::RaydiumAMM {
@@ -89,7 +100,7 @@ pub async fn pumpfun_sniper(manual_snipe: bool, route: SniperRoute) -> eyre::Res
         RAYDIUM_AMM_FEE_COLLECTOR
     };
 
-    let _ = match grpc_pair_sub(args, manual_snipe, token, contract.into(), route).await {
+    let _ = match pair_sub(args, manual_snipe, token, contract.into(), route).await {
         Ok(_) => info!("Transaction Sent"),
         Err(e) => error!("{}", e),
     };
@@ -99,81 +110,48 @@ pub async fn pumpfun_sniper(manual_snipe: bool, route: SniperRoute) -> eyre::Res
 
 pub async fn pumpfun_parser(
     args: SettingsConfig,
-    tx: SubscribeUpdateTransaction,
+    tx: &ParsedTransaction,
     base_mint: Option<Pubkey>,
+    amount: u64,
 ) -> eyre::Result<()> {
     let config = get_config().await?;
     let wallet = Keypair::from_base58_string(&config.engine.payer_keypair);
 
-    let info = tx.clone().transaction.unwrap_or_default();
-    let accounts = info
-        .transaction
-        .clone()
-        .unwrap_or_default()
-        .message
-        .unwrap_or_default()
-        .account_keys
-        .iter()
-        .map(|i| {
-            let mut array = [0; 32];
-            let bytes = &i[..array.len()]; // panics if not enough data
-            array.copy_from_slice(bytes);
-            Pubkey::new_from_array(array)
-        })
-        .collect::<Vec<Pubkey>>();
-    let outer_instructions = {
-        let transaction = info.transaction.clone().unwrap_or_default();
-        let message = transaction.message.unwrap_or_default();
-        let instructions = message.instructions.iter();
-        instructions.cloned().collect::<Vec<_>>()
+    // The create instruction names the mint it initializes first, and its
+    // creator, who may not have paid for the transaction.
+    let Some((mint, creator, coin_args)) = tx.find_instructions(&PUMP_PROGRAM).find_map(|ix| {
+        let coin_args = CreateIxData::deserialize(&ix.data).ok()?;
+        Some((*ix.accounts.first()?, *ix.accounts.get(CREATE_USER_INDEX)?, coin_args))
+    }) else {
+        return Ok(());
     };
 
-    let mut coin_found = false;
-
-    let mut coin_args: Option<CreateIxData> = None;
-    for instructions in outer_instructions.iter() {
-        match CreateIxData::deserialize(&instructions.data) {
-            Ok(decode_new_coin) => {
-                coin_found = true;
-                coin_args = Some(decode_new_coin);
-                break;
+    match base_mint {
+        Some(base_mint) if mint != base_mint => return Ok(()),
+        Some(_) => {}
+        None => {
+            let launch = Launch::from_transaction(tx, mint, creator, &coin_args.0);
+            if let Err(rejection) = screen(&args, &launch).await {
+                info!("Skipping {} ({}), {}", launch.mint, launch.symbol, rejection);
+                return Ok(());
             }
-            Err(_) => {
-                continue;
-            }
-        };
-    }
-
-    if !coin_found {
-        return Ok(());
-    }
-
-    let signature = bs58::encode(&info.signature).into_string();
-
-    if base_mint.is_some() {
-        if accounts[1] != base_mint.unwrap() {
-            return Ok(());
         }
     }
 
     println!(
         "Transaction: {}\nCoin: {:?}\nMaker: {}\nMint: {}",
-        &signature.to_string(),
-        coin_args.as_ref().unwrap().0,
-        accounts[0],
-        accounts[1]
+        tx.signature,
+        coin_args.0,
+        creator,
+        mint
     );
 
-    let config = get_config().await?;
-
-    let amount = sol_to_lamports(config.trading.buy_amount);
-
     match pump_swap(
         &Arc::new(wallet),
         args,
         PumpFunDirection::Buy,
-        accounts[1],
-        amount,
+        mint,
+        PumpAmount::Sol(amount),
     )
     .await
     {
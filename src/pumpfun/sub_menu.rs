@@ -5,6 +5,7 @@ use demand::{DemandOption, Select};
 use crate::app::{main_menu, theme};
 
 use super::{
+    launch::pump_launch,
     sniper::pumpfun_sniper,
     swap::{pump_swap_in, pump_swap_out},
 };
@@ -19,6 +20,7 @@ pub async fn pump_main() -> Result<(), Box<dyn Error + Send>> {
         .option(DemandOption::new("MigrationAuto").label("💠 PumpFun Migration Auto Sniper"))
         .option(DemandOption::new("PumpSniperAuto").label("▪ PumpFun Coin Auto Sniper"))
         .option(DemandOption::new("PumpSniperManual").label("▪ PumpFun Coin Manual Sniper"))
+        .option(DemandOption::new("PumpLaunch").label("▪ Launch on Pump.fun"))
         .option(DemandOption::new("PumpBuy").label("▪ Pump Swap-In"))
         .option(DemandOption::new("PumpSell").label("▪ Pump Swap-Out"))
         .option(DemandOption::new("Main Menu").label(" ↪  Main Menu"));
@@ -38,11 +40,20 @@ pub async fn pump_main() -> Result<(), Box<dyn Error + Send>> {
         "PumpSniperManual" => {
             let _ = pumpfun_sniper(true, crate::router::SniperRoute::PumpFun).await;
         }
+        "PumpLaunch" => {
+            if let Err(e) = pump_launch().await {
+                log::error!("Error: {}", e);
+            }
+        }
         "PumpBuy" => {
-            let _ = pump_swap_in().await;
+            if let Err(e) = pump_swap_in().await {
+                log::error!("Error: {}", e);
+            }
         }
         "PumpSell" => {
-            let _ = pump_swap_out().await;
+            if let Err(e) = pump_swap_out().await {
+                log::error!("Error: {}", e);
+            }
         }
         "Main Menu" => {
             let _ = main_menu(false).await;
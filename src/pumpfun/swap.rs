@@ -1,96 +1,58 @@
 use std::sync::Arc;
 
-use log::error;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{signature::Keypair, signer::Signer};
-use spl_associated_token_account::get_associated_token_address;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair};
 
 use crate::{
     env::load_config,
     input::{amount_input, mint_input, percentage_input},
+    sender::SendOutcome,
 };
 
-use super::{executor::pump_swap, pump_interface::builder::PumpFunDirection};
+use super::{
+    executor::pump_swap,
+    pump_interface::builder::{PumpAmount, PumpFunDirection},
+};
 
 pub async fn pump_swap_in() -> eyre::Result<()> {
-    let settings = match load_config().await {
-        Ok(s) => s,
-        Err(e) => {
-            log::error!("Error: {}", e);
-            return Ok(());
-        }
-    };
-
     let amount = amount_input("Input Sol: ").await;
-
-    let wallet = Keypair::from_base58_string(&settings.engine.payer_keypair);
     let token = mint_input("Base Mint: ").await;
-    match pump_swap(
-        &Arc::new(wallet),
-        settings,
-        PumpFunDirection::Buy,
-        token,
-        amount,
-    )
-    .await
-    {
-        Ok(s) => s,
-        Err(e) => {
-            log::error!("Error: {}", e);
-            return Ok(());
-        }
-    };
 
-    Ok(())
+    pump_buy(token, PumpAmount::Sol(amount)).await
 }
 
-pub async fn pump_swap_out() -> eyre::Result<()> {
-    let settings = match load_config().await {
-        Ok(s) => s,
-        Err(e) => {
-            log::error!("Error: {}", e);
-            return Ok(());
-        }
-    };
+/// Buys `token` for a SOL amount or an exact number of tokens. An error unless
+/// the buy landed.
+pub async fn pump_buy(token: Pubkey, amount: PumpAmount) -> eyre::Result<()> {
+    pump_trade(PumpFunDirection::Buy, token, amount).await
+}
 
-    let rpc_client = RpcClient::new(settings.clone().network.rpc_url);
-    let wallet = Keypair::from_base58_string(&settings.engine.payer_keypair);
+pub async fn pump_swap_out() -> eyre::Result<()> {
     let token_address = mint_input("Base Mint: ").await;
+    let percentage = percentage_input().await;
 
-    let tokens = percentage_input().await as u64;
-    let tokens_amount = rpc_client
-        .get_token_account_balance(&get_associated_token_address(
-            &wallet.pubkey(),
-            &token_address,
-        ))
-        .await?;
-
-    let token_amount = match tokens_amount.amount.parse::<u64>() {
-        Ok(a) => a,
-        Err(e) => {
-            error!("Error: {}", e);
-            return Ok(());
-        }
-    };
+    pump_sell(token_address, PumpAmount::Percentage(percentage)).await
+}
 
-    let amount = token_amount * tokens / 100;
+/// Sells an exact number of tokens, or a percentage of the wallet's balance.
+/// An error unless the sell landed.
+pub async fn pump_sell(token_address: Pubkey, amount: PumpAmount) -> eyre::Result<()> {
+    pump_trade(PumpFunDirection::Sell, token_address, amount).await
+}
 
+async fn pump_trade(
+    direction: PumpFunDirection,
+    token: Pubkey,
+    amount: PumpAmount,
+) -> eyre::Result<()> {
+    let settings = load_config().await?;
     let wallet = Keypair::from_base58_string(&settings.engine.payer_keypair);
-    match pump_swap(
-        &Arc::new(wallet),
-        settings,
-        PumpFunDirection::Sell,
-        token_address,
-        amount,
-    )
-    .await
-    {
-        Ok(s) => s,
-        Err(e) => {
-            log::error!("Error: {}", e);
-            return Ok(());
-        }
-    };
-
-    Ok(())
+    let report = pump_swap(&Arc::new(wallet), settings, direction, token, amount).await?;
+    match report.outcome {
+        SendOutcome::Landed { .. } => Ok(()),
+        SendOutcome::Failed { error } => Err(eyre::eyre!("Trade of {} failed: {}", token, error)),
+        SendOutcome::Expired => Err(eyre::eyre!(
+            "Trade of {} expired before it confirmed",
+            token
+        )),
+    }
 }
@@ -91,7 +91,6 @@ pub async fn copytrade_raydium_amm_builder(
         &Arc::new(wallet),
         pool_keys.clone(),
         *amount_in,
-        1,
         config.clone(),
         TradeDirection::Buy,
     )
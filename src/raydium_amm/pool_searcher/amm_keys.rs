@@ -1,13 +1,17 @@
 use std::sync::Arc;
 
-use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcProgramAccountsConfig,
+    rpc_filter::{Memcmp, RpcFilterType},
+};
 use solana_program::pubkey::Pubkey;
 
 use crate::{
     app::config_init::get_config,
     raydium_amm::{
         subscribe::PoolKeysSniper,
-        swap::instructions::SOLC_MINT,
+        swap::{instructions::SOLC_MINT, raydium_amm_sniper::RAYDIUM_AMM_V4_PROGRAM_ID},
         utils::utils::{
             market_authority, program_address, LIQUIDITY_STATE_LAYOUT_V4, MARKET_STATE_LAYOUT_V3,
             SPL_MINT_LAYOUT,
@@ -115,3 +119,36 @@ pub async fn get_market_accounts(
         }
     }
 }
+
+/// Size of an AMM v4 pool account, and where its coin and pc mints sit in it.
+const POOL_ACCOUNT_LEN: u64 = 752;
+const POOL_BASE_MINT_OFFSET: usize = 400;
+const POOL_QUOTE_MINT_OFFSET: usize = 432;
+
+/// The AMM v4 pool pairing `mint` with wrapped SOL, either way round, if one
+/// exists yet.
+pub async fn find_sol_pool(rpc_client: &RpcClient, mint: &Pubkey) -> eyre::Result<Option<Pubkey>> {
+    for (base, quote) in [(mint, &SOLC_MINT), (&SOLC_MINT, mint)] {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(POOL_ACCOUNT_LEN),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                    POOL_BASE_MINT_OFFSET,
+                    &base.to_bytes(),
+                )),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                    POOL_QUOTE_MINT_OFFSET,
+                    &quote.to_bytes(),
+                )),
+            ]),
+            ..RpcProgramAccountsConfig::default()
+        };
+        let pools = rpc_client
+            .get_program_accounts_with_config(&RAYDIUM_AMM_V4_PROGRAM_ID, config)
+            .await?;
+        if let Some((pool, _)) = pools.into_iter().next() {
+            return Ok(Some(pool));
+        }
+    }
+    Ok(None)
+}
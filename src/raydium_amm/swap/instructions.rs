@@ -1,5 +1,3 @@
-use jito_protos::searcher::SubscribeBundleResultsRequest;
-use jito_searcher_client::{get_searcher_client, send_bundle_with_confirmation};
 use log::info;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -17,9 +15,9 @@ use solana_program::{
 use solana_sdk::{
     address_lookup_table::AddressLookupTableAccount,
     commitment_config::{CommitmentConfig, CommitmentLevel},
-    compute_budget::ComputeBudgetInstruction,
-    message::{v0::Message, VersionedMessage},
+    message::v0::Message,
     native_token::{lamports_to_sol, sol_to_lamports},
+    program_pack::Pack,
     pubkey,
     signature::Keypair,
     signer::Signer,
@@ -34,11 +32,12 @@ use std::{mem::size_of, str::FromStr};
 use crate::{
     app::config_init::get_config,
     env::{load_config, minter::load_minter_settings},
-    liquidity::utils::{tip_account, tip_txn},
     raydium_amm::subscribe::PoolKeysSniper,
+    sender::{SendStrategy, TransactionSender},
+    utils::slippage::constant_product_out,
 };
 
-use super::{raydium_swap_in::TradeDirection, swapper::auth_keypair};
+use super::raydium_swap_in::TradeDirection;
 
 /// Instructions supported by the AmmInfo program.
 #[repr(C)]
@@ -260,7 +259,6 @@ pub async fn swap_base_out(
     base_mint: &Pubkey,
     amount_in: u64,
     minimum_amount_out: u64,
-    priority_fee: u64,
 ) -> Result<Vec<Instruction>, ProgramError> {
     let data = AmmInstruction::SwapBaseIn(SwapInstructionBaseIn {
         amount_in,
@@ -268,17 +266,11 @@ pub async fn swap_base_out(
     })
     .pack()?;
 
-    let unit_limit = ComputeBudgetInstruction::set_compute_unit_limit(80000);
-    let compute_price = ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
-
     let source_token_account = get_associated_token_address(wallet_address, &SOLC_MINT);
     let destination_token_account = get_associated_token_address(wallet_address, base_mint);
 
     let mut instructions = Vec::new();
 
-    instructions.push(compute_price);
-    instructions.push(unit_limit);
-
     let accounts = vec![
         // spl token
         AccountMeta::new_readonly(spl_token::id(), false),
@@ -544,6 +536,44 @@ pub async fn token_price_data(
     Ok(swap_amount_out)
 }
 
+/// Trade fee of the AMM v4 pools on the input side.
+pub const RAYDIUM_FEE_BPS: u64 = 25;
+
+/// Output of swapping `amount_in` of `input_mint` against the balances of the
+/// pool `vaults`. Funds the pool holds on its market are left out, so the
+/// quote errs low.
+pub async fn quote_from_vaults(
+    rpc_client: &RpcClient,
+    vaults: [Pubkey; 2],
+    input_mint: &Pubkey,
+    amount_in: u64,
+) -> eyre::Result<u64> {
+    let mut reserve_in = None;
+    let mut reserve_out = None;
+    for (vault, account) in vaults
+        .iter()
+        .zip(rpc_client.get_multiple_accounts(&vaults).await?)
+    {
+        let account = account.ok_or_else(|| eyre::eyre!("Pool vault {} not found", vault))?;
+        let vault = spl_token::state::Account::unpack_from_slice(&account.data)?;
+        if vault.mint == *input_mint {
+            reserve_in = Some(vault.amount);
+        } else {
+            reserve_out = Some(vault.amount);
+        }
+    }
+
+    match (reserve_in, reserve_out) {
+        (Some(reserve_in), Some(reserve_out)) => Ok(constant_product_out(
+            reserve_in,
+            reserve_out,
+            amount_in,
+            RAYDIUM_FEE_BPS,
+        )),
+        _ => Err(eyre::eyre!("Pool vaults do not trade {}", input_mint)),
+    }
+}
+
 /* ---------------------------------------------------------------- */
 
 pub async fn wrap_sol(
@@ -551,17 +581,11 @@ pub async fn wrap_sol(
     wallet: &Keypair,
     amount_in: u64,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let unit_limit = ComputeBudgetInstruction::set_compute_unit_limit(80000);
-    let compute_price = ComputeBudgetInstruction::set_compute_unit_price(sol_to_lamports(0.0001));
-
     let user_token_destination = get_associated_token_address(&wallet.pubkey(), &SOLC_MINT);
 
     info!("Wrapping Sol...");
     let mut instructions = Vec::new();
 
-    instructions.push(unit_limit);
-    instructions.push(compute_price);
-
     // Check if the account already exists and is owned by the SPL Token program
     if let Ok(account) = rpc_client.get_account(&user_token_destination).await {
         if account.owner != spl_token::id() {
@@ -591,19 +615,11 @@ pub async fn wrap_sol(
     let sync_native = sync_native(&spl_token::id(), &user_token_destination)?;
     instructions.push(sync_native);
 
-    let transaction = Transaction::new_signed_with_payer(
-        &instructions,
-        Some(&wallet.pubkey()),
-        &[wallet],
-        rpc_client.get_latest_blockhash().await?,
-    );
-
-    info!("Transaction Sent: {:?}", transaction.signatures[0]);
-
-    rpc_client
-        .send_and_confirm_transaction_with_spinner(&transaction)
+    let report = TransactionSender::from_config(&get_config().await?, rpc_client)
+        .send(wallet, &instructions, &[])
         .await
-        .unwrap();
+        .map_err(|e| e.to_string())?;
+    report.log();
 
     Ok(())
 }
@@ -612,7 +628,7 @@ pub async fn wrap_sol(
 
 pub async fn unwrap_sol(deployer: bool) -> Result<(), Box<dyn std::error::Error>> {
     let engine = load_config().await?;
-    let rpc_client = RpcClient::new(engine.network.rpc_url);
+    let rpc_client = RpcClient::new(engine.network.rpc_url.clone());
 
     let mut keypairs: Vec<Keypair> = Vec::new();
 
@@ -652,53 +668,17 @@ pub async fn unwrap_sol(deployer: bool) -> Result<(), Box<dyn std::error::Error>
         }
     }
 
-    let tip_txn = tip_txn(keypairs[0].pubkey(), tip_account(), sol_to_lamports(0.0001));
-    instructions.push(tip_txn);
-
-    let recent_blockhash = rpc_client.get_latest_blockhash().await?;
-
-    let v0_msg = VersionedMessage::V0(Message::try_compile(
-        &keypairs[0].pubkey(),
-        &instructions,
-        &[],
-        recent_blockhash,
-    )?);
-
-    let transaction =
-        VersionedTransaction::try_new(v0_msg, &keypairs.iter().collect::<Vec<&Keypair>>())?;
-
-    let mut client = match get_searcher_client(
-        &engine.network.block_engine_url,
-        &Arc::new(auth_keypair()),
-    )
-    .await
-    {
-        Ok(client) => client,
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            panic!("Error: {}", e);
-        }
-    };
-
-    let mut bundle_results_subscription = client
-        .subscribe_bundle_results(SubscribeBundleResultsRequest {})
+    let sender = TransactionSender::from_config(&engine, Arc::new(rpc_client)).with_strategy(
+        SendStrategy::Bundle {
+            tip_lamports: sol_to_lamports(0.0001),
+        },
+    );
+    let signers = keypairs.iter().collect::<Vec<&Keypair>>();
+    let report = sender
+        .send(&keypairs[0], &instructions, &signers)
         .await
-        .expect("subscribe to bundle results")
-        .into_inner();
-
-    let bundle = match send_bundle_with_confirmation(
-        &[transaction],
-        &Arc::new(rpc_client),
-        &mut client,
-        &mut bundle_results_subscription,
-    )
-    .await
-    {
-        Ok(_) => {}
-        Err(e) => {
-            return Ok(());
-        }
-    };
+        .map_err(|e| e.to_string())?;
+    report.log();
 
     Ok(())
 }
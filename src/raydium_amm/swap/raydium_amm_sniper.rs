@@ -1,12 +1,15 @@
 use {
-    super::{instructions::swap_base_in, raydium_swap_in::price_logger, swapper::auth_keypair},
+    super::{
+        instructions::{quote_from_vaults, swap_base_in},
+        raydium_swap_in::price_logger,
+    },
     crate::{
         app::config_init::get_config,
+        geyser::ParsedTransaction,
         instruction::instruction::{
             AmmInstruction, InitializePoolAccounts, SerumMarketAccounts,
             INITIALIZE_POOL_ACCOUNTS_LEN, SERUM_ACCOUNTS_LEN,
         },
-        liquidity::utils::tip_account,
         raydium_amm::{
             pool_searcher::amm_keys::{get_market_accounts, pool_keys_fetcher},
             sniper::utils::market_authority,
@@ -18,28 +21,28 @@ use {
         },
         router::SniperRoute,
         rpc::HTTP_CLIENT,
-        utils::{read_single_key_impl, transaction_history::get_transaction_history},
+        journal::{Order, TradeSide},
+        positions::{self, venue::Venue},
+        sender::TransactionSender,
+        utils::{
+            read_single_key_impl, slippage::min_out, transaction_history::get_transaction_history,
+        },
     },
     chrono::{DateTime, LocalResult, TimeZone, Utc},
     colorize::AnsiColor,
     crossterm::style::Stylize,
     eyre::Context,
-    futures::{channel::mpsc::SendError, Sink},
-    jito_protos::searcher::SubscribeBundleResultsRequest,
-    jito_searcher_client::{get_searcher_client, send_bundle_with_confirmation},
     log::{debug, error, info, warn},
     once_cell::sync::Lazy,
     serum_dex::instruction::MarketInstruction,
-    solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig},
+    solana_client::nonblocking::rpc_client::RpcClient,
     solana_program::pubkey::Pubkey,
     solana_sdk::{
         native_token::sol_to_lamports,
         program_pack::Pack,
         signature::Keypair,
         signer::Signer,
-        system_instruction::transfer,
         system_program,
-        transaction::{Transaction, VersionedTransaction},
     },
     spl_token::{instruction::TokenInstruction, state::Mint},
     std::{
@@ -51,11 +54,7 @@ use {
         time::{Duration, SystemTime, UNIX_EPOCH},
     },
     tokio::{time::sleep, try_join},
-    yellowstone_grpc_proto::{
-        geyser::SubscribeUpdateTransaction,
-        prelude::{CommitmentLevel, SubscribeRequest},
-        solana::storage::confirmed_block::CompiledInstruction,
-    },
+    yellowstone_grpc_proto::prelude::CommitmentLevel,
 };
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -112,41 +111,16 @@ pub const RAYDIUM_AMM_FEE_COLLECTOR: &str = "7YttLkHDoNj9wyDur5pM1ejNaAvT9X4eqaY
 
 pub async fn raydium_sniper_parser(
     rpc_client: Arc<RpcClient>,
-    tx: SubscribeUpdateTransaction,
+    tx: &ParsedTransaction,
     manual_snipe: bool,
     base_mint: Option<Pubkey>,
     route: SniperRoute,
-    subscribe_tx: tokio::sync::MutexGuard<
-        '_,
-        impl Sink<SubscribeRequest, Error = SendError> + std::marker::Unpin,
-    >,
+    amount_in: u64,
 ) -> eyre::Result<()> {
-    let info = tx.clone().transaction.unwrap_or_default();
-    let accounts = info
-        .transaction
-        .clone()
-        .unwrap_or_default()
-        .message
-        .unwrap_or_default()
-        .account_keys
-        .iter()
-        .map(|i| {
-            let mut array = [0; 32];
-            let bytes = &i[..array.len()]; // panics if not enough data
-            array.copy_from_slice(bytes);
-            Pubkey::new_from_array(array)
-        })
-        .collect::<Vec<Pubkey>>();
-    let outer_instructions = {
-        let transaction = info.transaction.unwrap_or_default();
-        let message = transaction.message.unwrap_or_default();
-        let instructions = message.instructions.iter();
-        instructions.cloned().collect::<Vec<_>>()
-    };
-
-    let meta = info.meta.unwrap_or_default();
+    let accounts = tx.account_keys();
+    let outer_instructions = &tx.instructions;
 
-    let log_messages = meta.clone().log_messages;
+    let log_messages = &tx.logs;
     let open_time_match = log_messages.iter().find(|m| m.contains("open_time"));
 
     let open_time_split = open_time_match
@@ -189,19 +163,6 @@ pub async fn raydium_sniper_parser(
         }
     };
 
-    let inner_instructions: Vec<CompiledInstruction> = meta
-        .clone()
-        .inner_instructions
-        .iter()
-        .flat_map(|inner| {
-            inner.instructions.iter().map(|instr| CompiledInstruction {
-                program_id_index: instr.program_id_index,
-                accounts: instr.accounts.clone(),
-                data: instr.data.clone(),
-            })
-        })
-        .collect();
-
     let mut coin_args_amm: Option<crate::instruction::instruction::InitializeInstruction2> = None;
     let mut raydium_accounts: Option<InitializePoolAccounts> = None;
     let mut trade_route: Option<SniperRoute> = None;
@@ -218,25 +179,25 @@ pub async fn raydium_sniper_parser(
                         spl_associated_token_account: spl_associated_token_account::id(),
                         system_program: system_program::id(),
                         rent: Pubkey::from_str("SysvarRent111111111111111111111111111111111")?,
-                        amm_pool: accounts[instructions.accounts[4] as usize],
-                        amm_authority: accounts[instructions.accounts[5] as usize],
-                        amm_open_orders: accounts[instructions.accounts[6] as usize],
-                        amm_lp_mint: accounts[instructions.accounts[7] as usize],
-                        amm_coin_mint: accounts[instructions.accounts[8] as usize],
-                        amm_pc_mint: accounts[instructions.accounts[9] as usize],
-                        amm_coin_vault: accounts[instructions.accounts[10] as usize],
-                        amm_pc_vault: accounts[instructions.accounts[11] as usize],
-                        amm_target_orders: accounts[instructions.accounts[12] as usize],
-                        amm_config: accounts[instructions.accounts[13] as usize],
-                        create_fee_destination: accounts[instructions.accounts[14] as usize],
+                        amm_pool: instructions.accounts[4],
+                        amm_authority: instructions.accounts[5],
+                        amm_open_orders: instructions.accounts[6],
+                        amm_lp_mint: instructions.accounts[7],
+                        amm_coin_mint: instructions.accounts[8],
+                        amm_pc_mint: instructions.accounts[9],
+                        amm_coin_vault: instructions.accounts[10],
+                        amm_pc_vault: instructions.accounts[11],
+                        amm_target_orders: instructions.accounts[12],
+                        amm_config: instructions.accounts[13],
+                        create_fee_destination: instructions.accounts[14],
                         market_program: Pubkey::from_str(
                             "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX",
                         )?,
-                        market: accounts[instructions.accounts[16] as usize],
-                        user_wallet: accounts[instructions.accounts[17] as usize],
-                        user_token_coin: accounts[instructions.accounts[18] as usize],
-                        user_token_pc: accounts[instructions.accounts[19] as usize],
-                        user_token_lp: accounts[instructions.accounts[20] as usize],
+                        market: instructions.accounts[16],
+                        user_wallet: instructions.accounts[17],
+                        user_token_coin: instructions.accounts[18],
+                        user_token_pc: instructions.accounts[19],
+                        user_token_lp: instructions.accounts[20],
                         // Additional fields from the previous RaydiumAmmAccounts
                     });
                 }
@@ -288,7 +249,7 @@ pub async fn raydium_sniper_parser(
         return Ok(());
     }
 
-    let _ = sniper_txn_in_2(accounts.clone(), open_time, datetime, route).await;
+    let _ = sniper_txn_in_2(accounts.clone(), open_time, datetime, route, amount_in).await;
 
     Ok(())
 }
@@ -298,6 +259,7 @@ pub async fn sniper_txn_in_2(
     sleep_duration: u64,
     datetime: chrono::DateTime<Utc>,
     route: SniperRoute,
+    amount_in: u64,
 ) -> eyre::Result<()> {
     let accounts = pool_keys.clone();
     tokio::spawn(async move {
@@ -309,7 +271,7 @@ pub async fn sniper_txn_in_2(
     let sleep_duration = calculate_sleep_duration(sleep_duration).await;
     sleep(sleep_duration).await;
 
-    let _ = match raydium_snipe_launch(pool_keys, None, 1, route).await {
+    let _ = match raydium_snipe_launch(pool_keys, Some(amount_in), route).await {
         Ok(tx) => tx,
         Err(e) => {
             error!("Error: {:?}", e);
@@ -368,7 +330,6 @@ async fn calculate_sleep_duration(sleep_duration: u64) -> Duration {
 pub async fn raydium_snipe_launch(
     pool_keys: InitializePoolAccounts,
     init_amount_in: Option<u64>,
-    amount_out: u64,
     route: SniperRoute,
 ) -> eyre::Result<()> {
     let config = get_config().await?;
@@ -382,11 +343,6 @@ pub async fn raydium_snipe_launch(
         let http_client = HTTP_CLIENT.lock().unwrap();
         http_client.get("http_client").unwrap().clone()
     };
-    let mut searcher_client =
-        get_searcher_client(&config.network.block_engine_url, &Arc::new(auth_keypair())).await?;
-
-    let tip_account = tip_account();
-
     let rpc_client = rpc_client;
 
     let token_address = if pool_keys.amm_coin_mint == SOLC_MINT {
@@ -395,7 +351,7 @@ pub async fn raydium_snipe_launch(
         pool_keys.clone().amm_coin_mint
     };
 
-    let route_data = match process_route(route, rpc_client.clone(), &pool_keys).await {
+    let route_data = match process_route(route.clone(), rpc_client.clone(), &pool_keys).await {
         Ok(keys) => keys,
         Err(e) => {
             println!("Error: {e}");
@@ -448,7 +404,16 @@ pub async fn raydium_snipe_launch(
 
         println!("Keys: {market:#?}\n{market_vault_signer:#?}");
 
-        let mut swap_instructions = swap_base_in(
+        let quote = quote_from_vaults(
+            &rpc_client,
+            [pool_keys.amm_coin_vault, pool_keys.amm_pc_vault],
+            &SOLC_MINT,
+            amount_in,
+        )
+        .await?;
+        let amount_out = min_out(quote, config.slippage_bps(&route));
+
+        let swap_instructions = swap_base_in(
             &RAYDIUM_AMM_V4_PROGRAM_ID,
             &pool_keys.amm_pool,
             &pool_keys.amm_authority,
@@ -473,113 +438,13 @@ pub async fn raydium_snipe_launch(
         )
         .await?;
 
-        let (latest_blockhash, _) = rpc_client
-            .get_latest_blockhash_with_commitment(solana_sdk::commitment_config::CommitmentConfig {
-                commitment: solana_sdk::commitment_config::CommitmentLevel::Finalized,
-            })
+        let report = TransactionSender::from_config(&config, rpc_client.clone())
+            .with_order(Order::new(route, TradeSide::Buy, token_address, amount_in))
+            .send(&wallet, &swap_instructions, &[])
             .await?;
-
-        let message = match solana_program::message::v0::Message::try_compile(
-            &user_source_owner,
-            &swap_instructions,
-            &[],
-            latest_blockhash,
-        ) {
-            Ok(x) => x,
-            Err(e) => {
-                println!("Error: {:?}", e);
-                return Ok(());
-            }
-        };
-
-        let transaction = match VersionedTransaction::try_new(
-            solana_program::message::VersionedMessage::V0(message),
-            &[&wallet],
-        ) {
-            Ok(x) => x,
-            Err(e) => {
-                println!("Error: {:?}", e);
-                return Ok(());
-            }
-        };
-
-        if config.engine.use_bundles {
-            info!("Building Bundle");
-
-            swap_instructions.push(transfer(
-                &wallet.pubkey(),
-                &tip_account,
-                sol_to_lamports(config.trading.bundle_tip),
-            ));
-
-            let swap_txn = VersionedTransaction::from(Transaction::new_signed_with_payer(
-                &swap_instructions,
-                Some(&wallet.pubkey()),
-                &[&wallet],
-                rpc_client.get_latest_blockhash().await.unwrap(),
-            ));
-
-            let bundle_txn = vec![swap_txn];
-
-            let mut bundle_results_subscription = searcher_client
-                .subscribe_bundle_results(SubscribeBundleResultsRequest {})
-                .await
-                .expect("subscribe to bundle results")
-                .into_inner();
-
-            let bundle = match send_bundle_with_confirmation(
-                &bundle_txn,
-                &rpc_client,
-                &mut searcher_client,
-                &mut bundle_results_subscription,
-            )
-            .await
-            {
-                Ok(_) => {}
-                Err(e) => {
-                    error!("Error: {}", e);
-                }
-            };
-
-            std::mem::drop(bundle_results_subscription);
-        } else {
-            info!("Sending Transaction");
-            let transaction_flight = RpcSendTransactionConfig {
-                skip_preflight: true,
-                ..Default::default()
-            };
-
-            if config.trading.spam {
-                let mut counter = 0;
-                while counter < config.trading.spam_count {
-                    let result = match rpc_client
-                        .send_transaction_with_config(&transaction, transaction_flight)
-                        .await
-                    {
-                        Ok(x) => x,
-                        Err(e) => {
-                            error!("Error: {:?}", e);
-                            return Ok(());
-                        }
-                    };
-
-                    info!("Transaction Sent {:?}", result);
-                    counter += 1;
-                }
-            } else {
-                let result = match rpc_client
-                    .send_transaction_with_config(&transaction, transaction_flight)
-                    .await
-                {
-                    Ok(x) => x,
-                    Err(e) => {
-                        error!("Error: {:?}", e);
-                        return Ok(());
-                    }
-                };
-
-                info!("Transaction Sent {:?}", result);
-            }
+        report.log();
+        if !report.landed() {
+            return Ok(());
         }
 
         let pool_keys = match fetch_pool_keys_with_retry(
@@ -595,6 +460,12 @@ pub async fn raydium_snipe_launch(
                 return Err(e.into());
             }
         };
+        positions::open(
+            Venue::RaydiumAMM(Box::new(pool_keys.clone())),
+            token_address,
+            amount_in,
+            &report,
+        );
 
         let (mut stop_tx, mut stop_rx) = tokio::sync::mpsc::channel::<()>(100);
 
@@ -603,22 +474,9 @@ pub async fn raydium_snipe_launch(
         let handle = thread::spawn(move || {
             let runtime = tokio::runtime::Runtime::new().unwrap();
             runtime.block_on(async {
-                match read_single_key_impl(
-                    &rpc_client,
-                    &mut stop_tx,
-                    pool_keys_clone,
-                    get_config().await.unwrap(),
-                    &Arc::new(Keypair::from_base58_string(
-                        &get_config().await.unwrap().engine.payer_keypair,
-                    )),
-                )
-                .await
-                {
-                    Ok(_) => {}
-                    Err(e) => {
-                        error!("Error: {}", e);
-                    }
-                };
+                if let Err(e) = read_single_key_impl(&mut stop_tx, pool_keys_clone).await {
+                    error!("Error: {}", e);
+                }
             });
         });
 
@@ -708,28 +566,7 @@ async fn process_route(
 
             for (i, txns) in history.iter().enumerate() {
                 debug!("Processing transaction {}", i);
-                let info = txns.transaction.clone().unwrap_or_default();
-                let accounts = info
-                    .transaction
-                    .clone()
-                    .unwrap_or_default()
-                    .message
-                    .unwrap_or_default()
-                    .account_keys
-                    .iter()
-                    .map(|i| {
-                        let mut array = [0; 32];
-                        let bytes = &i[..array.len()];
-                        array.copy_from_slice(bytes);
-                        Pubkey::new_from_array(array)
-                    })
-                    .collect::<Vec<Pubkey>>();
-
-                let outer_instructions = {
-                    let transaction = info.transaction.unwrap_or_default();
-                    let message = transaction.message.unwrap_or_default();
-                    message.instructions.iter().cloned().collect::<Vec<_>>()
-                };
+                let outer_instructions = &txns.instructions;
 
                 debug!(
                     "Found {} instructions in transaction",
@@ -744,16 +581,16 @@ async fn process_route(
                         if instructions.accounts.len() >= SERUM_ACCOUNTS_LEN {
                             info!("Initializing market accounts");
                             market_accounts = Some(SerumMarketAccounts {
-                                market_account: accounts[instructions.accounts[0] as usize],
-                                req_q: accounts[instructions.accounts[1] as usize],
-                                event_q: accounts[instructions.accounts[2] as usize],
-                                bids: accounts[instructions.accounts[3] as usize],
-                                asks: accounts[instructions.accounts[4] as usize],
-                                coin_vault: accounts[instructions.accounts[5] as usize],
-                                pc_vault: accounts[instructions.accounts[6] as usize],
-                                coin_mint: accounts[instructions.accounts[7] as usize],
-                                pc_mint: accounts[instructions.accounts[8] as usize],
-                                rent_sysvar: accounts[instructions.accounts[9] as usize],
+                                market_account: instructions.accounts[0],
+                                req_q: instructions.accounts[1],
+                                event_q: instructions.accounts[2],
+                                bids: instructions.accounts[3],
+                                asks: instructions.accounts[4],
+                                coin_vault: instructions.accounts[5],
+                                pc_vault: instructions.accounts[6],
+                                coin_mint: instructions.accounts[7],
+                                pc_mint: instructions.accounts[8],
+                                rent_sysvar: instructions.accounts[9],
                             });
                         } else {
                             warn!(
@@ -764,14 +601,8 @@ async fn process_route(
                         TokenInstruction::unpack(&instructions.data)
                     {
                         debug!("Found InitializeAccount instruction at index {}", j);
-                        let instruction_accounts: Vec<Pubkey> = instructions
-                            .accounts
-                            .iter()
-                            .map(|account_index| accounts[*account_index as usize])
-                            .collect();
-
                         if let Some(init_account_accounts) =
-                            InitializeInitAccounts::unpack(&instruction_accounts)
+                            InitializeInitAccounts::unpack(&instructions.accounts)
                         {
                             // Check if the mint isn't equal to SOLC_MINT
                             if init_account_accounts.mint_pubkey != SOLC_MINT {
@@ -1,15 +1,8 @@
-use jito_protos::searcher::SubscribeBundleResultsRequest;
-use jito_searcher_client::{get_searcher_client, send_bundle_with_confirmation};
-use log::{error, info};
+use log::{error, info, warn};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_client::rpc_config::RpcSendTransactionConfig;
 use solana_client::rpc_request::TokenAccountsFilter;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
-use solana_sdk::compute_budget::ComputeBudgetInstruction;
-use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
-use solana_sdk::system_instruction::transfer;
-use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use solana_sdk::native_token::lamports_to_sol;
 use solana_sdk::{signature::Keypair, signer::Signer};
 use spl_associated_token_account::get_associated_token_address;
 use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
@@ -17,21 +10,29 @@ use spl_token::instruction::{close_account, transfer_checked};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc::{self};
+use tokio::sync::{
+    mpsc::{self},
+    watch,
+};
 
 use crate::app::config_init::get_config;
-use crate::env::SettingsConfig;
-use crate::liquidity::utils::tip_account;
+use crate::env::{is_headless, SettingsConfig};
+use crate::journal::{Order, TradeSide};
+use crate::positions::{self, venue::Venue};
+use crate::price_feed::{self, Price, PriceSource};
+use crate::sender::{SendReport, TransactionSender};
 use crate::raydium_amm::subscribe::PoolKeysSniper;
-use crate::raydium_amm::swap::instructions::{swap_base_in, SwapDirection, SOLC_MINT};
+use crate::raydium_amm::swap::instructions::{
+    quote_from_vaults, swap_base_in, SwapDirection, SOLC_MINT,
+};
 use crate::raydium_amm::swap::raydium_amm_sniper::clear_previous_line;
-use crate::raydium_amm::swap::swapper::auth_keypair;
 use crate::router::SniperRoute;
-use crate::rpc::HTTP_CLIENT;
-use crate::utils::read_single_key_impl;
+use crate::utils::{read_single_key_impl, slippage::min_out};
 
 use super::instructions::token_price_data;
-use super::raydium_swap_out::raydium_out;
+
+/// How often the streamed worth reads the balance again, e.g. after a sell.
+const BALANCE_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum TradeDirection {
@@ -45,21 +46,14 @@ pub async fn raydium_in(
     wallet: &Arc<Keypair>,
     pool_keys: PoolKeysSniper,
     amount_in: u64,
-    amount_out: u64,
     args: SettingsConfig,
     direction: TradeDirection,
-) -> eyre::Result<()> {
-    let config = get_config().await?;
-
+) -> eyre::Result<SendReport> {
     let user_source_owner = wallet.pubkey();
-
-    let mut searcher_client =
-        get_searcher_client(&args.network.block_engine_url, &Arc::new(auth_keypair())).await?;
-
-    let tip_account = tip_account();
-
-    let priority_fee = sol_to_lamports(config.trading.priority_fee);
-    let bundle_tip = sol_to_lamports(config.trading.bundle_tip);
+    let side = match direction {
+        TradeDirection::Buy => TradeSide::Buy,
+        TradeDirection::Sell => TradeSide::Sell,
+    };
 
     let (token_address, decimals) = if pool_keys.base_mint == SOLC_MINT {
         (pool_keys.quote_mint, pool_keys.quote_decimals)
@@ -67,11 +61,20 @@ pub async fn raydium_in(
         (pool_keys.base_mint, pool_keys.base_decimals)
     };
 
+    let input_mint = match direction {
+        TradeDirection::Buy => SOLC_MINT,
+        TradeDirection::Sell => token_address,
+    };
+    let quote = quote_from_vaults(
+        rpc_client,
+        [pool_keys.base_vault, pool_keys.quote_vault],
+        &input_mint,
+        amount_in,
+    )
+    .await?;
+    let amount_out = min_out(quote, args.slippage_bps(&SniperRoute::RaydiumAMM));
+
     let mut swap_instructions = Vec::new();
-    let unit_limit = ComputeBudgetInstruction::set_compute_unit_limit(800000);
-    let compute_price = ComputeBudgetInstruction::set_compute_unit_price(priority_fee);
-    swap_instructions.push(unit_limit);
-    swap_instructions.push(compute_price);
 
     swap_instructions.extend(
         swap_base_in(
@@ -111,9 +114,8 @@ pub async fn raydium_in(
     {
         Ok(price) => price,
         Err(e) => {
-            error!("Error getting token price: {:?}", e);
-
-return Ok(())        }
+            return Err(eyre::eyre!("Error getting token price: {:?}", e));
+        }
     };
 
     println!("{price:#?}");
@@ -148,153 +150,33 @@ return Ok(())        }
     swap_instructions.push(transfer_ix);
     swap_instructions.push(close);
 
-    let config = CommitmentLevel::Processed;
-    let (latest_blockhash, _) = rpc_client
-        .get_latest_blockhash_with_commitment(solana_sdk::commitment_config::CommitmentConfig {
-            commitment: config,
-        })
+    let report = TransactionSender::from_config(&args, rpc_client.clone())
+        .with_order(Order::new(SniperRoute::RaydiumAMM, side, token_address, amount_in))
+        .send(wallet, &swap_instructions, &[])
         .await?;
-
-    let message = match solana_program::message::v0::Message::try_compile(
-        &user_source_owner,
-        &swap_instructions,
-        &[],
-        latest_blockhash,
-    ) {
-        Ok(x) => x,
-        Err(e) => {
-            println!("Error: {:?}", e);
-            return Ok(());
-        }
-    };
-
-    let transaction = match VersionedTransaction::try_new(
-        solana_program::message::VersionedMessage::V0(message),
-        &[&wallet],
-    ) {
-        Ok(x) => x,
-        Err(e) => {
-            println!("Error: {:?}", e);
-            return Ok(());
-        }
-    };
-
-    if args.engine.use_bundles {
-        let tip_ix = transfer(&wallet.pubkey(), &tip_account, bundle_tip);
-        swap_instructions.push(tip_ix);
-
-        let message = match solana_program::message::v0::Message::try_compile(
-            &user_source_owner,
-            &swap_instructions,
-            &[],
-            latest_blockhash,
-        ) {
-            Ok(x) => x,
-            Err(e) => {
-                println!("Error: {:?}", e);
-                return Ok(());
-            }
-        };
-        let transaction = match VersionedTransaction::try_new(
-            solana_program::message::VersionedMessage::V0(message),
-            &[&wallet],
-        ) {
-            Ok(x) => x,
-            Err(e) => {
-                println!("Error: {:?}", e);
-                return Ok(());
-            }
-        };
-
-        let bundle_txn = vec![transaction];
-
-        let mut bundle_results_subscription = searcher_client
-            .subscribe_bundle_results(SubscribeBundleResultsRequest {})
-            .await
-            .expect("subscribe to bundle results")
-            .into_inner();
-
-        match send_bundle_with_confirmation(
-            &bundle_txn,
-            &rpc_client,
-            &mut searcher_client,
-            &mut bundle_results_subscription,
-        )
-        .await
-        {
-            Ok(_) => {}
-            Err(e) => {
-                error!("Error: {}", e);
-            }
-        };
-
-        std::mem::drop(bundle_results_subscription);
-    } else {
-        info!("Sending Transaction");
-        let config = RpcSendTransactionConfig {
-            skip_preflight: true,
-            ..Default::default()
-        };
-
-        if args.trading.spam {
-            let mut counter = 0;
-            while counter < args.trading.spam_count {
-                let result = match rpc_client
-                    .send_transaction_with_config(&transaction, config)
-                    .await
-                {
-                    Ok(x) => x,
-                    Err(e) => {
-                        error!("Error: {:?}", e);
-                        return Ok(());
-                    }
-                };
-
-                info!("Transaction Sent {:?}", result);
-                counter += 1;
-            }
-        } else {
-            let result = match rpc_client
-                .send_transaction_with_config(&transaction, config)
-                .await
-            {
-                Ok(x) => x,
-                Err(e) => {
-                    error!("Error: {:?}", e);
-                    return Ok(());
-                }
-            };
-
-            rpc_client
-                .confirm_transaction_with_spinner(
-                    &result,
-                    &latest_blockhash,
-                    CommitmentConfig::confirmed(),
-                )
-                .await?;
-
-            info!("Transaction Sent {:?}", result);
-        }
+    report.log();
+    if !report.landed() {
+        return Ok(report);
+    }
+    if side == TradeSide::Buy {
+        positions::open(
+            Venue::RaydiumAMM(Box::new(pool_keys.clone())),
+            token_address,
+            amount_in,
+            &report,
+        );
+    }
+    // Without a terminal there are no hotkeys to read or prices to show.
+    if is_headless() {
+        return Ok(report);
     }
 
     let (mut stop_tx, mut stop_rx) = tokio::sync::mpsc::channel::<()>(100);
     let pool_keys_clone = pool_keys.clone();
     tokio::spawn(async move {
-        let config = get_config().await.unwrap();
-        match read_single_key_impl(
-            &Arc::new(RpcClient::new(config.clone().network.rpc_url)),
-            &mut stop_tx,
-            pool_keys_clone,
-            config.clone(),
-            &Arc::new(Keypair::from_base58_string(&config.engine.payer_keypair)),
-        )
-        .await
-        {
-            Ok(_) => {}
-            Err(e) => {
-                error!("Error: {}", e);
-            }
-        };
+        if let Err(e) = read_single_key_impl(&mut stop_tx, pool_keys_clone).await {
+            error!("Error: {}", e);
+        }
     });
     price_logger(
         &mut stop_rx,
@@ -305,7 +187,7 @@ return Ok(())        }
     )
     .await;
 
-    Ok(())
+    Ok(report)
 }
 
 pub async fn price_logger(
@@ -316,22 +198,51 @@ pub async fn price_logger(
     snipe_route: SniperRoute,
 ) {
     let config = get_config().await.unwrap();
-    let rpc_client = Arc::new(RpcClient::new(config.network.rpc_url));
+    let rpc_client = Arc::new(RpcClient::new(config.network.rpc_url.clone()));
     let wallet = Arc::new(Keypair::from_base58_string(&config.engine.payer_keypair));
     let start_time = Instant::now();
     let rapid_check_duration = Duration::from_secs(5);
 
+    // Stop with the position manager once it has sold everything.
+    let tracked_mint = mint.or(pool_keys.as_ref().map(|keys| {
+        if keys.base_mint == SOLC_MINT {
+            keys.quote_mint
+        } else {
+            keys.base_mint
+        }
+    }));
+    let managed = tracked_mint.is_some_and(|mint| positions::is_open(&mint));
+
+    if let (false, Some(keys)) = (config.network.grpc_url.is_empty(), &pool_keys) {
+        let source = PriceSource::RaydiumAMM(Box::new(keys.clone()));
+        let holding = get_associated_token_address(&wallet.pubkey(), &source.mint());
+        match price_feed::track(&config, source.clone(), holding).await {
+            Ok(prices) => {
+                stream_worth(stop_rx, prices, &rpc_client, &wallet, &source, amount_in, managed)
+                    .await;
+                if !managed {
+                    price_feed::untrack(&config, &source.mint());
+                }
+                return;
+            }
+            Err(e) => warn!("Cannot stream the price, polling instead: {}", e),
+        }
+    }
+
     loop {
         if stop_rx.try_recv().is_ok() {
             break;
         }
+        if managed && tracked_mint.is_some_and(|mint| !positions::is_open(&mint)) {
+            break;
+        }
 
         let rpc_client_clone = rpc_client.clone();
         let pool_keys_clone = pool_keys.clone().unwrap();
         let token_accounts = match rpc_client_clone
             .get_token_accounts_by_owner(
                 &wallet.pubkey(),
-                TokenAccountsFilter::Mint(mint.unwrap_or(pool_keys_clone.base_mint)),
+                TokenAccountsFilter::Mint(tracked_mint.unwrap_or(pool_keys_clone.base_mint)),
             )
             .await
         {
@@ -390,27 +301,11 @@ pub async fn price_logger(
                 }
             };
 
-            let total_value = lamports_to_sol(price as u64);
-            let profit_percentage =
-                ((total_value - lamports_to_sol(amount_in)) / lamports_to_sol(amount_in)) * 100.0;
-
-            clear_previous_line();
-            info!(
-                
-                "1 to Sell 100%, 2 for 75%, 3 for 50%, 4 for 25%\nAped: {:.3} Sol | Worth {:.4} Sol | Profit {:.2}%",
-                lamports_to_sol(amount_in),
-                total_value,
-                profit_percentage
-            );
-
-            // if profit_percentage >= config.trading.profit_threshold_percentage
-            //     || profit_percentage <= config.trading.loss_threshold_percentage
-            // {
-            //     if let Err(e) = sell_tokens(pool_keys.clone().unwrap()).await {
-            //         error!("Error selling tokens: {}", e);
-            //     }
-            //     break;
-            // }
+            // After a partial sell only the rest of the cost is compared
+            let cost = tracked_mint
+                .and_then(|mint| positions::cost_basis(&mint, token_balance))
+                .unwrap_or(amount_in);
+            log_worth(cost, price as u64);
         }
 
         // Implement delay based on elapsed time
@@ -420,55 +315,68 @@ pub async fn price_logger(
     }
 }
 
-pub async fn sell_tokens(pool_keys: PoolKeysSniper) -> eyre::Result<()> {
-    let config = get_config().await?;
-
-    let wallet = Keypair::from_base58_string(&config.engine.payer_keypair);
-    let rpc_client = {
-        let http_client = HTTP_CLIENT.lock().unwrap();
-        http_client.get("http_client").unwrap().clone()
-    };
+/// Logs the worth of the wallet's tokens on every price the feed publishes.
+/// The balance streams with the price; while the stream is down it is read
+/// over RPC at most every `BALANCE_INTERVAL`.
+async fn stream_worth(
+    stop_rx: &mut mpsc::Receiver<()>,
+    mut prices: watch::Receiver<Option<Price>>,
+    rpc_client: &RpcClient,
+    wallet: &Keypair,
+    source: &PriceSource,
+    amount_in: u64,
+    managed: bool,
+) {
+    let mint = source.mint();
+    let token_account = get_associated_token_address(&wallet.pubkey(), &mint);
     let mut token_balance = 0;
-    let rpc_client_clone = rpc_client.clone();
-    let pool_keys_clone = pool_keys.clone();
-    let token_accounts = rpc_client_clone
-        .get_token_accounts_by_owner(
-            &wallet.pubkey(),
-            TokenAccountsFilter::Mint(pool_keys_clone.base_mint),
-        )
-        .await?;
-
-    for rpc_keyed_account in &token_accounts {
-        let pubkey = &rpc_keyed_account.pubkey;
-        //convert to pubkey
-        let pubkey = Pubkey::from_str(&pubkey)?;
+    let mut balance_read: Option<Instant> = None;
 
-        let balance = rpc_client_clone.get_token_account_balance(&pubkey).await?;
-        let lamports = match balance.amount.parse::<u64>() {
-            Ok(lamports) => lamports,
-            Err(e) => {
-                eprintln!("Failed to parse balance: {}", e);
-                break;
+    loop {
+        tokio::select! {
+            Some(()) = stop_rx.recv() => break,
+            changed = prices.changed() => {
+                if changed.is_err() {
+                    break;
+                }
             }
-        };
-
-        token_balance = lamports;
-
-        if lamports != 0 {
+            _ = tokio::time::sleep(BALANCE_INTERVAL) => {}
+        }
+        if managed && !positions::is_open(&mint) {
             break;
         }
 
-        std::thread::sleep(Duration::from_secs(1));
-    }
-
-    info!("Token Balance: {:?}", token_balance);
+        match price_feed::balance(&mint) {
+            Some(balance) => token_balance = balance,
+            None if balance_read.is_none_or(|at| at.elapsed() >= BALANCE_INTERVAL) => {
+                match rpc_client.get_token_account_balance(&token_account).await {
+                    Ok(balance) => token_balance = balance.amount.parse().unwrap_or(0),
+                    Err(e) => error!("Failed to get token account balance: {}", e),
+                }
+                balance_read = Some(Instant::now());
+            }
+            None => {}
+        }
 
-    let _ = match raydium_out(pool_keys, token_balance, 0).await {
-        Ok(_) => {}
-        Err(e) => {
-            error!("{}", e);
+        let Some(price) = *prices.borrow_and_update() else {
+            continue;
+        };
+        if token_balance > 0 {
+            let cost = positions::cost_basis(&mint, token_balance).unwrap_or(amount_in);
+            log_worth(cost, price.sell_quote(token_balance));
         }
-    };
+    }
+}
+
+fn log_worth(cost: u64, worth: u64) {
+    let total_value = lamports_to_sol(worth);
+    let profit_percentage = ((total_value - lamports_to_sol(cost)) / lamports_to_sol(cost)) * 100.0;
 
-    Ok(())
+    clear_previous_line();
+    info!(
+        "1 to Sell 100%, 2 for 75%, 3 for 50%, 4 for 25%\nAped: {:.3} Sol | Worth {:.4} Sol | Profit {:.2}%",
+        lamports_to_sol(cost),
+        total_value,
+        profit_percentage
+    );
 }
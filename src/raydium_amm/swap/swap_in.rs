@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use log::{error, info};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{bs58, signature::Keypair};
+use solana_sdk::{bs58, pubkey::Pubkey, signature::Keypair};
 
 use crate::{
     env::load_config,
@@ -15,6 +15,17 @@ use crate::{
 use super::{raydium_swap_in::raydium_in, raydium_swap_out::raydium_txn_backrun};
 
 pub async fn swap_in() -> Result<(), Box<dyn std::error::Error>> {
+    let sol_amount = amount_input("Swap Amount:").await;
+
+    let token_out = mint_input("Pool Address").await;
+
+    raydium_buy(token_out, sol_amount).await
+}
+
+pub async fn raydium_buy(
+    token_out: Pubkey,
+    sol_amount: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
     let args = match load_config().await {
         Ok(args) => args,
         Err(e) => {
@@ -25,10 +36,6 @@ pub async fn swap_in() -> Result<(), Box<dyn std::error::Error>> {
 
     let rpc_client = &Arc::new(RpcClient::new(args.network.rpc_url.clone()));
 
-    let sol_amount = amount_input("Swap Amount:").await;
-
-    let token_out = mint_input("Pool Address").await;
-
     let private_key =
         Keypair::from_bytes(&bs58::decode(&args.engine.payer_keypair).into_vec().unwrap())?;
 
@@ -42,7 +49,6 @@ pub async fn swap_in() -> Result<(), Box<dyn std::error::Error>> {
         &Arc::new(private_key),
         pool_keys,
         sol_amount,
-        0,
         args,
         TradeDirection::Buy,
     )
@@ -62,6 +68,17 @@ pub struct PriorityTip {
 }
 
 pub async fn swap_out() -> Result<(), Box<dyn std::error::Error>> {
+    let token_out = mint_input("Pool Address").await;
+
+    let percentage = percentage_input().await;
+
+    raydium_sell(token_out, percentage as u64).await
+}
+
+pub async fn raydium_sell(
+    token_out: Pubkey,
+    percentage: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
     let args = match load_config().await {
         Ok(args) => args,
         Err(e) => {
@@ -72,20 +89,16 @@ pub async fn swap_out() -> Result<(), Box<dyn std::error::Error>> {
 
     let rpc_client = &Arc::new(RpcClient::new(args.network.rpc_url.clone()));
 
-    let token_out = mint_input("Pool Address").await;
-
     let private_key =
         Keypair::from_bytes(&bs58::decode(&args.engine.payer_keypair).into_vec().unwrap())?;
 
     let pool_keys = pool_keys_fetcher(token_out).await?;
 
-    let percentage = percentage_input().await;
-
     let _swap = match raydium_txn_backrun(
         rpc_client,
         &Arc::new(private_key),
         pool_keys,
-        percentage as u64,
+        percentage,
     )
     .await
     {
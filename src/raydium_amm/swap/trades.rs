@@ -1,11 +1,6 @@
-use std::sync::Arc;
-
 use log::{error, info};
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::signature::Keypair;
 
 use crate::{
-    app::config_init::get_config,
     raydium_amm::{
         pool_searcher::amm_keys::pool_keys_fetcher, swap::raydium_swap_in::price_logger,
     },
@@ -25,21 +20,9 @@ pub async fn track_trades() -> eyre::Result<()> {
 
     let pool_keys_clone = pool_keys.clone();
     tokio::spawn(async move {
-        let config = get_config().await.unwrap();
-        match read_single_key_impl(
-            &Arc::new(RpcClient::new(config.clone().network.rpc_url)),
-            &mut stop_tx,
-            pool_keys_clone,
-            config.clone(),
-            &Arc::new(Keypair::from_base58_string(&config.engine.payer_keypair)),
-        )
-        .await
-        {
-            Ok(_) => {}
-            Err(e) => {
-                error!("Error: {}", e);
-            }
-        };
+        if let Err(e) = read_single_key_impl(&mut stop_tx, pool_keys_clone).await {
+            error!("Error: {}", e);
+        }
     });
 
     price_logger(
@@ -0,0 +1,213 @@
+use std::{sync::Arc, time::Duration};
+
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use futures::{stream::select_all, StreamExt};
+use log::{info, warn};
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::{RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+use tokio::time::sleep;
+
+use crate::{
+    env::SettingsConfig,
+    geyser::ParsedTransaction,
+    latency::{Stage, Timeline},
+    pumpfun::events,
+};
+
+use super::{dispatch, SnipeTarget, SniperRoute};
+
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// `getTransaction` can lag a moment behind the log notification.
+const FETCH_ATTEMPTS: u32 = 5;
+
+const FETCH_RETRY_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Snipes every target from `logsSubscribe` notifications on
+/// `network.pubsub_url`, fetching each launch with `getTransaction`. Slower
+/// than Geyser since logs are only fetchable once confirmed.
+pub async fn logs_multi_sub(
+    args: SettingsConfig,
+    manual_snipe: bool,
+    base_mint: Option<Pubkey>,
+    targets: Vec<SnipeTarget>,
+) -> anyhow::Result<()> {
+    if args.network.pubsub_url.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Set network.grpc_url or network.pubsub_url to snipe launches"
+        ));
+    }
+
+    let rpc_client = Arc::new(RpcClient::new(args.network.rpc_url.clone()));
+    let mut retry = ExponentialBackoff {
+        max_interval: MAX_RETRY_INTERVAL,
+        max_elapsed_time: None,
+        ..Default::default()
+    };
+
+    loop {
+        let error = match stream_logs(
+            &args,
+            &rpc_client,
+            manual_snipe,
+            base_mint,
+            &targets,
+            &mut retry,
+        )
+        .await
+        {
+            Ok(()) => "subscription ended".to_string(),
+            Err(e) => e.to_string(),
+        };
+
+        let retry_in = retry.next_backoff().unwrap_or(MAX_RETRY_INTERVAL);
+        warn!(
+            "Log stream lost ({}), reconnecting in {:.1}s",
+            error,
+            retry_in.as_secs_f64()
+        );
+        sleep(retry_in).await;
+    }
+}
+
+/// Streams until the websocket drops.
+async fn stream_logs(
+    args: &SettingsConfig,
+    rpc_client: &Arc<RpcClient>,
+    manual_snipe: bool,
+    base_mint: Option<Pubkey>,
+    targets: &[SnipeTarget],
+    retry: &mut ExponentialBackoff,
+) -> eyre::Result<()> {
+    let pubsub_client = PubsubClient::new(&args.network.pubsub_url).await?;
+
+    let mut subscriptions = Vec::with_capacity(targets.len());
+    for target in targets {
+        let (notifications, _unsubscribe) = pubsub_client
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![target.contract.clone()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .await?;
+        subscriptions.push(notifications.map(move |response| (target, response)));
+    }
+    info!("Listening for launches over {}", args.network.pubsub_url);
+
+    let mut notifications = select_all(subscriptions);
+    let mut healthy = false;
+    while let Some((target, response)) = notifications.next().await {
+        if !healthy {
+            healthy = true;
+            retry.reset();
+        }
+        let logs = response.value;
+        if logs.err.is_some() {
+            continue;
+        }
+        if target.route == SniperRoute::PumpFun {
+            for event in events::events_from_logs(&logs.logs) {
+                events::apply(&event, response.context.slot);
+            }
+        }
+        if !is_launch(&target.route, &logs.logs) {
+            continue;
+        }
+        let signature = match logs.signature.parse::<Signature>() {
+            Ok(signature) => signature,
+            Err(e) => {
+                warn!(
+                    "Bad signature {} in log notification: {}",
+                    logs.signature, e
+                );
+                continue;
+            }
+        };
+
+        let mut timeline = Timeline::start(response.context.slot);
+        let rpc_client = rpc_client.clone();
+        let args = args.clone();
+        let target = target.clone();
+        tokio::spawn(async move {
+            match fetch_parsed(&rpc_client, &signature).await {
+                Ok(tx) => {
+                    timeline.mark(Stage::Parsed);
+                    timeline
+                        .scope(dispatch(
+                            rpc_client,
+                            args,
+                            target,
+                            Arc::new(tx),
+                            manual_snipe,
+                            base_mint,
+                        ))
+                        .await
+                }
+                Err(e) => warn!("Cannot fetch {}: {}", signature, e),
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Most notifications are trades, only launches are worth a `getTransaction`.
+fn is_launch(route: &SniperRoute, logs: &[String]) -> bool {
+    let needle = match route {
+        SniperRoute::PumpFun => "Program log: Instruction: Create",
+        SniperRoute::MoonShot => "Program log: Instruction: TokenMint",
+        SniperRoute::RaydiumAMM | SniperRoute::PumpFunMigration => "initialize2",
+        _ => return true,
+    };
+    logs.iter().any(|log| log.contains(needle))
+}
+
+async fn fetch_parsed(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+) -> eyre::Result<ParsedTransaction> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    let mut attempt = 1;
+    loop {
+        match rpc_client
+            .get_transaction_with_config(signature, config)
+            .await
+        {
+            Ok(transaction) => return ParsedTransaction::try_from(transaction),
+            Err(e) if attempt >= FETCH_ATTEMPTS => return Err(e.into()),
+            Err(_) => {
+                attempt += 1;
+                sleep(FETCH_RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_launch_logs_are_fetched() {
+        let create = vec![
+            "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P invoke [1]".to_string(),
+            "Program log: Instruction: Create".to_string(),
+        ];
+        let buy = vec!["Program log: Instruction: Buy".to_string()];
+
+        assert!(is_launch(&SniperRoute::PumpFun, &create));
+        assert!(!is_launch(&SniperRoute::PumpFun, &buy));
+        assert!(!is_launch(&SniperRoute::RaydiumAMM, &create));
+        assert!(is_launch(&SniperRoute::DaosFun, &buy));
+    }
+}
@@ -1,34 +1,37 @@
-use std::{
-    collections::HashMap,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
-    thread,
-    time::Duration,
-};
+pub mod logs;
+pub mod replay;
+
+use std::{collections::HashMap, sync::Arc, time::Instant};
 
-use colorize::AnsiColor;
-use futures::{SinkExt, StreamExt};
-use log::{error, info};
+use log::{error, info, warn};
 use maplit::hashmap;
+use serde::{Deserialize, Serialize};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+};
+use tokio::task::JoinHandle;
 use yellowstone_grpc_proto::geyser::{
-    subscribe_update::UpdateOneof, SubscribeRequest, SubscribeRequestFilterBlocksMeta,
-    SubscribeRequestFilterTransactions,
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterTransactions, SubscribeUpdate,
 };
 
 use crate::{
-    daos_fun::daosfun_snipe::daosfun_parser,
-    env::SettingsConfig,
-    moonshot::sniper::moonshot_parser,
-    plugins::yellowstone_plugin::lib::GeyserGrpcClient,
-    pumpfun::{migration_sniper::pumpfun_migration_snipe_parser, sniper::pumpfun_parser},
-    raydium_amm::swap::raydium_amm_sniper::{clear_previous_line, raydium_sniper_parser},
+    daos_fun::{daos_transaction::DAOS_BURNED_PROGRAM, daosfun_snipe::daosfun_parser},
+    env::{RouteSettings, SettingsConfig},
+    geyser::{GeyserSubscription, ParsedTransaction, Recorder},
+    latency::{self, Stage, Timeline},
+    moonshot::sniper::MOONSHOT_CONTRACT,
+    pumpfun::{
+        events,
+        migration_sniper::pumpfun_migration_snipe_parser,
+        sniper::{pumpfun_parser, PUMPFUN_CONTRACT, PUMPFUN_MIGRATION},
+    },
+    raydium_amm::swap::raydium_amm_sniper::{raydium_sniper_parser, RAYDIUM_AMM_FEE_COLLECTOR},
 };
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub enum SniperRoute {
     RaydiumAMM,
     RaydiumCPMM,
@@ -39,159 +42,403 @@ pub enum SniperRoute {
     DaosFun,
 }
 
-pub async fn grpc_pair_sub(
+impl SniperRoute {
+    /// Account whose transactions show launches on the route.
+    pub fn launch_account(&self) -> Option<String> {
+        match self {
+            SniperRoute::RaydiumAMM => Some(RAYDIUM_AMM_FEE_COLLECTOR.to_string()),
+            SniperRoute::PumpFun => Some(PUMPFUN_CONTRACT.to_string()),
+            SniperRoute::PumpFunMigration => Some(PUMPFUN_MIGRATION.to_string()),
+            SniperRoute::DaosFun => Some(DAOS_BURNED_PROGRAM.to_string()),
+            SniperRoute::MoonShot => Some(MOONSHOT_CONTRACT.to_string()),
+            SniperRoute::RaydiumCPMM | SniperRoute::Jupiter => None,
+        }
+    }
+}
+
+/// A route watched by [`multi_sub`].
+#[derive(Debug, Clone)]
+pub struct SnipeTarget {
+    pub route: SniperRoute,
+    /// Account whose transactions are streamed for the route.
+    pub contract: String,
+    /// Lamports spent per snipe.
+    pub buy_amount: u64,
+}
+
+impl SnipeTarget {
+    /// Name of the route's transaction filter, which updates come back tagged
+    /// with.
+    fn filter_name(&self) -> String {
+        format!("{:?}", self.route)
+    }
+}
+
+/// The routes enabled under `[sniper]`, each with its own buy amount.
+pub fn enabled_targets(args: &SettingsConfig) -> Vec<SnipeTarget> {
+    args.sniper
+        .routes()
+        .into_iter()
+        .filter(|(_, _, settings)| settings.enabled)
+        .filter_map(|(_, route, settings)| target(args, route, settings))
+        .collect()
+}
+
+/// Every route with a launch account, enabled or not.
+pub fn all_targets(args: &SettingsConfig) -> Vec<SnipeTarget> {
+    args.sniper
+        .routes()
+        .into_iter()
+        .filter_map(|(_, route, settings)| target(args, route, settings))
+        .collect()
+}
+
+fn target(
+    args: &SettingsConfig,
+    route: SniperRoute,
+    settings: &RouteSettings,
+) -> Option<SnipeTarget> {
+    let buy_amount = if settings.buy_amount > 0.0 {
+        settings.buy_amount
+    } else {
+        args.trading.buy_amount
+    };
+    Some(SnipeTarget {
+        contract: route.launch_account()?,
+        route,
+        buy_amount: sol_to_lamports(buy_amount),
+    })
+}
+
+/// Snipes a single route, spending `trading.buy_amount` per snipe.
+pub async fn pair_sub(
     args: SettingsConfig,
     manual_snipe: bool,
     base_mint: Option<Pubkey>,
     contract: String,
     route: SniperRoute,
 ) -> anyhow::Result<()> {
-    // Shared atomic boolean flag to stop the animation
-    let stop_animation = Arc::new(AtomicBool::new(false));
-    let stop_animation_clone = Arc::clone(&stop_animation);
-
-    // Start the dot animation in a separate thread
-    let handle = thread::spawn(move || {
-        let mut dots = String::new();
-        let mut count = 0;
-        while !stop_animation_clone.load(Ordering::Relaxed) {
-            if count == 3 {
-                dots.clear();
-                count = 0;
-            }
-            dots.push('.');
-            count += 1;
-            clear_previous_line();
+    let target = SnipeTarget {
+        route,
+        contract,
+        buy_amount: sol_to_lamports(args.trading.buy_amount),
+    };
+    multi_sub(args, manual_snipe, base_mint, vec![target]).await
+}
 
-            info!("Connecting to the Port{}", dots.clone().red());
-            thread::sleep(Duration::from_millis(500));
-        }
-    });
+/// Snipes every target over Geyser, or over `logsSubscribe` when no Geyser
+/// endpoint is configured.
+pub async fn multi_sub(
+    args: SettingsConfig,
+    manual_snipe: bool,
+    base_mint: Option<Pubkey>,
+    targets: Vec<SnipeTarget>,
+) -> anyhow::Result<()> {
+    if args.network.grpc_url.is_empty() {
+        logs::logs_multi_sub(args, manual_snipe, base_mint, targets).await
+    } else {
+        grpc_multi_sub(args, manual_snipe, base_mint, targets).await
+    }
+}
 
-    let endpoint = args.network.grpc_url.clone();
+/// Snipes every target over one Geyser stream, with a transaction filter per
+/// route. A transaction matching several filters reaches each of their
+/// parsers.
+pub async fn grpc_multi_sub(
+    args: SettingsConfig,
+    manual_snipe: bool,
+    base_mint: Option<Pubkey>,
+    targets: Vec<SnipeTarget>,
+) -> anyhow::Result<()> {
     let rpc_client = Arc::new(RpcClient::new(args.clone().network.rpc_url));
 
-    let x_token = Some("00000000-0000-0000-0000-000000000000");
-
-    let mut client = GeyserGrpcClient::connect(endpoint, x_token, None)?;
-    let (mut subscribe_tx, mut stream) = client.subscribe().await?;
-
-    clear_previous_line();
-    info!("Successfully connected to Geyser");
-
-    // Stop the animation
-    stop_animation.store(true, Ordering::Relaxed);
-
-    // Wait for the animation thread to finish
-    handle.join().unwrap();
-
-    let commitment = 0;
-    subscribe_tx
-        .send(SubscribeRequest {
-            slots: HashMap::new(),
-            accounts: HashMap::new(),
-            transactions: hashmap! { "".to_owned() => SubscribeRequestFilterTransactions {
-                vote: Default::default(),
-                failed: Default::default(),
-                signature: Default::default(),
-                account_include: [contract].into(),
-                account_exclude: Default::default(),
-                account_required: Default::default(),
-            } },
-            entry: HashMap::new(),
-            blocks: HashMap::new(),
-            blocks_meta: hashmap! { "".to_owned() => SubscribeRequestFilterBlocksMeta {} },
-            commitment: Some(commitment as i32),
-            accounts_data_slice: vec![],
-            ping: None,
+    let transactions = targets
+        .iter()
+        .map(|target| {
+            (
+                target.filter_name(),
+                SubscribeRequestFilterTransactions {
+                    vote: Default::default(),
+                    failed: Default::default(),
+                    signature: Default::default(),
+                    account_include: vec![target.contract.clone()],
+                    account_exclude: Default::default(),
+                    account_required: Default::default(),
+                },
+            )
         })
-        .await?;
+        .collect();
+    let request = SubscribeRequest {
+        slots: HashMap::new(),
+        accounts: HashMap::new(),
+        transactions,
+        entry: HashMap::new(),
+        blocks: HashMap::new(),
+        blocks_meta: hashmap! { "".to_owned() => SubscribeRequestFilterBlocksMeta {} },
+        commitment: Some(CommitmentLevel::Processed as i32),
+        accounts_data_slice: vec![],
+        ping: None,
+    };
 
-    let subscribe_tx = Arc::new(tokio::sync::Mutex::new(subscribe_tx));
+    for target in &targets {
+        info!(
+            "Sniping {:?} with {} SOL",
+            target.route,
+            lamports_to_sol(target.buy_amount)
+        );
+    }
+    let mut stream = GeyserSubscription::from_config(&args, request).start()?;
+    let mut recorder = Recorder::from_config(&args)
+        .map_err(|e| anyhow::anyhow!("{}", e))?
+        .map(Recorder::spawn);
 
     while let Some(message) = stream.next().await {
-        let rpc_client = rpc_client.clone();
-        let subscribe_tx = Arc::clone(&subscribe_tx);
-        let args = args.clone();
-        let route = route.clone();
-        tokio::spawn(async move {
-            let subscribe_tx = subscribe_tx.lock().await;
-            match message {
-                Ok(msg) => match msg.update_oneof {
-                    Some(UpdateOneof::Transaction(tx)) => {
-                        if route == SniperRoute::RaydiumAMM {
-                            let tx = tx.clone();
-                            let _ = match raydium_sniper_parser(
-                                rpc_client.clone(),
-                                tx,
-                                manual_snipe,
-                                base_mint,
-                                route,
-                                subscribe_tx,
-                            )
-                            .await
-                            {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    error!("Error: {:?}", e);
-                                }
-                            };
-                        } else if route == SniperRoute::PumpFunMigration {
-                            let tx = tx.clone();
-                            let _ = match pumpfun_migration_snipe_parser(
-                                rpc_client.clone(),
-                                tx,
-                                manual_snipe,
-                                base_mint,
-                                route,
-                                subscribe_tx,
-                            )
-                            .await
-                            {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    error!("Error: {:?}", e);
-                                }
-                            };
-                        } else if route == SniperRoute::PumpFun {
-                            let _ = match pumpfun_parser(args, tx, base_mint).await {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    error!("Error: {:?}", e);
-                                }
-                            };
-                        } else if route == SniperRoute::DaosFun {
-                            let _ = match daosfun_parser(args, tx, base_mint).await {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    error!("Error: {:?}", e);
-                                }
-                            };
-                        } else if route == SniperRoute::MoonShot {
-                            let _ = match moonshot_parser(
-                                rpc_client.clone(),
-                                tx,
-                                manual_snipe,
-                                subscribe_tx,
-                            )
-                            .await
-                            {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    error!("Error: {:?}", e);
-                                }
-                            };
-                        }
-                    }
-                    _ => {}
-                },
-                Err(error) => {
-                    error!("stream error: {error:?}");
-                }
+        let received = Instant::now();
+        // Parsers get the update first, it is only recorded after.
+        let recorded = recorder.as_ref().map(|_| message.clone());
+        route_update(
+            &rpc_client,
+            &args,
+            &targets,
+            manual_snipe,
+            base_mint,
+            message,
+        );
+        if let (Some(sink), Some(update)) = (&recorder, recorded) {
+            if !sink.record(received, update) {
+                recorder = None;
             }
-            Ok::<(), ()>(())
-        });
+        }
     }
 
     Ok(())
 }
+
+/// Hands a transaction update to the parser of every target whose filter it
+/// matched, and returns the spawned parsers. Each parser times its trade from
+/// the moment the update got here.
+fn route_update(
+    rpc_client: &Arc<RpcClient>,
+    args: &SettingsConfig,
+    targets: &[SnipeTarget],
+    manual_snipe: bool,
+    base_mint: Option<Pubkey>,
+    message: SubscribeUpdate,
+) -> Vec<JoinHandle<()>> {
+    latency::observe(&message);
+    let Some(UpdateOneof::Transaction(tx)) = message.update_oneof else {
+        return Vec::new();
+    };
+    let mut timeline = Timeline::start(tx.slot);
+    let tx = match ParsedTransaction::try_from(tx) {
+        Ok(tx) => Arc::new(tx),
+        Err(e) => {
+            warn!("Skipping a transaction that does not decode: {}", e);
+            return Vec::new();
+        }
+    };
+    timeline.mark(Stage::Parsed);
+    events::observe(&tx);
+
+    targets
+        .iter()
+        .filter(|target| message.filters.contains(&target.filter_name()))
+        .map(|target| {
+            tokio::spawn(timeline.clone().scope(dispatch(
+                rpc_client.clone(),
+                args.clone(),
+                target.clone(),
+                tx.clone(),
+                manual_snipe,
+                base_mint,
+            )))
+        })
+        .collect()
+}
+
+async fn dispatch(
+    rpc_client: Arc<RpcClient>,
+    args: SettingsConfig,
+    target: SnipeTarget,
+    tx: Arc<ParsedTransaction>,
+    manual_snipe: bool,
+    base_mint: Option<Pubkey>,
+) {
+    let amount = target.buy_amount;
+    let route = target.route.clone();
+    let result = match target.route {
+        SniperRoute::RaydiumAMM => {
+            raydium_sniper_parser(
+                rpc_client,
+                &tx,
+                manual_snipe,
+                base_mint,
+                target.route,
+                amount,
+            )
+            .await
+        }
+        SniperRoute::PumpFunMigration => {
+            pumpfun_migration_snipe_parser(
+                rpc_client,
+                &tx,
+                manual_snipe,
+                base_mint,
+                target.route,
+                amount,
+            )
+            .await
+        }
+        SniperRoute::PumpFun => pumpfun_parser(args, &tx, base_mint, amount).await,
+        SniperRoute::DaosFun => daosfun_parser(args, &tx, base_mint, amount).await,
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        error!("{:?}: {:?}", route, e);
+    }
+}
+
+/// Snipes every route enabled under `[sniper]`, all from one stream.
+pub async fn multi_sniper_run(args: SettingsConfig, base_mint: Option<Pubkey>) -> eyre::Result<()> {
+    let targets = enabled_targets(&args);
+    if targets.is_empty() {
+        return Err(eyre::eyre!(
+            "No route is enabled, set enabled = true for one under [sniper]"
+        ));
+    }
+
+    multi_sub(args, base_mint.is_some(), base_mint, targets)
+        .await
+        .map_err(|e| eyre::eyre!("{}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use solana_sdk::{
+        signature::{Keypair, Signature},
+        signer::Signer,
+        transaction::VersionedTransaction,
+    };
+
+    use super::*;
+    use crate::{
+        app::config_init::set_global_config,
+        pumpfun::pump_interface::{
+            accounts::BondingCurve,
+            builder::{get_bonding_curve, pump_buy_args, GLOBAL_STATE, PUMP_PROGRAM},
+            instructions::{BuyIxData, BuyKeys, BUY_IX_ACCOUNTS_LEN},
+        },
+        sender::set_dry_run,
+        testing::{self, rpc::SLOT, transaction_update, MockGeyser, MockRpc},
+    };
+
+    #[test]
+    fn enabled_routes_fall_back_to_the_buy_amount() {
+        let mut args = SettingsConfig::default();
+        args.trading.buy_amount = 0.1;
+        args.sniper.pump_fun.enabled = true;
+        args.sniper.daos_fun.enabled = true;
+        args.sniper.daos_fun.buy_amount = 0.5;
+
+        let targets = enabled_targets(&args);
+
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].route, SniperRoute::PumpFun);
+        assert_eq!(targets[0].contract, PUMPFUN_CONTRACT);
+        assert_eq!(targets[0].buy_amount, sol_to_lamports(0.1));
+        assert_eq!(targets[1].route, SniperRoute::DaosFun);
+        assert_eq!(targets[1].buy_amount, sol_to_lamports(0.5));
+    }
+
+    /// The first signed transaction simulated, which a dry run sends there.
+    async fn first_signed_simulation(rpc: &MockRpc) -> VersionedTransaction {
+        loop {
+            let simulated = rpc.transactions("simulateTransaction");
+            if let Some(tx) = simulated
+                .into_iter()
+                .find(|tx| tx.signatures[0] != Signature::default())
+            {
+                return tx;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn pump_launch_on_the_stream_builds_a_buy() {
+        set_dry_run(true);
+        let payer = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let curve = BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        };
+
+        let launch = testing::pump_launch(&Pubkey::new_unique(), &mint);
+        let geyser = MockGeyser::start(vec![transaction_update(launch, SLOT)]).await;
+        let rpc = MockRpc::start().await;
+        let global = testing::pump_global();
+        rpc.set_account(
+            get_bonding_curve(mint, &PUMP_PROGRAM),
+            testing::bonding_curve_account(&curve),
+        );
+        rpc.set_account(GLOBAL_STATE, testing::global_account(&global));
+        let args = testing::config(&geyser, &rpc, &payer);
+        set_global_config(args.clone()).await;
+
+        let target = SnipeTarget {
+            route: SniperRoute::PumpFun,
+            contract: PUMPFUN_CONTRACT.to_string(),
+            buy_amount: sol_to_lamports(0.1),
+        };
+        let sniper = tokio::spawn(multi_sub(args.clone(), false, None, vec![target]));
+        let buy = tokio::time::timeout(Duration::from_secs(10), first_signed_simulation(&rpc))
+            .await
+            .expect("no buy was built");
+        sniper.abort();
+
+        let request = &geyser.requests()[0];
+        assert_eq!(
+            request.transactions["PumpFun"].account_include,
+            vec![PUMPFUN_CONTRACT.to_string()]
+        );
+
+        let keys = buy.message.static_account_keys();
+        assert_eq!(keys[0], payer.pubkey());
+        let instructions = buy.message.instructions();
+        assert!(instructions
+            .iter()
+            .any(|ix| keys[ix.program_id_index as usize] == spl_associated_token_account::id()));
+
+        let buy_ix = instructions
+            .iter()
+            .find(|ix| keys[ix.program_id_index as usize] == PUMP_PROGRAM)
+            .expect("no Pump.fun instruction");
+        let accounts: [Pubkey; BUY_IX_ACCOUNTS_LEN] = buy_ix
+            .accounts
+            .iter()
+            .map(|index| keys[*index as usize])
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let accounts = BuyKeys::from(accounts);
+        assert_eq!(accounts.mint, mint);
+        assert_eq!(accounts.user, payer.pubkey());
+        assert_eq!(
+            BuyIxData::deserialize(&buy_ix.data).unwrap().0,
+            pump_buy_args(
+                &curve,
+                &global,
+                sol_to_lamports(0.1),
+                args.slippage_bps(&SniperRoute::PumpFun)
+            )
+            .unwrap()
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::{path::Path, sync::Arc, time::Instant};
+
+use futures::future::join_all;
+use log::{error, info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use tokio::time::sleep;
+use yellowstone_grpc_proto::geyser::{subscribe_update::UpdateOneof, SubscribeUpdate};
+
+use crate::{
+    copytrade::{copytrading_decoder::copy_trade_sub, subscription::COPYTRADE_FILTER},
+    env::SettingsConfig,
+    geyser::{ParsedTransaction, Recording},
+    latency::{Stage, Timeline},
+    sender,
+};
+
+use super::{all_targets, route_update};
+
+/// Feeds a recording back through the parsers of every route, and of
+/// copytrade, with sending disabled. Updates follow the recorded pace sped up
+/// `speed` times, or come as fast as they are read when it is 0.
+pub async fn replay(args: SettingsConfig, path: &Path, speed: f64) -> eyre::Result<()> {
+    sender::set_dry_run(true);
+    let rpc_client = Arc::new(RpcClient::new(args.network.rpc_url.clone()));
+    let targets = all_targets(&args);
+
+    let mut recording =
+        Recording::open(path).map_err(|e| eyre::eyre!("Cannot open {}: {}", path.display(), e))?;
+    let started = Instant::now();
+    let mut parsers = Vec::new();
+    let mut updates = 0;
+
+    while let Some((received, update)) = recording.next_update()? {
+        if speed > 0.0 {
+            if let Some(wait) = received.div_f64(speed).checked_sub(started.elapsed()) {
+                sleep(wait).await;
+            }
+        }
+        updates += 1;
+
+        if update
+            .filters
+            .iter()
+            .any(|filter| filter == COPYTRADE_FILTER)
+        {
+            parsers.extend(copy_trade(&rpc_client, &update));
+        }
+        parsers.extend(route_update(
+            &rpc_client,
+            &args,
+            &targets,
+            false,
+            None,
+            update,
+        ));
+    }
+
+    join_all(parsers).await;
+    info!("Replayed {} updates from {}", updates, path.display());
+    Ok(())
+}
+
+fn copy_trade(
+    rpc_client: &Arc<RpcClient>,
+    update: &SubscribeUpdate,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let Some(UpdateOneof::Transaction(tx)) = &update.update_oneof else {
+        return None;
+    };
+    let mut timeline = Timeline::start(tx.slot);
+    let tx = match ParsedTransaction::try_from(tx.clone()) {
+        Ok(tx) => tx,
+        Err(e) => {
+            warn!("Skipping a transaction that does not decode: {}", e);
+            return None;
+        }
+    };
+    timeline.mark(Stage::Parsed);
+
+    let rpc_client = rpc_client.clone();
+    Some(tokio::spawn(timeline.scope(async move {
+        if let Err(e) = copy_trade_sub(&tx, rpc_client).await {
+            error!("Copytrade: {:?}", e);
+        }
+    })))
+}
@@ -0,0 +1,248 @@
+use log::warn;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    compute_budget::{self, ComputeBudgetInstruction},
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0::Message, VersionedMessage},
+    native_token::sol_to_lamports,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::env::SettingsConfig;
+
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Used when the simulation fails, e.g. because the pool is not live yet.
+pub const DEFAULT_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// Extra units on top of the simulated consumption, in percent.
+const COMPUTE_UNIT_HEADROOM: u64 = 15;
+
+/// `getRecentPrioritizationFees` takes at most this many accounts.
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// Picks the compute-unit limit and price of a transaction. Fees are budgeted
+/// in lamports per transaction and converted to the micro-lamports per unit
+/// that `set_compute_unit_price` expects once the limit is known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeEstimator {
+    /// Percentile of the recent fees paid on the same writable accounts, or
+    /// `None` to pay `min_lamports` only.
+    percentile: Option<u8>,
+    min_lamports: u64,
+    max_lamports: u64,
+    /// Unit limit set without simulating, or `None` to simulate every
+    /// transaction.
+    fixed_limit: Option<u32>,
+}
+
+impl FeeEstimator {
+    pub fn new(percentile: Option<u8>, min_lamports: u64, max_lamports: u64) -> Self {
+        FeeEstimator {
+            percentile,
+            min_lamports,
+            max_lamports,
+            fixed_limit: None,
+        }
+    }
+
+    /// Sets `limit` units on every transaction instead of simulating it.
+    pub fn with_fixed_limit(mut self, limit: u32) -> Self {
+        self.fixed_limit = Some(limit.min(MAX_COMPUTE_UNIT_LIMIT));
+        self
+    }
+
+    pub fn from_config(args: &SettingsConfig) -> Self {
+        let percentile = args.trading.priority_fee_percentile;
+        let estimator = Self::new(
+            (percentile > 0).then_some(percentile.min(100)),
+            sol_to_lamports(args.trading.priority_fee),
+            sol_to_lamports(args.trading.max_priority_fee.max(args.trading.priority_fee)),
+        );
+        match args.trading.compute_unit_limit {
+            0 => estimator,
+            limit => estimator.with_fixed_limit(limit),
+        }
+    }
+
+    /// Replaces any compute budget instructions in `instructions` with a
+    /// simulated or fixed unit limit and an estimated unit price. The
+    /// simulation and the fee lookup run at the same time.
+    pub async fn apply(
+        &self,
+        rpc_client: &RpcClient,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+    ) -> Vec<Instruction> {
+        let body: Vec<Instruction> = instructions
+            .iter()
+            .filter(|ix| ix.program_id != compute_budget::id())
+            .cloned()
+            .collect();
+
+        let limit = async {
+            match self.fixed_limit {
+                Some(limit) => limit,
+                None => self.unit_limit(rpc_client, payer, &body).await,
+            }
+        };
+        let (limit, recent) = tokio::join!(limit, self.recent_fee(rpc_client, &body));
+        let price = self.unit_price(recent, limit);
+
+        let mut budgeted = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(limit),
+            ComputeBudgetInstruction::set_compute_unit_price(price),
+        ];
+        budgeted.extend(body);
+        budgeted
+    }
+
+    /// Simulates `instructions` once with the maximum limit and returns the units
+    /// consumed plus headroom.
+    pub async fn unit_limit(
+        &self,
+        rpc_client: &RpcClient,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+    ) -> u32 {
+        let mut simulated = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            MAX_COMPUTE_UNIT_LIMIT,
+        )];
+        simulated.extend_from_slice(instructions);
+
+        let transaction = match unsigned_transaction(payer, &simulated) {
+            Ok(transaction) => transaction,
+            Err(e) => {
+                warn!("Cannot compile transaction for simulation: {}", e);
+                return DEFAULT_COMPUTE_UNIT_LIMIT;
+            }
+        };
+
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(CommitmentConfig::processed()),
+            encoding: Some(UiTransactionEncoding::Base64),
+            ..Default::default()
+        };
+
+        match rpc_client
+            .simulate_transaction_with_config(&transaction, config)
+            .await
+        {
+            Ok(response) => match (response.value.err, response.value.units_consumed) {
+                (None, Some(units)) => with_headroom(units),
+                (Some(err), _) => {
+                    warn!("Simulation failed ({}), using the default compute limit", err);
+                    DEFAULT_COMPUTE_UNIT_LIMIT
+                }
+                (None, None) => DEFAULT_COMPUTE_UNIT_LIMIT,
+            },
+            Err(e) => {
+                warn!("Simulation unavailable ({}), using the default compute limit", e);
+                DEFAULT_COMPUTE_UNIT_LIMIT
+            }
+        }
+    }
+
+    /// The configured percentile of recent unit prices paid on the accounts
+    /// `instructions` write to, in micro-lamports. 0 without a percentile.
+    pub async fn recent_fee(&self, rpc_client: &RpcClient, instructions: &[Instruction]) -> u64 {
+        let percentile = match self.percentile {
+            Some(percentile) => percentile,
+            None => return 0,
+        };
+
+        let accounts = writable_accounts(instructions);
+        match rpc_client.get_recent_prioritization_fees(&accounts).await {
+            Ok(fees) => percentile_of(
+                fees.into_iter().map(|fee| fee.prioritization_fee).collect(),
+                percentile,
+            ),
+            Err(e) => {
+                warn!("Cannot read recent prioritization fees: {}", e);
+                0
+            }
+        }
+    }
+
+    /// Unit price in micro-lamports for a transaction with `limit` units given
+    /// the `recent` price, never below the configured minimum nor above the cap.
+    pub fn unit_price(&self, recent: u64, limit: u32) -> u64 {
+        let floor = micro_lamports_per_unit(self.min_lamports, limit);
+        let cap = micro_lamports_per_unit(self.max_lamports, limit);
+        recent.max(floor).min(cap)
+    }
+}
+
+fn unsigned_transaction(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+) -> eyre::Result<VersionedTransaction> {
+    let message = Message::try_compile(payer, instructions, &[], Hash::default())?;
+    Ok(VersionedTransaction {
+        signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+        message: VersionedMessage::V0(message),
+    })
+}
+
+fn with_headroom(units: u64) -> u32 {
+    let limit = units + units * COMPUTE_UNIT_HEADROOM / 100;
+    limit.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+pub fn micro_lamports_per_unit(lamports: u64, limit: u32) -> u64 {
+    if limit == 0 {
+        return 0;
+    }
+    (lamports as u128 * 1_000_000 / limit as u128) as u64
+}
+
+fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = vec![];
+    for meta in instructions.iter().flat_map(|ix| &ix.accounts) {
+        if meta.is_writable && !accounts.contains(&meta.pubkey) {
+            accounts.push(meta.pubkey);
+        }
+    }
+    accounts.truncate(MAX_FEE_ACCOUNTS);
+    accounts
+}
+
+fn percentile_of(mut fees: Vec<u64>, percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    fees.sort_unstable();
+    let index = (fees.len() - 1) * percentile.min(100) as usize / 100;
+    fees[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fee_math() {
+        assert_eq!(percentile_of(vec![], 75), 0);
+        assert_eq!(percentile_of(vec![40, 10, 30, 20, 50], 50), 30);
+        assert_eq!(percentile_of(vec![40, 10, 30, 20, 50], 100), 50);
+
+        // 0.0001 SOL over 100k units is one lamport per unit.
+        assert_eq!(micro_lamports_per_unit(sol_to_lamports(0.0001), 100_000), 1_000_000);
+        assert_eq!(micro_lamports_per_unit(5_000, 200_000), 25_000);
+
+        assert_eq!(with_headroom(100_000), 115_000);
+
+        // 0.0001 SOL floor and 0.001 SOL cap over 100k units
+        let estimator = FeeEstimator::new(Some(75), 100_000, 1_000_000).with_fixed_limit(100_000);
+        assert_eq!(estimator.unit_price(0, 100_000), 1_000_000);
+        assert_eq!(estimator.unit_price(50_000_000, 100_000), 10_000_000);
+        assert_eq!(with_headroom(2_000_000), MAX_COMPUTE_UNIT_LIMIT);
+    }
+}
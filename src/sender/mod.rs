@@ -0,0 +1,639 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures::future::join_all;
+use jito_protos::searcher::SubscribeBundleResultsRequest;
+use jito_searcher_client::{
+    get_searcher_client, send_bundle_with_confirmation, BundleRejectionError,
+};
+use log::{error, info, warn};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig, RpcTransactionConfig},
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0::Message, VersionedMessage},
+    native_token::sol_to_lamports,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    system_instruction::transfer,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{UiTransactionEncoding, UiTransactionStatusMeta};
+use tokio::time::{sleep, Instant};
+
+use crate::{
+    env::SettingsConfig,
+    instruction::error::describe_transaction_error,
+    journal::{self, Fill, Order},
+    latency::{self, Stage},
+    liquidity::utils::tip_account,
+    raydium_amm::swap::swapper::auth_keypair,
+};
+
+pub mod fees;
+
+use fees::FeeEstimator;
+
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Backstop in case the RPC never answers the blockhash validity check.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// With `dry_run` set, every send is simulated and reported as failed instead,
+/// e.g. while replaying a recording.
+pub fn set_dry_run(dry_run: bool) {
+    DRY_RUN.store(dry_run, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// How a transaction is put on the wire.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SendStrategy {
+    /// One `sendTransaction` to the configured RPC.
+    Rpc,
+    /// The same signed transaction sent `copies` times to the configured RPC.
+    Spam { copies: u32 },
+    /// A Jito bundle, tipping `tip_lamports` to a random tip account.
+    Bundle { tip_lamports: u64 },
+    /// One copy to the configured RPC and to every extra endpoint, concurrently.
+    FanOut { rpc_urls: Vec<String> },
+}
+
+impl SendStrategy {
+    /// Bundles win over fan-out, fan-out wins over spam.
+    pub fn from_config(args: &SettingsConfig) -> Self {
+        if args.engine.use_bundles {
+            SendStrategy::Bundle {
+                tip_lamports: sol_to_lamports(args.trading.bundle_tip),
+            }
+        } else if !args.network.send_rpc_urls.is_empty() {
+            SendStrategy::FanOut {
+                rpc_urls: args.network.send_rpc_urls.clone(),
+            }
+        } else if args.trading.spam {
+            SendStrategy::Spam {
+                copies: args.trading.spam_count.max(1) as u32,
+            }
+        } else {
+            SendStrategy::Rpc
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SendOutcome {
+    /// Confirmed without error. Fee and compute units are `None` when the RPC
+    /// could not return the transaction details in time.
+    Landed {
+        slot: u64,
+        fee: Option<u64>,
+        cu: Option<u64>,
+    },
+    /// Landed with an error, or nothing would take the transaction.
+    Failed { error: String },
+    /// Never confirmed before the blockhash expired, retries included.
+    Expired,
+}
+
+#[derive(Debug, Clone)]
+pub struct SendReport {
+    pub signatures: Vec<Signature>,
+    pub outcome: SendOutcome,
+    /// Balance changes of a landed send, when it was journaled with an order.
+    pub fill: Option<Fill>,
+}
+
+impl SendReport {
+    /// Signature of the first (main) transaction.
+    pub fn signature(&self) -> Option<Signature> {
+        self.signatures.first().copied()
+    }
+
+    pub fn landed(&self) -> bool {
+        matches!(self.outcome, SendOutcome::Landed { .. })
+    }
+
+    pub fn log(&self) {
+        let signature = self
+            .signature()
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+        match &self.outcome {
+            SendOutcome::Landed { slot, fee, cu } => info!(
+                "Transaction {} landed in slot {} (fee: {} lamports, cu: {})",
+                signature,
+                slot,
+                fee.map_or("?".to_string(), |f| f.to_string()),
+                cu.map_or("?".to_string(), |c| c.to_string())
+            ),
+            SendOutcome::Failed { error } => error!("Transaction {} failed: {}", signature, error),
+            SendOutcome::Expired => warn!("Transaction {} expired before it confirmed", signature),
+        }
+    }
+}
+
+/// Single entry point for submitting transactions, whatever the venue. Every
+/// send is tracked until it confirms or its blockhash expires.
+#[derive(Clone)]
+pub struct TransactionSender {
+    rpc_client: Arc<RpcClient>,
+    block_engine_url: String,
+    strategy: SendStrategy,
+    retries: u32,
+    fees: Option<FeeEstimator>,
+    order: Option<Order>,
+}
+
+impl TransactionSender {
+    pub fn new(rpc_client: Arc<RpcClient>, block_engine_url: String, strategy: SendStrategy) -> Self {
+        TransactionSender {
+            rpc_client,
+            block_engine_url,
+            strategy,
+            retries: 0,
+            fees: None,
+            order: None,
+        }
+    }
+
+    pub fn from_config(args: &SettingsConfig, rpc_client: Arc<RpcClient>) -> Self {
+        Self::new(
+            rpc_client,
+            args.network.block_engine_url.clone(),
+            SendStrategy::from_config(args),
+        )
+        .with_retries(args.trading.send_retries)
+        .with_fees(FeeEstimator::from_config(args))
+    }
+
+    pub fn with_strategy(mut self, strategy: SendStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Number of re-signs `send` may do after an expiry.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Lets `send` set the compute budget of every transaction it signs.
+    pub fn with_fees(mut self, fees: FeeEstimator) -> Self {
+        self.fees = Some(fees);
+        self
+    }
+
+    /// Records the outcome and fill of every send in the trade journal.
+    pub fn with_order(mut self, order: Order) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    pub fn strategy(&self) -> &SendStrategy {
+        &self.strategy
+    }
+
+    pub fn rpc_client(&self) -> &Arc<RpcClient> {
+        &self.rpc_client
+    }
+
+    /// Compiles `instructions` into a v0 transaction paid by `payer`, signs it and
+    /// sends it. For bundles the tip transfer is appended to the same transaction,
+    /// so it is only paid if the swap lands. With a fee estimator, any compute
+    /// budget instructions are replaced by a simulated limit and an estimated
+    /// price, looked up while the blockhash is fetched. An expired transaction
+    /// is signed again at the same price with a fresh blockhash, up to the
+    /// retry budget.
+    pub async fn send(
+        &self,
+        payer: &Keypair,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> eyre::Result<SendReport> {
+        latency::mark(Stage::Built);
+        let mut instructions = instructions.to_vec();
+        if let SendStrategy::Bundle { tip_lamports } = self.strategy {
+            instructions.push(transfer(&payer.pubkey(), &tip_account(), tip_lamports));
+        }
+
+        let budgeted = async {
+            match &self.fees {
+                Some(fees) => fees.apply(&self.rpc_client, &payer.pubkey(), &instructions).await,
+                None => instructions.clone(),
+            }
+        };
+        let (budgeted, blockhash) = tokio::join!(budgeted, self.latest_blockhash());
+        let mut blockhash = blockhash?;
+
+        let mut attempt = 0;
+        loop {
+            let transaction = self.sign_with(payer, &budgeted, signers, blockhash)?;
+            latency::mark(Stage::Signed);
+            let transactions = vec![transaction];
+            let mut report = self.dispatch(&transactions).await?;
+            if report.outcome != SendOutcome::Expired || attempt >= self.retries {
+                report.fill = self.journal(&report, transactions).await;
+                return Ok(report);
+            }
+
+            attempt += 1;
+            warn!(
+                "Transaction {} expired, re-signing ({}/{})",
+                report.signatures[0], attempt, self.retries
+            );
+            blockhash = self.latest_blockhash().await?;
+        }
+    }
+
+    /// Sends transactions that are already signed. For bundles a separate tip
+    /// transaction from `payer` is added at the end. These cannot be re-signed,
+    /// so an expiry is final.
+    pub async fn send_signed(
+        &self,
+        payer: &Keypair,
+        mut transactions: Vec<VersionedTransaction>,
+    ) -> eyre::Result<SendReport> {
+        latency::mark(Stage::Built);
+        latency::mark(Stage::Signed);
+        if let SendStrategy::Bundle { tip_lamports } = self.strategy {
+            let tip = transfer(&payer.pubkey(), &tip_account(), tip_lamports);
+            transactions.push(self.sign(payer, &[tip], &[]).await?);
+        }
+        let mut report = self.dispatch(&transactions).await?;
+        report.fill = self.journal(&report, transactions).await;
+        Ok(report)
+    }
+
+    /// Sends signed transactions as one bundle as they are, whatever the strategy.
+    /// The caller is responsible for the tip.
+    pub async fn send_bundle(&self, transactions: Vec<VersionedTransaction>) -> eyre::Result<SendReport> {
+        latency::mark(Stage::Built);
+        latency::mark(Stage::Signed);
+        if is_dry_run() {
+            return Ok(self.simulate(&transactions).await);
+        }
+        let outcome = match self.bundle(&transactions).await {
+            Ok(()) => {
+                latency::mark(Stage::Sent);
+                self.confirm(&transactions[0]).await
+            }
+            Err(error) => SendOutcome::Failed { error },
+        };
+        let mut report = SendReport {
+            signatures: signatures_of(&transactions),
+            outcome,
+            fill: None,
+        };
+        report.fill = self.journal(&report, transactions).await;
+        Ok(report)
+    }
+
+    pub async fn sign(
+        &self,
+        payer: &Keypair,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> eyre::Result<VersionedTransaction> {
+        let blockhash = self.latest_blockhash().await?;
+        self.sign_with(payer, instructions, signers, blockhash)
+    }
+
+    async fn latest_blockhash(&self) -> eyre::Result<Hash> {
+        let (blockhash, _) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await?;
+        Ok(blockhash)
+    }
+
+    fn sign_with(
+        &self,
+        payer: &Keypair,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+        blockhash: Hash,
+    ) -> eyre::Result<VersionedTransaction> {
+        let message = Message::try_compile(&payer.pubkey(), instructions, &[], blockhash)?;
+
+        let mut keypairs = vec![payer];
+        keypairs.extend(signers.iter().filter(|s| s.pubkey() != payer.pubkey()));
+
+        Ok(VersionedTransaction::try_new(
+            VersionedMessage::V0(message),
+            &keypairs,
+        )?)
+    }
+
+    async fn dispatch(&self, transactions: &[VersionedTransaction]) -> eyre::Result<SendReport> {
+        if is_dry_run() {
+            return Ok(self.simulate(transactions).await);
+        }
+        let sent = match &self.strategy {
+            SendStrategy::Bundle { .. } => self.bundle(transactions).await,
+            SendStrategy::Rpc => submit(std::slice::from_ref(&self.rpc_client), transactions, 1).await,
+            SendStrategy::Spam { copies } => {
+                submit(std::slice::from_ref(&self.rpc_client), transactions, *copies).await
+            }
+            SendStrategy::FanOut { rpc_urls } => {
+                let mut clients = vec![self.rpc_client.clone()];
+                clients.extend(
+                    rpc_urls
+                        .iter()
+                        .map(|url| Arc::new(RpcClient::new(url.clone()))),
+                );
+                submit(&clients, transactions, 1).await
+            }
+        };
+
+        let outcome = match sent {
+            Ok(()) => {
+                latency::mark(Stage::Sent);
+                self.confirm(&transactions[0]).await
+            }
+            Err(error) => SendOutcome::Failed { error },
+        };
+
+        Ok(SendReport {
+            signatures: signatures_of(transactions),
+            outcome,
+            fill: None,
+        })
+    }
+
+    /// Simulates the main transaction of a dry run in place of sending.
+    async fn simulate(&self, transactions: &[VersionedTransaction]) -> SendReport {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(CommitmentConfig::processed()),
+            encoding: Some(UiTransactionEncoding::Base64),
+            ..Default::default()
+        };
+        let simulation = match self
+            .rpc_client
+            .simulate_transaction_with_config(&transactions[0], config)
+            .await
+        {
+            Ok(response) => match response.value.err {
+                None => format!(
+                    "simulated in {} cu",
+                    response
+                        .value
+                        .units_consumed
+                        .map_or("?".to_string(), |units| units.to_string())
+                ),
+                Some(err) => format!(
+                    "simulation failed: {}",
+                    describe_transaction_error(&err, &program_ids_of(&transactions[0]))
+                ),
+            },
+            Err(e) => format!("not simulated: {}", e),
+        };
+
+        SendReport {
+            signatures: signatures_of(transactions),
+            outcome: SendOutcome::Failed {
+                error: format!("dry run, not sent ({})", simulation),
+            },
+            fill: None,
+        }
+    }
+
+    /// Only a failed simulation is final here. A lost auction or a block engine
+    /// that goes quiet says nothing about whether the bundle lands, so those are
+    /// left to `confirm`.
+    async fn bundle(&self, transactions: &[VersionedTransaction]) -> Result<(), String> {
+        info!("Building Bundle");
+
+        let mut searcher_client = get_searcher_client(&self.block_engine_url, &Arc::new(auth_keypair()))
+            .await
+            .map_err(|e| format!("block engine unavailable: {}", e))?;
+
+        let mut bundle_results_subscription = searcher_client
+            .subscribe_bundle_results(SubscribeBundleResultsRequest {})
+            .await
+            .map_err(|e| format!("bundle results subscription failed: {}", e))?
+            .into_inner();
+
+        let result = send_bundle_with_confirmation(
+            transactions,
+            &self.rpc_client,
+            &mut searcher_client,
+            &mut bundle_results_subscription,
+        )
+        .await;
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => match e.downcast_ref::<BundleRejectionError>() {
+                Some(rejection @ BundleRejectionError::SimulationFailure(..)) => {
+                    Err(rejection.to_string())
+                }
+                _ => {
+                    warn!("Bundle not confirmed by the block engine: {}", e);
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    /// Polls the status of `transaction` until it is confirmed or its blockhash
+    /// is no longer valid. The status is checked once more after expiry so a
+    /// late landing is not reported as expired.
+    async fn confirm(&self, transaction: &VersionedTransaction) -> SendOutcome {
+        let signature = transaction.signatures[0];
+        let blockhash = *transaction.message.recent_blockhash();
+        let started = Instant::now();
+        let mut expired = false;
+
+        loop {
+            match self.rpc_client.get_signature_statuses(&[signature]).await {
+                Ok(response) => {
+                    if let Some(Some(status)) = response.value.into_iter().next() {
+                        if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                            return match status.err {
+                                Some(err) => SendOutcome::Failed {
+                                    error: describe_transaction_error(
+                                        &err,
+                                        &program_ids_of(transaction),
+                                    ),
+                                },
+                                None => self.landed(&signature, status.slot).await,
+                            };
+                        }
+                    }
+                }
+                Err(e) => warn!("Status check for {} failed: {}", signature, e),
+            }
+
+            if expired || started.elapsed() > CONFIRM_TIMEOUT {
+                return SendOutcome::Expired;
+            }
+            expired = !self.blockhash_valid(&blockhash).await;
+            if !expired {
+                sleep(CONFIRM_POLL_INTERVAL).await;
+            }
+        }
+    }
+
+    async fn blockhash_valid(&self, blockhash: &Hash) -> bool {
+        self.rpc_client
+            .is_blockhash_valid(blockhash, CommitmentConfig::processed())
+            .await
+            .unwrap_or(true)
+    }
+
+    async fn landed(&self, signature: &Signature, slot: u64) -> SendOutcome {
+        latency::landed(slot);
+        match fetch_confirmed(&self.rpc_client, signature).await {
+            Some((slot, meta)) => SendOutcome::Landed {
+                slot,
+                fee: Some(meta.fee),
+                cu: meta.compute_units_consumed.into(),
+            },
+            None => SendOutcome::Landed {
+                slot,
+                fee: None,
+                cu: None,
+            },
+        }
+    }
+
+    /// Journals the finished send when an order is attached and returns its
+    /// fill. This is awaited rather than spawned so one-shot CLI runs do not
+    /// exit before the write. The send ends the timeline of its trigger.
+    async fn journal(
+        &self,
+        report: &SendReport,
+        transactions: Vec<VersionedTransaction>,
+    ) -> Option<Fill> {
+        let latency = latency::take();
+        if is_dry_run() {
+            return None;
+        }
+        let order = self.order.as_ref()?;
+        journal::record(
+            self.rpc_client.clone(),
+            order.clone(),
+            report.clone(),
+            transactions,
+            latency,
+        )
+        .await
+    }
+}
+
+/// Slot and status meta of a confirmed transaction. The RPC may lag behind the
+/// status it just returned, so this retries briefly.
+pub(crate) async fn fetch_confirmed(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+) -> Option<(u64, UiTransactionStatusMeta)> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    for _ in 0..3 {
+        if let Ok(transaction) = rpc_client.get_transaction_with_config(signature, config).await {
+            if let Some(meta) = transaction.transaction.meta {
+                return Some((transaction.slot, meta));
+            }
+        }
+        sleep(CONFIRM_POLL_INTERVAL).await;
+    }
+    None
+}
+
+fn signatures_of(transactions: &[VersionedTransaction]) -> Vec<Signature> {
+    transactions.iter().map(|tx| tx.signatures[0]).collect()
+}
+
+/// Program of each top-level instruction, used to decode custom errors.
+fn program_ids_of(transaction: &VersionedTransaction) -> Vec<Pubkey> {
+    let keys = transaction.message.static_account_keys();
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .map(|ix| keys.get(ix.program_id_index as usize).copied().unwrap_or_default())
+        .collect()
+}
+
+/// Sends every transaction `copies` times to each client. Errors are logged and
+/// counted, never fatal: it only fails when nothing got through.
+async fn submit(
+    clients: &[Arc<RpcClient>],
+    transactions: &[VersionedTransaction],
+    copies: u32,
+) -> Result<(), String> {
+    let config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        ..Default::default()
+    };
+
+    let sends = clients.iter().flat_map(|client| {
+        transactions.iter().flat_map(move |transaction| {
+            (0..copies).map(move |_| client.send_transaction_with_config(transaction, config))
+        })
+    });
+    let results = join_all(sends).await;
+
+    let attempts = results.len();
+    let mut last_error = None;
+    let accepted = results
+        .into_iter()
+        .filter(|result| match result {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Send failed: {}", e);
+                last_error = Some(e.to_string());
+                false
+            }
+        })
+        .count();
+
+    if accepted == 0 {
+        return Err(last_error.unwrap_or_else(|| "nothing was sent".to_string()));
+    }
+    info!("Transaction accepted by {} of {} sends", accepted, attempts);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategy_precedence() {
+        let mut args = SettingsConfig::default();
+        args.engine.use_bundles = false;
+        assert_eq!(SendStrategy::from_config(&args), SendStrategy::Rpc);
+
+        args.trading.spam = true;
+        args.trading.spam_count = 3;
+        assert_eq!(SendStrategy::from_config(&args), SendStrategy::Spam { copies: 3 });
+
+        args.network.send_rpc_urls = vec!["https://example.com".to_string()];
+        assert!(matches!(SendStrategy::from_config(&args), SendStrategy::FanOut { .. }));
+
+        args.engine.use_bundles = true;
+        assert!(matches!(SendStrategy::from_config(&args), SendStrategy::Bundle { .. }));
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    collections::HashSet,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use futures::{stream, Stream, StreamExt};
+use solana_sdk::{pubkey::Pubkey, transaction::SanitizedTransaction, transaction::Transaction};
+use tokio::{net::TcpListener, task::JoinHandle};
+use yellowstone_grpc_proto::{
+    convert_to,
+    geyser::{
+        geyser_server::{Geyser, GeyserServer},
+        subscribe_update::UpdateOneof,
+        GetBlockHeightRequest, GetBlockHeightResponse, GetLatestBlockhashRequest,
+        GetLatestBlockhashResponse, GetSlotRequest, GetSlotResponse, GetVersionRequest,
+        GetVersionResponse, IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest,
+        PongResponse, SubscribeRequest, SubscribeUpdate, SubscribeUpdateTransaction,
+        SubscribeUpdateTransactionInfo,
+    },
+    prelude::TransactionStatusMeta,
+    tonic::{self, transport::Server, Request, Response, Status, Streaming},
+};
+
+use crate::geyser::account_keys;
+
+type UpdateStream = Pin<Box<dyn Stream<Item = Result<SubscribeUpdate, Status>> + Send>>;
+
+/// A Yellowstone endpoint on localhost that plays the same scripted updates to
+/// every subscriber, then keeps the stream open.
+pub struct MockGeyser {
+    /// `http://` address to put in `network.grpc_url`.
+    pub endpoint: String,
+    requests: Arc<Mutex<Vec<SubscribeRequest>>>,
+    server: JoinHandle<()>,
+}
+
+impl MockGeyser {
+    pub async fn start(updates: Vec<SubscribeUpdate>) -> MockGeyser {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let service = ScriptedGeyser {
+            updates,
+            requests: requests.clone(),
+        };
+        let incoming = stream::unfold(listener, |listener| async move {
+            let stream = listener.accept().await.map(|(stream, _)| stream);
+            Some((stream, listener))
+        });
+        let server = tokio::spawn(async move {
+            Server::builder()
+                .add_service(GeyserServer::new(service))
+                .serve_with_incoming(incoming)
+                .await
+                .unwrap();
+        });
+
+        MockGeyser {
+            endpoint,
+            requests,
+            server,
+        }
+    }
+
+    /// The first request of every subscription so far.
+    pub fn requests(&self) -> Vec<SubscribeRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockGeyser {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Wraps a transaction the way Geyser streams it, with empty balances.
+pub fn transaction_update(transaction: Transaction, slot: u64) -> SubscribeUpdate {
+    let signature = transaction.signatures[0];
+    let keys = transaction.message.account_keys.len();
+    let sanitized = SanitizedTransaction::try_from_legacy_transaction(transaction).unwrap();
+
+    SubscribeUpdate {
+        filters: vec![],
+        update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: signature.as_ref().to_vec(),
+                is_vote: false,
+                transaction: Some(convert_to::create_transaction(&sanitized)),
+                meta: Some(TransactionStatusMeta {
+                    pre_balances: vec![0; keys],
+                    post_balances: vec![0; keys],
+                    ..Default::default()
+                }),
+                index: 0,
+            }),
+            slot,
+        })),
+    }
+}
+
+struct ScriptedGeyser {
+    updates: Vec<SubscribeUpdate>,
+    requests: Arc<Mutex<Vec<SubscribeRequest>>>,
+}
+
+impl ScriptedGeyser {
+    /// Names of the transaction filters `update` matches, as a real endpoint
+    /// tags it. Other updates keep the filters they were scripted with.
+    fn tag(request: &SubscribeRequest, mut update: SubscribeUpdate) -> Option<SubscribeUpdate> {
+        let Some(UpdateOneof::Transaction(transaction)) = &update.update_oneof else {
+            return Some(update);
+        };
+        let keys: HashSet<String> = transaction
+            .transaction
+            .as_ref()
+            .and_then(|info| account_keys(info).ok())
+            .unwrap_or_default()
+            .iter()
+            .map(Pubkey::to_string)
+            .collect();
+
+        update.filters = request
+            .transactions
+            .iter()
+            .filter(|(_, filter)| {
+                filter.account_include.is_empty()
+                    || filter.account_include.iter().any(|key| keys.contains(key))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        (!update.filters.is_empty()).then_some(update)
+    }
+}
+
+#[tonic::async_trait]
+impl Geyser for ScriptedGeyser {
+    type SubscribeStream = UpdateStream;
+
+    async fn subscribe(
+        &self,
+        request: Request<Streaming<SubscribeRequest>>,
+    ) -> Result<Response<UpdateStream>, Status> {
+        let mut incoming = request.into_inner();
+        let request = incoming
+            .next()
+            .await
+            .ok_or_else(|| Status::invalid_argument("no subscribe request"))??;
+        self.requests.lock().unwrap().push(request.clone());
+
+        let updates: Vec<_> = self
+            .updates
+            .iter()
+            .filter_map(|update| Self::tag(&request, update.clone()))
+            .map(Ok)
+            .collect();
+        Ok(Response::new(Box::pin(
+            stream::iter(updates).chain(stream::pending()),
+        )))
+    }
+
+    async fn ping(&self, request: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+        Ok(Response::new(PongResponse {
+            count: request.into_inner().count,
+        }))
+    }
+
+    async fn get_latest_blockhash(
+        &self,
+        _: Request<GetLatestBlockhashRequest>,
+    ) -> Result<Response<GetLatestBlockhashResponse>, Status> {
+        Err(Status::unimplemented("not scripted"))
+    }
+
+    async fn get_block_height(
+        &self,
+        _: Request<GetBlockHeightRequest>,
+    ) -> Result<Response<GetBlockHeightResponse>, Status> {
+        Err(Status::unimplemented("not scripted"))
+    }
+
+    async fn get_slot(
+        &self,
+        _: Request<GetSlotRequest>,
+    ) -> Result<Response<GetSlotResponse>, Status> {
+        Err(Status::unimplemented("not scripted"))
+    }
+
+    async fn is_blockhash_valid(
+        &self,
+        _: Request<IsBlockhashValidRequest>,
+    ) -> Result<Response<IsBlockhashValidResponse>, Status> {
+        Err(Status::unimplemented("not scripted"))
+    }
+
+    async fn get_version(
+        &self,
+        _: Request<GetVersionRequest>,
+    ) -> Result<Response<GetVersionResponse>, Status> {
+        Err(Status::unimplemented("not scripted"))
+    }
+}
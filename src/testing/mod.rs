@@ -0,0 +1,100 @@
+//! Local stand-ins for the Geyser and RPC endpoints, so a snipe can run end to
+//! end without the network.
+
+pub mod geyser;
+pub mod rpc;
+
+use solana_sdk::{
+    account::Account, pubkey::Pubkey, signature::Keypair, system_program, transaction::Transaction,
+};
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::{
+    env::SettingsConfig,
+    pumpfun::pump_interface::{
+        accounts::{BondingCurve, BondingCurveAccount, Global, GlobalAccount},
+        builder::{get_bonding_curve, EVENT_AUTH, FEE_RECEPIENT, GLOBAL_STATE, PUMP_PROGRAM},
+        instructions::{create_ix, CreateIxArgs, CreateKeys},
+    },
+};
+
+pub use geyser::{transaction_update, MockGeyser};
+pub use rpc::MockRpc;
+
+/// Settings pointing at the mocks, sending one plain transaction per snipe.
+pub fn config(geyser: &MockGeyser, rpc: &MockRpc, payer: &Keypair) -> SettingsConfig {
+    let mut args = SettingsConfig::default();
+    args.network.grpc_url = geyser.endpoint.clone();
+    args.network.rpc_url = rpc.url.clone();
+    args.engine.payer_keypair = payer.to_base58_string();
+    args.engine.use_bundles = false;
+    args.trading.send_retries = 0;
+    args
+}
+
+/// `creator` launching `mint` on Pump.fun.
+pub fn pump_launch(creator: &Pubkey, mint: &Pubkey) -> Transaction {
+    let bonding_curve = get_bonding_curve(*mint, &PUMP_PROGRAM);
+    let (metadata, _) = mpl_token_metadata::accounts::Metadata::find_pda(mint);
+    let instruction = create_ix(
+        CreateKeys {
+            mint: *mint,
+            mint_authority: Pubkey::new_unique(),
+            bonding_curve,
+            associated_bonding_curve: get_associated_token_address(&bonding_curve, mint),
+            global: GLOBAL_STATE,
+            mpl_token_metadata: mpl_token_metadata::ID,
+            metadata,
+            user: *creator,
+            system_program: system_program::id(),
+            token_program: spl_token::id(),
+            associated_token_program: spl_associated_token_account::id(),
+            rent: solana_sdk::sysvar::rent::id(),
+            event_authority: EVENT_AUTH,
+            program: PUMP_PROGRAM,
+        },
+        CreateIxArgs {
+            name: "Mock".to_string(),
+            symbol: "MOCK".to_string(),
+            uri: "https://example.com/mock.json".to_string(),
+        },
+    )
+    .unwrap();
+    Transaction::new_with_payer(&[instruction], Some(creator))
+}
+
+/// The Pump.fun global account as it is on mainnet, with a 1% trade fee.
+pub fn pump_global() -> Global {
+    Global {
+        initialized: true,
+        authority: Pubkey::default(),
+        fee_recipient: FEE_RECEPIENT,
+        initial_virtual_token_reserves: 1_073_000_000_000_000,
+        initial_virtual_sol_reserves: 30_000_000_000,
+        initial_real_token_reserves: 793_100_000_000_000,
+        token_total_supply: 1_000_000_000_000_000,
+        fee_basis_points: 100,
+    }
+}
+
+/// The global account at [`GLOBAL_STATE`], owned by Pump.fun.
+pub fn global_account(global: &Global) -> Account {
+    Account {
+        lamports: 1_000_000_000,
+        data: GlobalAccount(global.clone()).try_to_vec().unwrap(),
+        owner: PUMP_PROGRAM,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// The bonding curve account of a mint, owned by Pump.fun.
+pub fn bonding_curve_account(curve: &BondingCurve) -> Account {
+    Account {
+        lamports: 1_000_000_000,
+        data: BondingCurveAccount(curve.clone()).try_to_vec().unwrap(),
+        owner: PUMP_PROGRAM,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
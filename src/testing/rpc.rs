@@ -0,0 +1,232 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+use solana_sdk::{account::Account, hash::Hash, pubkey::Pubkey, transaction::VersionedTransaction};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+/// Slot every answer is given at.
+pub const SLOT: u64 = 1;
+
+/// A JSON-RPC endpoint on localhost answering from fixtures, which records the
+/// params of every call.
+pub struct MockRpc {
+    /// Address to put in `network.rpc_url`.
+    pub url: String,
+    state: Arc<Mutex<State>>,
+    server: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct State {
+    accounts: HashMap<Pubkey, Account>,
+    results: HashMap<String, Value>,
+    calls: Vec<(String, Value)>,
+}
+
+impl MockRpc {
+    pub async fn start() -> MockRpc {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let connections = state.clone();
+        let server = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, connections.clone()));
+            }
+        });
+
+        MockRpc { url, state, server }
+    }
+
+    /// Answers `getAccountInfo` and `getMultipleAccounts` for `pubkey`.
+    pub fn set_account(&self, pubkey: Pubkey, account: Account) {
+        self.state.lock().unwrap().accounts.insert(pubkey, account);
+    }
+
+    /// Answers every `method` call with `result`, in place of the default.
+    pub fn set_result(&self, method: &str, result: Value) {
+        self.state
+            .lock()
+            .unwrap()
+            .results
+            .insert(method.to_string(), result);
+    }
+
+    /// Params of every `method` call so far.
+    pub fn calls(&self, method: &str) -> Vec<Value> {
+        self.state
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .filter(|(called, _)| called == method)
+            .map(|(_, params)| params.clone())
+            .collect()
+    }
+
+    /// Transactions sent to `sendTransaction` or `simulateTransaction`.
+    pub fn transactions(&self, method: &str) -> Vec<VersionedTransaction> {
+        self.calls(method)
+            .iter()
+            .filter_map(|params| decode_transaction(params).ok())
+            .collect()
+    }
+}
+
+impl Drop for MockRpc {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Answers requests on one keep-alive connection until the client hangs up.
+async fn serve(stream: TcpStream, state: Arc<Mutex<State>>) {
+    let mut stream = BufReader::new(stream);
+    loop {
+        let mut content_length = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match stream.read_line(&mut line).await {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+
+        let mut body = vec![0; content_length];
+        if stream.read_exact(&mut body).await.is_err() {
+            return;
+        }
+        let response = match serde_json::from_slice::<Value>(&body) {
+            Ok(Value::Array(requests)) => Value::Array(
+                requests
+                    .iter()
+                    .map(|request| answer(&state, request))
+                    .collect(),
+            ),
+            Ok(request) => answer(&state, &request),
+            Err(e) => json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32700, "message": e.to_string()}}),
+        }
+        .to_string();
+
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+            response.len()
+        );
+        let stream = stream.get_mut();
+        if stream.write_all(head.as_bytes()).await.is_err()
+            || stream.write_all(response.as_bytes()).await.is_err()
+        {
+            return;
+        }
+    }
+}
+
+fn answer(state: &Mutex<State>, request: &Value) -> Value {
+    let id = request["id"].clone();
+    let method = request["method"].as_str().unwrap_or_default().to_string();
+    let params = request["params"].clone();
+
+    let mut state = state.lock().unwrap();
+    state.calls.push((method.clone(), params.clone()));
+    let result = match state.results.get(&method) {
+        Some(result) => Some(result.clone()),
+        None => default_result(&state, &method, &params),
+    };
+
+    match result {
+        Some(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        None => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": -32601, "message": format!("{} is not mocked", method)},
+        }),
+    }
+}
+
+fn default_result(state: &State, method: &str, params: &Value) -> Option<Value> {
+    let account = |key: &Value| {
+        key.as_str()
+            .and_then(|key| key.parse().ok())
+            .and_then(|key| state.accounts.get(&key))
+            .map_or(Value::Null, ui_account)
+    };
+    let result = match method {
+        "getAccountInfo" => json!({"context": {"slot": SLOT}, "value": account(&params[0])}),
+        "getMultipleAccounts" => json!({
+            "context": {"slot": SLOT},
+            "value": params[0]
+                .as_array()
+                .map(|keys| keys.iter().map(account).collect::<Vec<_>>())
+                .unwrap_or_default(),
+        }),
+        "getLatestBlockhash" => json!({
+            "context": {"slot": SLOT},
+            "value": {
+                "blockhash": Hash::new_from_array([1; 32]).to_string(),
+                "lastValidBlockHeight": SLOT + 150,
+            },
+        }),
+        "sendTransaction" => {
+            let transaction = decode_transaction(params).ok()?;
+            json!(transaction.signatures.first()?.to_string())
+        }
+        "simulateTransaction" => json!({
+            "context": {"slot": SLOT},
+            "value": {
+                "err": null,
+                "logs": [],
+                "accounts": null,
+                "unitsConsumed": 50_000,
+                "returnData": null,
+            },
+        }),
+        "getRecentPrioritizationFees" => json!([]),
+        "getSlot" | "getBlockHeight" => json!(SLOT),
+        "getVersion" => json!({"solana-core": "1.17.20", "feature-set": 0}),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn ui_account(account: &Account) -> Value {
+    json!({
+        "lamports": account.lamports,
+        "data": [STANDARD.encode(&account.data), "base64"],
+        "owner": account.owner.to_string(),
+        "executable": account.executable,
+        "rentEpoch": account.rent_epoch,
+        "space": account.data.len(),
+    })
+}
+
+/// The transaction in the params of a send or simulate call, base64 or base58
+/// as its config says.
+fn decode_transaction(params: &Value) -> eyre::Result<VersionedTransaction> {
+    let encoded = params[0]
+        .as_str()
+        .ok_or_else(|| eyre::eyre!("No transaction in {}", params))?;
+    let data = match params[1]["encoding"].as_str() {
+        Some("base64") => STANDARD.decode(encoded)?,
+        _ => bs58::decode(encoded).into_vec()?,
+    };
+    Ok(bincode::deserialize(&data)?)
+}
@@ -5,20 +5,19 @@ use crossterm::style::Stylize;
 use demand::{DemandOption, Select};
 use log::{error, info};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{native_token::lamports_to_sol, signature::Keypair};
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey, signature::Keypair};
 
 use crate::{
     app::{config_init::update_config_field, theme},
-    env::load_config,
+    env::{load_config, SettingsConfig},
     raydium_amm::{
-        subscribe::auto_sniper_stream,
         swap::{
             instructions::{unwrap_sol, wrap_sol},
             metadata::decode_metadata,
             raydium_amm_sniper::RAYDIUM_AMM_FEE_COLLECTOR,
         },
     },
-    router::{grpc_pair_sub, SniperRoute},
+    router::{multi_sniper_run, pair_sub, SniperRoute},
 };
 
 use super::{
@@ -30,6 +29,10 @@ pub async fn wrap_sol_call() -> eyre::Result<()> {
     let sol_amount = sol_amount("Wrap Amount: ").await;
     // let wallet = private_key_env().await?;
 
+    wrap_sol_run(sol_amount).await
+}
+
+pub async fn wrap_sol_run(sol_amount: u64) -> eyre::Result<()> {
     let args = match load_config().await {
         Ok(args) => args,
         Err(e) => {
@@ -73,6 +76,20 @@ pub async fn unwrap_sol_call() -> eyre::Result<()> {
     Ok(())
 }
 
+/// Snipes the next launches on every route enabled under `[sniper]`.
+pub async fn multi_route_sniper() -> eyre::Result<()> {
+    let args = load_config().await?;
+
+    for (key, _, settings) in args.sniper.routes() {
+        if settings.enabled {
+            info!("Watching {}", key);
+        }
+    }
+    info!("Listening for launches...");
+
+    multi_sniper_run(args, None).await
+}
+
 pub async fn automatic_snipe(manual_snipe: bool) -> eyre::Result<()> {
     let args = match load_config().await {
         Ok(args) => args,
@@ -82,14 +99,8 @@ pub async fn automatic_snipe(manual_snipe: bool) -> eyre::Result<()> {
         }
     };
 
-    if args.network.grpc_url.is_empty() {
-        let _ = auto_sniper_stream(manual_snipe).await?;
-        return Ok(());
-    }
     let sol_amount = sol_amount("Snipe Amount:").await;
 
-    update_config_field(|c| &mut c.trading.buy_amount, lamports_to_sol(sol_amount)).await?;
-
     let token;
 
     if manual_snipe {
@@ -124,7 +135,19 @@ pub async fn automatic_snipe(manual_snipe: bool) -> eyre::Result<()> {
         token = None;
     }
 
-    let _ = match grpc_pair_sub(
+    raydium_sniper_run(args, token, sol_amount).await
+}
+
+pub async fn raydium_sniper_run(
+    mut args: SettingsConfig,
+    token: Option<Pubkey>,
+    sol_amount: u64,
+) -> eyre::Result<()> {
+    let manual_snipe = token.is_some();
+    update_config_field(|c| &mut c.trading.buy_amount, lamports_to_sol(sol_amount)).await?;
+    args.trading.buy_amount = lamports_to_sol(sol_amount);
+
+    let _ = match pair_sub(
         args,
         manual_snipe,
         token,
@@ -1,12 +1,10 @@
 pub mod rand;
+pub mod slippage;
 pub mod terminal;
-pub mod transaction;
 pub mod transaction_history;
 
-use log::info;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::signature::Keypair;
-use std::{error::Error, sync::Arc};
+use log::{error, info};
+use std::error::Error;
 use tokio::sync::mpsc::Sender;
 
 pub async fn read_single_key(stop_tx: &mut tokio::sync::mpsc::Sender<()>) {
@@ -51,77 +49,48 @@ pub async fn read_single_key(stop_tx: &mut tokio::sync::mpsc::Sender<()>) {
 use console::{Key, Term};
 
 use crate::{
-    env::SettingsConfig,
-    raydium_amm::{
-        subscribe::PoolKeysSniper,
-        swap::{raydium_swap_in::sell_tokens, raydium_swap_out::raydium_txn_backrun},
-    },
+    positions::{self, rules::FULL_EXIT_BPS, venue::Venue},
+    raydium_amm::{subscribe::PoolKeysSniper, swap::instructions::SOLC_MINT},
 };
 
+/// Sells 100%, 75%, 50% or 25% of the tracked token on keys 1 to 4. Only the
+/// full sell stops the price logger.
 pub async fn read_single_key_impl(
-    rpc_client: &Arc<RpcClient>,
     stop_tx: &mut Sender<()>,
     pool_keys: PoolKeysSniper,
-    args: SettingsConfig,
-    wallet: &Arc<Keypair>,
 ) -> Result<(), Box<dyn Error + Send>> {
     let term = Term::stdout();
+    let mint = if pool_keys.base_mint == SOLC_MINT {
+        pool_keys.quote_mint
+    } else {
+        pool_keys.base_mint
+    };
 
     loop {
-        match term.read_key().unwrap() {
-            Key::Char('1') => {
-                let _ = stop_tx.send(()).await;
-                info!("Selling 100% of tokens");
-                let _ = match raydium_txn_backrun(rpc_client, wallet, pool_keys, 100).await {
-                    Ok(_) => {}
-                    Err(e) => {
-                        info!("Error: {}", e);
-                    }
-                };
-                return Ok(());
-            }
-            Key::Char('2') => {
-                let _ = stop_tx.send(()).await;
-                info!("Selling 75% of tokens");
-                let _ = match sell_tokens(pool_keys).await {
-                    Ok(_) => {}
-                    Err(e) => {
-                        info!("Error: {}", e);
-                    }
-                };
-                return Ok(());
-            }
-            Key::Char('3') => {
-                let _ = stop_tx.send(()).await;
-                info!("Selling 50% of tokens");
-                let _ = match sell_tokens(pool_keys).await {
-                    Ok(_) => {}
-                    Err(e) => {
-                        info!("Error: {}", e);
-                    }
-                };
-                return Ok(());
-            }
-            Key::Char('4') => {
-                let _ = stop_tx.send(()).await;
-                info!("Selling 25% of tokens");
-                let _ = match sell_tokens(pool_keys).await {
-                    Ok(_) => {}
-                    Err(e) => {
-                        info!("Error: {}", e);
-                    }
-                };
-                return Ok(());
-            }
-            Key::Escape => {
-                // Handle Escape key
-            }
-            Key::Enter => {
-                // Handle Enter key
-            }
-            _ => {
-                // Handle other keys
-            }
+        let bps = match term.read_key().unwrap() {
+            Key::Char('1') => FULL_EXIT_BPS,
+            Key::Char('2') => 7_500,
+            Key::Char('3') => 5_000,
+            Key::Char('4') => 2_500,
+            _ => continue,
+        };
+
+        if bps == FULL_EXIT_BPS {
+            let _ = stop_tx.send(()).await;
+        }
+        info!("Selling {}% of tokens", bps / 100);
+
+        let venue = positions::venue_of(&mint).unwrap_or(Venue::RaydiumAMM(Box::new(pool_keys.clone())));
+        // Raydium sells log their own report
+        let logged = matches!(venue, Venue::RaydiumAMM(_));
+        match positions::sell_fraction_on(venue, mint, bps).await {
+            Ok(report) if !logged => report.log(),
+            Ok(_) => {}
+            Err(e) => error!("Cannot sell {}: {}", mint, e),
+        }
+
+        if bps == FULL_EXIT_BPS {
+            return Ok(());
         }
     }
 }
@@ -0,0 +1,48 @@
+/// Basis points in 100%.
+pub const MAX_BPS: u64 = 10_000;
+
+/// Least output accepted for a quoted `quote`, `bps` below it.
+pub fn min_out(quote: u64, bps: u16) -> u64 {
+    let bps = (bps as u64).min(MAX_BPS);
+    (quote as u128 * (MAX_BPS - bps) as u128 / MAX_BPS as u128) as u64
+}
+
+/// Most input paid for a quoted `quote`, `bps` above it.
+pub fn max_in(quote: u64, bps: u16) -> u64 {
+    let bps = (bps as u64).min(MAX_BPS);
+    let max = (quote as u128 * (MAX_BPS + bps) as u128).div_ceil(MAX_BPS as u128);
+    max.min(u64::MAX as u128) as u64
+}
+
+/// Output of a constant product swap of `amount_in`, after a `fee_bps` fee on
+/// the input.
+pub fn constant_product_out(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    fee_bps: u64,
+) -> u64 {
+    let amount_in = amount_in as u128 * (MAX_BPS - fee_bps.min(MAX_BPS)) as u128 / MAX_BPS as u128;
+    let denominator = reserve_in as u128 + amount_in;
+    if denominator == 0 {
+        return 0;
+    }
+    (reserve_out as u128 * amount_in / denominator) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_and_quotes() {
+        assert_eq!(min_out(1_000_000, 500), 950_000);
+        assert_eq!(min_out(1_000_000, 20_000), 0);
+        assert_eq!(max_in(1_000_000, 500), 1_050_000);
+        assert_eq!(max_in(3, 1), 4);
+
+        // 25 bps fee, 1 in 1000 of the pool in
+        assert_eq!(constant_product_out(1_000_000, 2_000_000, 1_000, 25), 1_992);
+        assert_eq!(constant_product_out(0, 0, 0, 25), 0);
+    }
+}